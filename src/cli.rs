@@ -1,4 +1,13 @@
-use clap::{Parser, Subcommand};
+use crate::commands::wait::WaitCondition;
+use crate::config::Config;
+use crate::port::{BackendKind, IpFamily, NetnsScope, PortTarget, Target, TargetSpec};
+use crate::process::{Escalation, ProtectPattern, Protection, Signal};
+use crate::utils::parse_duration;
+use clap::parser::ValueSource;
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use colored::Colorize;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(
@@ -22,30 +31,244 @@ pub struct Cli {
 
     #[arg(short = 'v', long, global = true, help = "Enable verbose output")]
     pub verbose: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Show which processes would get which signals without killing anything"
+    )]
+    pub dry_run: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATTERN",
+        help = "Never kill processes matching PATTERN (name:GLOB, exe:GLOB, user:NAME or port:PORT[-END]); repeatable"
+    )]
+    pub protect: Vec<ProtectPattern>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Kill protected processes (init, sshd, systemd, kernel threads, kilar's own session and --protect matches)"
+    )]
+    pub allow_protected: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        ignore_case = true,
+        default_value_t = BackendKind::Auto,
+        help = "Port scanning backend"
+    )]
+    pub backend: BackendKind,
+
+    #[arg(
+        short = '4',
+        long = "ipv4",
+        global = true,
+        conflicts_with = "ipv6",
+        help = "Only IPv4 sockets"
+    )]
+    pub ipv4: bool,
+
+    #[arg(short = '6', long = "ipv6", global = true, help = "Only IPv6 sockets")]
+    pub ipv6: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "netns",
+        help = "Scan every network namespace (containers, ip netns)"
+    )]
+    pub all_namespaces: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Scan one network namespace (name in /run/netns or a PID inside it)"
+    )]
+    pub netns: Option<String>,
+
+    /// Settings from the user and project configuration files
+    #[arg(skip)]
+    pub config: Config,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
     #[command(about = "Check port usage status")]
     Check {
-        #[arg(help = "Port number to check")]
-        port: u16,
+        #[arg(
+            help = "Port to check (PORT, HOST:PORT, [IPV6]:PORT, a UNIX socket path or a registry name)"
+        )]
+        target: Target,
 
         #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
         protocol: String,
 
         #[arg(short, long, help = "Enable interactive mode with kill option")]
         interactive: bool,
+
+        #[arg(
+            long,
+            default_value = "listen",
+            help = "TCP states to include, comma-separated (e.g. established,time-wait) or all"
+        )]
+        state: String,
     },
 
-    #[command(about = "Kill process using specified port")]
+    #[command(about = "Kill processes using the specified ports")]
     Kill {
-        #[arg(help = "Port number used by the process to kill")]
-        port: u16,
+        #[arg(
+            required_unless_present_any = ["name", "cwd", "path", "range"],
+            help = "Ports, port ranges or sockets used by the processes to kill (PORT, HOST:PORT, [IPV6]:PORT, START-END, a UNIX socket path or a registry name)"
+        )]
+        targets: Vec<TargetSpec>,
 
-        #[arg(short, long, help = "Force kill without confirmation")]
+        #[arg(
+            short,
+            long,
+            visible_alias = "yes",
+            visible_short_alias = 'y',
+            help = "Kill without confirmation (required when stdin is not a terminal)"
+        )]
         force: bool,
 
+        #[arg(long, help = "Only kill processes whose name contains NAME")]
+        name: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Only kill processes running in DIR or below it"
+        )]
+        cwd: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Only kill processes whose executable is PATH or lies below it"
+        )]
+        path: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "START-END",
+            help = "Only kill processes listening on a port in this range (e.g., 3000-9000)"
+        )]
+        range: Option<String>,
+
+        #[arg(
+            long,
+            help = "Stop the owning systemd unit instead of killing its process"
+        )]
+        stop_unit: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "group",
+            help = "Kill the whole process tree of the listener, children first"
+        )]
+        tree: bool,
+
+        #[arg(
+            long,
+            value_name = "SCOPE",
+            num_args = 0..=1,
+            default_missing_value = "pgrp",
+            value_parser = ["pgrp", "session"],
+            help = "Kill the listener's process group, or its session with --group=session"
+        )]
+        group: Option<String>,
+
+        #[command(flatten)]
+        signals: SignalArgs,
+
+        #[arg(
+            long,
+            value_name = "DURATION",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "10s",
+            value_parser = parse_duration,
+            help = "Wait until the port is released, up to DURATION (default: 10s)"
+        )]
+        wait: Option<Duration>,
+
+        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
+        protocol: String,
+    },
+
+    #[command(about = "List local processes connected to a port")]
+    Clients {
+        #[arg(help = "Port the clients connect to (PORT, HOST:PORT or [IPV6]:PORT)")]
+        target: PortTarget,
+    },
+
+    #[command(about = "Find free ports")]
+    Free {
+        #[arg(
+            short = 'r',
+            long,
+            default_value = "3000-65535",
+            help = "Port range to search (e.g., 3000-3999)"
+        )]
+        range: String,
+
+        #[arg(
+            short = 'n',
+            long,
+            default_value = "1",
+            value_parser = clap::value_parser!(u16).range(1..),
+            help = "Number of free ports to find"
+        )]
+        count: u16,
+
+        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
+        protocol: String,
+    },
+
+    #[command(about = "Bind a port and keep it until released or the lease expires")]
+    Hold {
+        #[arg(
+            required_unless_present = "any",
+            conflicts_with = "any",
+            value_parser = clap::value_parser!(u16).range(1..),
+            help = "Port to hold"
+        )]
+        port: Option<u16>,
+
+        #[arg(
+            long,
+            value_name = "RANGE",
+            help = "Hold the first free port in a range (e.g., 3000-3999)"
+        )]
+        any: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = parse_duration,
+            help = "Release the port after this long (default: until released)"
+        )]
+        ttl: Option<Duration>,
+
+        #[arg(long, help = "Label shown for the lease in `kilar list`")]
+        label: Option<String>,
+
+        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
+        protocol: String,
+    },
+
+    #[command(about = "Release a port held by `kilar hold` or reserved by `kilar free`")]
+    Release {
+        #[arg(
+            value_parser = clap::value_parser!(u16).range(1..),
+            help = "Port to release"
+        )]
+        port: u16,
+
         #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
         protocol: String,
     },
@@ -58,6 +281,19 @@ pub enum Commands {
         #[arg(short, long, help = "Filter by process name")]
         filter: Option<String>,
 
+        #[arg(
+            long,
+            value_name = "DIR",
+            help = "Only show processes running in DIR or below it"
+        )]
+        cwd: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Only show processes whose executable is PATH or lies below it"
+        )]
+        path: Option<PathBuf>,
+
         #[arg(
             short,
             long,
@@ -66,7 +302,12 @@ pub enum Commands {
         )]
         sort: String,
 
-        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp/all)")]
+        #[arg(
+            short,
+            long,
+            default_value = "tcp",
+            help = "Protocol (tcp/udp/unix/all)"
+        )]
         protocol: String,
 
         #[arg(long, help = "View only (no kill feature)")]
@@ -74,12 +315,256 @@ pub enum Commands {
 
         #[arg(long, help = "Watch mode - continuously monitor port changes")]
         watch: bool,
+
+        #[arg(
+            long,
+            value_name = "DURATION",
+            default_value = "1s",
+            value_parser = parse_duration,
+            help = "How often watch mode refreshes"
+        )]
+        interval: Duration,
+
+        #[arg(
+            long,
+            default_value = "listen",
+            help = "TCP states to include, comma-separated (e.g. established,time-wait) or all"
+        )]
+        state: String,
+
+        #[arg(long, help = "Show the parent process chain of each listener")]
+        tree: bool,
+
+        #[command(flatten)]
+        signals: SignalArgs,
+    },
+
+    #[command(about = "Wait until ports are listening or free")]
+    Wait {
+        #[arg(
+            required = true,
+            help = "Ports or sockets to wait for (PORT, HOST:PORT, [IPV6]:PORT or a UNIX socket path)"
+        )]
+        targets: Vec<Target>,
+
+        #[arg(
+            long,
+            value_name = "CONDITION",
+            default_value = "listening",
+            help = "Wait until every target is listening or free"
+        )]
+        until: WaitCondition,
+
+        #[arg(
+            long,
+            value_name = "DURATION",
+            default_value = "30s",
+            value_parser = parse_duration,
+            help = "Give up after this long and exit with code 124"
+        )]
+        timeout: Duration,
+
+        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
+        protocol: String,
+    },
+
+    #[command(
+        about = "Check the ports the project in the current directory needs (package.json, compose files, .env, vite.config, config/puma.rb)"
+    )]
+    Project {
+        #[arg(long, help = "Kill the processes holding the project's ports")]
+        kill: bool,
+
+        #[arg(
+            short,
+            long,
+            visible_alias = "yes",
+            visible_short_alias = 'y',
+            requires = "kill",
+            help = "Kill without confirmation (required when stdin is not a terminal)"
+        )]
+        force: bool,
+
+        #[command(flatten)]
+        signals: SignalArgs,
+
+        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
+        protocol: String,
+    },
+
+    #[command(about = "Check the ports declared in the [registry] configuration section")]
+    Registry {
+        #[command(subcommand)]
+        action: RegistryAction,
+    },
+
+    #[command(about = "Inspect the configuration files")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
 }
 
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigAction {
+    #[command(about = "Print the effective configuration and where each value comes from")]
+    Show,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryAction {
+    #[command(about = "Report whether each registered port is free, owned or held by an impostor")]
+    Check {
+        #[arg(short, long, default_value = "tcp", help = "Protocol (tcp/udp)")]
+        protocol: String,
+    },
+}
+
+/// How processes are signalled when killed (`kill` and `list`)
+#[derive(Args, Debug, Clone, Default)]
+pub struct SignalArgs {
+    #[arg(
+        long,
+        value_name = "NAME|NUM",
+        help = "Signal to send first, e.g. INT or 2 (default: TERM)"
+    )]
+    pub signal: Option<Signal>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "How long to wait for exit before sending SIGKILL (default: 5s)"
+    )]
+    pub timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        conflicts_with_all = ["signal", "timeout"],
+        help = "Signals to send in turn until the process exits, e.g. INT:5s,TERM:10s,KILL"
+    )]
+    pub escalation: Option<Escalation>,
+}
+
+impl SignalArgs {
+    /// The escalation ladder selected by these options
+    pub fn escalation(&self) -> Escalation {
+        Escalation::from_options(self.signal, self.timeout, self.escalation.clone())
+    }
+}
+
 impl Cli {
-    pub fn parse_args() -> Self {
-        Self::parse()
+    /// Parse the command line, taking option defaults from the user and
+    /// project configuration files
+    pub fn parse_args() -> crate::Result<Self> {
+        Self::parse_with(Config::load(), std::env::args_os())
+    }
+
+    /// Parse `args` with option defaults from `config`.
+    ///
+    /// An invalid configuration is an error, except for `config show`, which
+    /// warns and shows the built-in defaults so that the files can be fixed.
+    fn parse_with<I, T>(config: crate::Result<Config>, args: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let args: Vec<std::ffi::OsString> = args.into_iter().map(Into::into).collect();
+        let (command, config) =
+            match config.and_then(|config| Ok((Self::configured_command(&config)?, config))) {
+                Ok(configured) => configured,
+                Err(e) => {
+                    let config_show =
+                        Self::command()
+                            .try_get_matches_from(&args)
+                            .is_ok_and(|matches| {
+                                matches.subcommand().is_some_and(|(name, sub)| {
+                                    name == "config" && sub.subcommand_name() == Some("show")
+                                })
+                            });
+                    if !config_show {
+                        return Err(e);
+                    }
+                    eprintln!("{} {}", "Warning:".yellow(), e);
+                    (Self::command(), Config::default())
+                }
+            };
+        let matches = command.get_matches_from(args);
+        let mut cli = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+        // --signal/--timeout on the command line replace a configured escalation
+        if let (
+            Commands::Kill { signals, .. }
+            | Commands::List { signals, .. }
+            | Commands::Project { signals, .. },
+            Some((_, sub)),
+        ) = (&mut cli.command, matches.subcommand())
+        {
+            let explicit = |id| sub.value_source(id) == Some(ValueSource::CommandLine);
+            if (explicit("signal") || explicit("timeout")) && !explicit("escalation") {
+                signals.escalation = None;
+            }
+        }
+
+        cli.config = config;
+        Ok(cli)
+    }
+
+    /// The command-line definition with option defaults taken from `config`,
+    /// accepting the names of its registry entries as `check`/`kill` targets
+    pub fn configured_command(config: &Config) -> crate::Result<clap::Command> {
+        let command = config.apply(Self::command())?;
+        let registry = config.port_registry();
+        if registry.is_empty() {
+            return Ok(command);
+        }
+        Ok(command.mut_subcommands(|sub| match sub.get_name() {
+            "check" => {
+                let registry = registry.clone();
+                sub.mut_arg("target", |arg| {
+                    arg.value_parser(move |s: &str| registry.resolve::<Target>(s))
+                })
+            }
+            "kill" => {
+                let registry = registry.clone();
+                sub.mut_arg("targets", |arg| {
+                    arg.value_parser(move |s: &str| registry.resolve::<TargetSpec>(s))
+                })
+            }
+            _ => sub,
+        }))
+    }
+
+    /// Address family selected with `-4` / `-6`
+    pub fn ip_family(&self) -> Option<IpFamily> {
+        if self.ipv4 {
+            Some(IpFamily::V4)
+        } else if self.ipv6 {
+            Some(IpFamily::V6)
+        } else {
+            None
+        }
+    }
+
+    /// Processes guarded by `--protect` and the configured protect patterns,
+    /// unless `--allow-protected` is given
+    pub fn protection(&self) -> Protection {
+        let mut patterns = self.config.protect_patterns();
+        patterns.extend(self.protect.iter().cloned());
+        Protection {
+            patterns,
+            allow: self.allow_protected,
+        }
+    }
+
+    /// Network namespaces selected with `--all-namespaces` / `--netns`
+    pub fn netns_scope(&self) -> crate::Result<NetnsScope> {
+        match &self.netns {
+            Some(value) => NetnsScope::resolve(value),
+            None if self.all_namespaces => Ok(NetnsScope::All),
+            None => Ok(NetnsScope::Host),
+        }
     }
 }
 
@@ -97,16 +582,19 @@ mod tests {
         assert!(!cli.quiet);
         assert!(!cli.json);
         assert!(!cli.verbose);
+        assert_eq!(cli.backend, BackendKind::Auto);
 
         match cli.command {
             Commands::Check {
-                port,
+                target,
                 protocol,
                 interactive,
+                state,
             } => {
-                assert_eq!(port, 3000);
+                assert_eq!(target, Target::from(3000));
                 assert_eq!(protocol, "tcp");
                 assert!(!interactive);
+                assert_eq!(state, "listen");
             }
             _ => panic!("Expected Check command"),
         }
@@ -143,11 +631,17 @@ mod tests {
 
             match cli.command {
                 Commands::Check {
-                    port,
+                    target,
                     protocol,
                     interactive,
+                    ..
                 } => {
-                    assert_eq!(port, expected_port, "Port mismatch for args: {:?}", args);
+                    assert_eq!(
+                        target.port_target().unwrap().port,
+                        expected_port,
+                        "Port mismatch for args: {:?}",
+                        args
+                    );
                     assert_eq!(
                         protocol, expected_protocol,
                         "Protocol mismatch for args: {:?}",
@@ -190,11 +684,17 @@ mod tests {
 
             match cli.command {
                 Commands::Kill {
-                    port,
+                    targets,
                     protocol,
                     force,
+                    ..
                 } => {
-                    assert_eq!(port, expected_port, "Port mismatch for args: {:?}", args);
+                    assert_eq!(
+                        targets,
+                        vec![TargetSpec::from(Target::from(expected_port))],
+                        "Port mismatch for args: {:?}",
+                        args
+                    );
                     assert_eq!(
                         protocol, expected_protocol,
                         "Protocol mismatch for args: {:?}",
@@ -220,13 +720,18 @@ mod tests {
                 protocol,
                 view_only,
                 watch,
+                state,
+                tree,
+                ..
             } => {
+                assert!(!tree);
                 assert_eq!(ports, None);
                 assert_eq!(filter, None);
                 assert_eq!(sort, "port");
                 assert_eq!(protocol, "tcp");
                 assert!(!view_only);
                 assert!(!watch);
+                assert_eq!(state, "listen");
             }
             _ => panic!("Expected List command"),
         }
@@ -258,6 +763,7 @@ mod tests {
                 protocol,
                 view_only,
                 watch,
+                ..
             } => {
                 assert_eq!(ports, Some("3000-4000".to_string()));
                 assert_eq!(filter, Some("node".to_string()));
@@ -320,6 +826,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_backend_flag() {
+        // --backend はグローバルフラグとしてどのサブコマンドでも受け付ける
+        let test_cases = vec![
+            (vec!["kilar", "check", "3000"], BackendKind::Auto),
+            (
+                vec!["kilar", "check", "3000", "--backend", "procfs"],
+                BackendKind::Procfs,
+            ),
+            (
+                vec!["kilar", "kill", "3000", "--backend", "lsof"],
+                BackendKind::Lsof,
+            ),
+            (
+                vec!["kilar", "list", "--backend", "NETSTAT"],
+                BackendKind::Netstat,
+            ),
+        ];
+
+        for (args, expected_backend) in test_cases {
+            let cli = Cli::try_parse_from(&args)
+                .unwrap_or_else(|_| panic!("Failed to parse: {:?}", args));
+            assert_eq!(
+                cli.backend, expected_backend,
+                "Backend mismatch for args: {:?}",
+                args
+            );
+        }
+    }
+
     #[test]
     fn test_port_range_validation() {
         // 有効なポート番号の範囲をテスト
@@ -334,9 +870,10 @@ mod tests {
             if let Ok(cli) = result {
                 match cli.command {
                     Commands::Check {
-                        port: parsed_port, ..
+                        target: parsed_target,
+                        ..
                     } => {
-                        assert_eq!(parsed_port, port);
+                        assert_eq!(parsed_target.port_target().unwrap().port, port);
                     }
                     _ => panic!("Expected Check command"),
                 }
@@ -344,6 +881,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_address_targets() {
+        // host:port形式のターゲット
+        let cli = Cli::try_parse_from(["kilar", "check", "127.0.0.1:3000"])
+            .expect("Failed to parse IPv4 target");
+        match cli.command {
+            Commands::Check { target, .. } => {
+                assert_eq!(target.to_string(), "127.0.0.1:3000");
+            }
+            _ => panic!("Expected Check command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "[::1]:8080", "--force"])
+            .expect("Failed to parse IPv6 target");
+        match cli.command {
+            Commands::Kill { targets, force, .. } => {
+                assert_eq!(targets[0].to_string(), "[::1]:8080");
+                let TargetSpec::Single(target) = &targets[0] else {
+                    panic!("Expected a single target");
+                };
+                assert_eq!(target.port_target().unwrap().family, Some(IpFamily::V6));
+                assert!(force);
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        for invalid in ["localhost:3000", "::1:3000", "127.0.0.1:"] {
+            let result = Cli::try_parse_from(["kilar", "check", invalid]);
+            assert!(result.is_err(), "Target '{}' should be invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn test_ip_family_flags() {
+        let cli = Cli::try_parse_from(["kilar", "list"]).unwrap();
+        assert_eq!(cli.ip_family(), None);
+
+        let cli = Cli::try_parse_from(["kilar", "list", "-4"]).unwrap();
+        assert_eq!(cli.ip_family(), Some(IpFamily::V4));
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "--ipv6"]).unwrap();
+        assert_eq!(cli.ip_family(), Some(IpFamily::V6));
+
+        // -4と-6は同時に指定できない
+        let result = Cli::try_parse_from(["kilar", "list", "-4", "-6"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_invalid_port_numbers() {
         // 無効なポート番号のテスト（u16の範囲外や文字列）
@@ -447,11 +1032,12 @@ mod tests {
 
         match cli.command {
             Commands::Check {
-                port,
+                target,
                 protocol,
                 interactive,
+                ..
             } => {
-                assert_eq!(port, 3000);
+                assert_eq!(target, Target::from(3000));
                 assert_eq!(protocol, "udp");
                 assert!(interactive);
             }
@@ -487,6 +1073,401 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_state_option() {
+        let cli = Cli::try_parse_from(["kilar", "list", "--state", "established,time-wait"])
+            .expect("Failed to parse --state");
+        match cli.command {
+            Commands::List { state, .. } => {
+                assert_eq!(state, "established,time-wait");
+            }
+            _ => panic!("Expected List command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "check", "3000", "--state", "all"])
+            .expect("Failed to parse --state on check");
+        match cli.command {
+            Commands::Check { state, .. } => {
+                assert_eq!(state, "all");
+            }
+            _ => panic!("Expected Check command"),
+        }
+    }
+
+    #[test]
+    fn test_unix_socket_targets() {
+        let cli = Cli::try_parse_from(["kilar", "check", "/run/app.sock"])
+            .expect("Failed to parse socket path");
+        match cli.command {
+            Commands::Check { target, .. } => {
+                assert_eq!(target, Target::Unix("/run/app.sock".to_string()));
+            }
+            _ => panic!("Expected Check command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "@app-abstract", "-f"])
+            .expect("Failed to parse abstract socket");
+        match cli.command {
+            Commands::Kill { targets, .. } => assert!(targets[0].is_unix()),
+            _ => panic!("Expected Kill command"),
+        }
+    }
+
+    #[test]
+    fn test_clients_command_parsing() {
+        let cli = Cli::try_parse_from(["kilar", "clients", "127.0.0.1:5432", "-j"])
+            .expect("Failed to parse clients command");
+        assert!(cli.json);
+        match cli.command {
+            Commands::Clients { target } => {
+                assert_eq!(target.to_string(), "127.0.0.1:5432");
+            }
+            _ => panic!("Expected Clients command"),
+        }
+    }
+
+    #[test]
+    fn test_kill_tree_and_group_options() {
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "--tree"]).unwrap();
+        match cli.command {
+            Commands::Kill { tree, group, .. } => {
+                assert!(tree);
+                assert_eq!(group, None);
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        // 値なしの--groupはプロセスグループ
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "--group"]).unwrap();
+        match cli.command {
+            Commands::Kill { group, .. } => assert_eq!(group.as_deref(), Some("pgrp")),
+            _ => panic!("Expected Kill command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "--group=session"]).unwrap();
+        match cli.command {
+            Commands::Kill { group, .. } => assert_eq!(group.as_deref(), Some("session")),
+            _ => panic!("Expected Kill command"),
+        }
+
+        assert!(Cli::try_parse_from(["kilar", "kill", "3000", "--group=world"]).is_err());
+        assert!(Cli::try_parse_from(["kilar", "kill", "3000", "--tree", "--group"]).is_err());
+    }
+
+    #[test]
+    fn test_signal_options() {
+        let cli = Cli::try_parse_from([
+            "kilar",
+            "kill",
+            "3000",
+            "--signal",
+            "INT",
+            "--timeout",
+            "10s",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Kill { signals, .. } => {
+                assert_eq!(signals.escalation().to_string(), "INT:10s,KILL");
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["kilar", "list", "--escalation", "INT:5s,TERM:10s,KILL"]).unwrap();
+        match cli.command {
+            Commands::List { signals, .. } => {
+                assert_eq!(signals.escalation().steps.len(), 3);
+            }
+            _ => panic!("Expected List command"),
+        }
+
+        // 指定なしはSIGTERMの後に5秒でSIGKILL
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000"]).unwrap();
+        match cli.command {
+            Commands::Kill { signals, .. } => {
+                assert_eq!(signals.escalation(), Escalation::default());
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        assert!(Cli::try_parse_from(["kilar", "kill", "3000", "--signal", "NOPE"]).is_err());
+        assert!(Cli::try_parse_from(["kilar", "kill", "3000", "--timeout", "5x"]).is_err());
+        assert!(Cli::try_parse_from([
+            "kilar",
+            "kill",
+            "3000",
+            "--signal",
+            "INT",
+            "--escalation",
+            "INT:5s,KILL",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_wait_option() {
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "--wait"]).unwrap();
+        match cli.command {
+            Commands::Kill { wait, .. } => assert_eq!(wait, Some(Duration::from_secs(10))),
+            _ => panic!("Expected Kill command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "--wait=30s"]).unwrap();
+        match cli.command {
+            Commands::Kill { wait, .. } => assert_eq!(wait, Some(Duration::from_secs(30))),
+            _ => panic!("Expected Kill command"),
+        }
+
+        // 値は--wait=の形でのみ受け付ける（ポート番号を待ち時間と誤解しない）
+        let cli = Cli::try_parse_from(["kilar", "kill", "--wait", "3000"]).unwrap();
+        match cli.command {
+            Commands::Kill { targets, wait, .. } => {
+                assert_eq!(targets, vec![TargetSpec::from(Target::from(3000))]);
+                assert_eq!(wait, Some(Duration::from_secs(10)));
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000"]).unwrap();
+        match cli.command {
+            Commands::Kill { wait, .. } => assert_eq!(wait, None),
+            _ => panic!("Expected Kill command"),
+        }
+    }
+
+    #[test]
+    fn test_wait_command_parsing() {
+        let cli = Cli::try_parse_from([
+            "kilar",
+            "wait",
+            "3000",
+            "127.0.0.1:8080",
+            "--until",
+            "free",
+            "--timeout",
+            "2m",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Wait {
+                targets,
+                until,
+                timeout,
+                protocol,
+            } => {
+                assert_eq!(targets.len(), 2);
+                assert_eq!(targets[0], Target::from(3000));
+                assert_eq!(until, WaitCondition::Free);
+                assert_eq!(timeout, Duration::from_secs(120));
+                assert_eq!(protocol, "tcp");
+            }
+            _ => panic!("Expected Wait command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "wait", "3000"]).unwrap();
+        match cli.command {
+            Commands::Wait { until, timeout, .. } => {
+                assert_eq!(until, WaitCondition::Listening);
+                assert_eq!(timeout, Duration::from_secs(30));
+            }
+            _ => panic!("Expected Wait command"),
+        }
+
+        assert!(Cli::try_parse_from(["kilar", "wait"]).is_err());
+        assert!(Cli::try_parse_from(["kilar", "wait", "3000", "--until", "up"]).is_err());
+    }
+
+    #[test]
+    fn test_free_command_parsing() {
+        let cli =
+            Cli::try_parse_from(["kilar", "free", "--range", "3000-3999", "-n", "3"]).unwrap();
+        match cli.command {
+            Commands::Free {
+                range,
+                count,
+                protocol,
+            } => {
+                assert_eq!(range, "3000-3999");
+                assert_eq!(count, 3);
+                assert_eq!(protocol, "tcp");
+            }
+            _ => panic!("Expected Free command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "free"]).unwrap();
+        match cli.command {
+            Commands::Free { range, count, .. } => {
+                assert_eq!(range, "3000-65535");
+                assert_eq!(count, 1);
+            }
+            _ => panic!("Expected Free command"),
+        }
+
+        assert!(Cli::try_parse_from(["kilar", "free", "--count", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_hold_command_parsing() {
+        let cli = Cli::try_parse_from([
+            "kilar",
+            "hold",
+            "--any",
+            "3000-3999",
+            "--ttl",
+            "10m",
+            "--label",
+            "shard-1",
+        ])
+        .unwrap();
+        match cli.command {
+            Commands::Hold {
+                port,
+                any,
+                ttl,
+                label,
+                protocol,
+            } => {
+                assert_eq!(port, None);
+                assert_eq!(any.as_deref(), Some("3000-3999"));
+                assert_eq!(ttl, Some(Duration::from_secs(600)));
+                assert_eq!(label.as_deref(), Some("shard-1"));
+                assert_eq!(protocol, "tcp");
+            }
+            _ => panic!("Expected Hold command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "hold", "3000"]).unwrap();
+        match cli.command {
+            Commands::Hold { port, ttl, .. } => {
+                assert_eq!(port, Some(3000));
+                assert_eq!(ttl, None);
+            }
+            _ => panic!("Expected Hold command"),
+        }
+
+        // ポートか--anyのどちらか一方が必要
+        assert!(Cli::try_parse_from(["kilar", "hold"]).is_err());
+        assert!(Cli::try_parse_from(["kilar", "hold", "3000", "--any", "3000-3999"]).is_err());
+
+        let cli = Cli::try_parse_from(["kilar", "release", "3000", "-p", "udp"]).unwrap();
+        match cli.command {
+            Commands::Release { port, protocol } => {
+                assert_eq!(port, 3000);
+                assert_eq!(protocol, "udp");
+            }
+            _ => panic!("Expected Release command"),
+        }
+    }
+
+    #[test]
+    fn test_kill_multiple_targets_parsing() {
+        let cli = Cli::try_parse_from(["kilar", "kill", "3000", "5173", "8000-8010", ":9229"])
+            .expect("Failed to parse multiple kill targets");
+        match cli.command {
+            Commands::Kill { targets, .. } => {
+                let targets: Vec<String> = targets.iter().map(ToString::to_string).collect();
+                assert_eq!(targets, ["3000", "5173", "8000-8010", "9229"]);
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        assert!(Cli::try_parse_from(["kilar", "kill"]).is_err());
+        assert!(Cli::try_parse_from(["kilar", "kill", "8010-8000"]).is_err());
+    }
+
+    #[test]
+    fn test_kill_filter_parsing() {
+        let cli = Cli::try_parse_from([
+            "kilar",
+            "kill",
+            "--name",
+            "node",
+            "--cwd",
+            "/srv/shop",
+            "--range",
+            "3000-9000",
+            "--yes",
+        ])
+        .expect("Failed to parse kill filters");
+        match cli.command {
+            Commands::Kill {
+                targets,
+                name,
+                cwd,
+                range,
+                force,
+                ..
+            } => {
+                assert!(targets.is_empty());
+                assert_eq!(name.as_deref(), Some("node"));
+                assert_eq!(cwd, Some(PathBuf::from("/srv/shop")));
+                assert_eq!(range.as_deref(), Some("3000-9000"));
+                assert!(force);
+            }
+            _ => panic!("Expected Kill command"),
+        }
+
+        let cli = Cli::try_parse_from(["kilar", "kill", "-y", "--path", "/usr/bin/node"]).unwrap();
+        assert!(matches!(cli.command, Commands::Kill { force: true, .. }));
+    }
+
+    #[test]
+    fn test_protect_options() {
+        let cli = Cli::try_parse_from([
+            "kilar",
+            "kill",
+            "22",
+            "--protect",
+            "user:postgres",
+            "--protect",
+            "port:5400-5499",
+        ])
+        .expect("Failed to parse --protect");
+        let protection = cli.protection();
+        assert_eq!(protection.patterns.len(), 2);
+        assert_eq!(protection.patterns[0].to_string(), "user:postgres");
+        assert!(!protection.allow);
+
+        let cli = Cli::try_parse_from(["kilar", "list", "--allow-protected"]).unwrap();
+        assert!(cli.protection().allow);
+
+        assert!(Cli::try_parse_from(["kilar", "kill", "22", "--protect", "pid:1"]).is_err());
+    }
+
+    #[test]
+    fn test_stop_unit_option() {
+        let cli = Cli::try_parse_from(["kilar", "kill", "8080", "--stop-unit"])
+            .expect("Failed to parse --stop-unit");
+        match cli.command {
+            Commands::Kill {
+                stop_unit, force, ..
+            } => {
+                assert!(stop_unit);
+                assert!(!force);
+            }
+            _ => panic!("Expected Kill command"),
+        }
+    }
+
+    #[test]
+    fn test_netns_options() {
+        let cli = Cli::try_parse_from(["kilar", "list", "--all-namespaces"])
+            .expect("Failed to parse --all-namespaces");
+        assert!(cli.all_namespaces);
+        assert_eq!(cli.netns_scope().unwrap(), NetnsScope::All);
+
+        let cli = Cli::try_parse_from(["kilar", "check", "3000"]).unwrap();
+        assert_eq!(cli.netns_scope().unwrap(), NetnsScope::Host);
+
+        let cli = Cli::try_parse_from(["kilar", "check", "3000", "--netns", "blue"]).unwrap();
+        assert_eq!(cli.netns.as_deref(), Some("blue"));
+
+        // 両方を同時に指定することはできない
+        let result = Cli::try_parse_from(["kilar", "list", "--all-namespaces", "--netns", "blue"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_cli_parse_args_method() {
         // parse_args メソッドのテスト（実際のコマンドライン引数をテストできないため、構造テスト）
@@ -494,7 +1475,27 @@ mod tests {
         // しかし、メソッドが存在することは確認できる
 
         // メソッドが存在し、正しいシグネチャを持つことを確認
-        fn _test_parse_args_signature(_: fn() -> Cli) {}
+        fn _test_parse_args_signature(_: fn() -> crate::Result<Cli>) {}
         _test_parse_args_signature(Cli::parse_args);
     }
+
+    #[test]
+    fn test_invalid_config_only_warns_for_config_show() {
+        let invalid = || {
+            Err(crate::Error::ParseError(
+                "/work/.kilar.toml: bad".to_string(),
+            ))
+        };
+
+        let cli = Cli::parse_with(invalid(), ["kilar", "config", "show"]).unwrap();
+        assert!(cli.config.files.is_empty());
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                action: ConfigAction::Show
+            }
+        ));
+
+        assert!(Cli::parse_with(invalid(), ["kilar", "list"]).is_err());
+    }
 }
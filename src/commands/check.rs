@@ -1,4 +1,12 @@
-use crate::{port::PortManager, process::ProcessManager, Result};
+use crate::{
+    commands::{kill::KillScope, KillCommand, ListCommand},
+    port::{
+        create_backend, free::probe_bind, BackendKind, FreePortFinder, LeaseRegistry, PortBackend,
+        PortTarget, ProcessInfo, Target, TcpState,
+    },
+    process::{Escalation, Protection, SystemctlStopper},
+    Result,
+};
 use colored::Colorize;
 use dialoguer::Confirm;
 
@@ -43,102 +51,260 @@ impl CheckCommand {
         verbose: bool,
         interactive: bool,
     ) -> Result<()> {
-        let port_manager = PortManager::new();
+        let backend = create_backend(BackendKind::default());
+        Self::execute_with_backend(
+            backend.as_ref(),
+            &Target::Port(PortTarget::new(port)),
+            protocol,
+            &[TcpState::Listen],
+            quiet,
+            json,
+            verbose,
+            interactive,
+            false,
+            &Protection::default(),
+        )
+        .await
+    }
+
+    /// Execute the check command using the given port backend.
+    ///
+    /// `target` is a port, optionally narrowed to one bound address or address
+    /// family, or a UNIX socket path (checked with protocol `unix`).
+    /// `states` selects which TCP states count as using the port. With
+    /// `dry_run`, the interactive kill only reports what it would do, and it
+    /// refuses the processes guarded by `protection`.
+    /// See [`CheckCommand::execute`] for a description of the remaining arguments.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn execute_with_backend(
+        backend: &dyn PortBackend,
+        target: &Target,
+        protocol: &str,
+        states: &[TcpState],
+        quiet: bool,
+        json: bool,
+        verbose: bool,
+        interactive: bool,
+        dry_run: bool,
+        protection: &Protection,
+    ) -> Result<()> {
+        let listen_only = TcpState::is_listen_only(states);
+
+        match Self::find_holders(backend, target, protocol, states).await {
+            Ok(holders) if !holders.is_empty() => {
+                // Listeners are shown once per process; other states are shown
+                // per socket, since one process can hold many connections
+                let mut holders = if listen_only {
+                    KillCommand::unique_holders(holders)
+                } else {
+                    holders
+                };
+                crate::process::tree::attach_ancestors(&mut holders);
+                let process_info = &holders[0];
+                // 表示も出力もしない場合は空きポートを探さない
+                let suggested_port = if listen_only && (json || !quiet) {
+                    Self::suggest_port(backend, target, protocol).await
+                } else {
+                    None
+                };
 
-        match port_manager.check_port(port, protocol).await {
-            Ok(Some(process_info)) => {
                 if json {
-                    let json_output = serde_json::json!({
-                        "port": port,
-                        "protocol": protocol,
-                        "status": "occupied",
-                        "process": {
-                            "pid": process_info.pid,
-                            "name": process_info.name,
-                            "executable_path": process_info.executable_path,
-                            "working_directory": process_info.working_directory,
-                            "command": process_info.command
-                        }
-                    });
+                    let processes: Vec<serde_json::Value> =
+                        holders.iter().map(Self::process_json).collect();
+                    let mut json_output = Self::target_json(target, protocol);
+                    json_output["status"] = "occupied".into();
+                    json_output["process"] = Self::process_json(process_info);
+                    json_output["processes"] = processes.into();
+                    if let Some(port) = suggested_port {
+                        json_output["suggested_port"] = port.into();
+                    }
                     println!("{}", serde_json::to_string_pretty(&json_output)?);
                 } else if !quiet {
-                    println!(
-                        "{} {}:{} is in use",
-                        "✓".green(),
-                        protocol.to_uppercase().blue(),
-                        port.to_string().yellow()
-                    );
-                    println!("  {} {}", "PID:".cyan(), process_info.pid);
-                    println!("  {} {}", "Process:".cyan(), process_info.name);
-
-                    // Use smart path display logic
-                    let display_path = port_manager.get_display_path(&process_info);
-                    println!("  {} {}", "Path:".cyan(), display_path);
-                    if verbose {
-                        println!("  {} {}", "Command:".cyan(), process_info.command);
+                    if holders.len() == 1 {
+                        println!(
+                            "{} {}:{} is in use",
+                            "✓".green(),
+                            protocol.to_uppercase().blue(),
+                            target.to_string().yellow()
+                        );
+                    } else {
+                        println!(
+                            "{} {}:{} is in use by {} {}",
+                            "✓".green(),
+                            protocol.to_uppercase().blue(),
+                            target.to_string().yellow(),
+                            holders.len(),
+                            if listen_only { "processes" } else { "sockets" }
+                        );
+                    }
+
+                    for (i, holder) in holders.iter().enumerate() {
+                        if i > 0 {
+                            println!();
+                        }
+                        if let Some(state) = holder.state.filter(|_| verbose || !listen_only) {
+                            println!("  {} {}", "State:".cyan(), state);
+                        }
+                        if let Some(netns) = holder.netns {
+                            println!("  {} net:[{}]", "Netns:".cyan(), netns);
+                        }
+                        if holder.pid == 0 {
+                            // TIME_WAIT and friends are owned by the kernel
+                            println!("  {} - (no owning process)", "PID:".cyan());
+                            continue;
+                        }
+                        println!("  {} {}", "PID:".cyan(), holder.pid);
+                        println!("  {} {}", "Process:".cyan(), holder.name);
+                        if let Some(ancestors) = holder.ancestors.as_ref().filter(|a| !a.is_empty())
+                        {
+                            println!(
+                                "  {} {}",
+                                "Tree:".cyan(),
+                                ListCommand::format_ancestry(holder, ancestors)
+                            );
+                        }
+
+                        if let Some(unit) = &holder.unit {
+                            println!("  {} {}", "Unit:".cyan(), unit);
+                        }
+                        if let Some(container) = &holder.container {
+                            println!("  {} {}", "Container:".cyan(), container);
+                            if verbose {
+                                if let Some(image) = &container.image {
+                                    println!("  {} {}", "Image:".cyan(), image);
+                                }
+                                if let Some(forwarded_to) = &container.forwarded_to {
+                                    println!("  {} {}", "Forwards to:".cyan(), forwarded_to);
+                                }
+                            }
+                        } else {
+                            // Use smart path display logic
+                            let display_path = backend.get_display_path(holder);
+                            println!("  {} {}", "Path:".cyan(), display_path);
+                        }
+                        if verbose {
+                            if let Some(fd) = holder.fd {
+                                println!("  {} {}", "FD:".cyan(), fd);
+                            }
+                            println!("  {} {}", "Command:".cyan(), holder.command);
+                        }
+                    }
+
+                    if let Some(port) = suggested_port {
+                        println!();
+                        println!(
+                            "{} Nearest free port: {}",
+                            "→".cyan(),
+                            port.to_string().green()
+                        );
                     }
 
                     // Interactive kill option
                     if interactive && !json {
                         println!();
-                        let prompt = format!(
-                            "Kill process {} (PID: {})?",
-                            process_info.name.yellow(),
-                            process_info.pid.to_string().cyan()
+                        let holders = KillCommand::unique_holders(
+                            holders.into_iter().filter(|p| p.pid != 0).collect(),
                         );
+                        let Some(process_info) = holders.first() else {
+                            println!(
+                                "{} No process owns these sockets; nothing to kill",
+                                "×".yellow()
+                            );
+                            return Ok(());
+                        };
 
-                        let confirmed = Confirm::new()
-                            .with_prompt(prompt)
-                            .default(false)
-                            .interact()?;
-
-                        if confirmed {
-                            let process_manager = ProcessManager::new();
-                            match process_manager.kill_process(process_info.pid).await {
-                                Ok(()) => {
-                                    println!(
-                                        "{} Killed process {} (PID: {})",
-                                        "✓".green(),
-                                        process_info.name.yellow(),
-                                        process_info.pid.to_string().cyan()
-                                    );
-                                }
-                                Err(e) => {
-                                    eprintln!("{} Failed to kill process: {}", "×".red(), e);
-                                    return Err(e);
-                                }
+                        let targets = if holders.len() == 1 {
+                            let prompt = format!(
+                                "Kill process {} (PID: {})?",
+                                process_info.name.yellow(),
+                                process_info.pid.to_string().cyan()
+                            );
+
+                            let confirmed = Confirm::new()
+                                .with_prompt(prompt)
+                                .default(false)
+                                .interact()?;
+
+                            if !confirmed {
+                                println!("{} Operation cancelled", "×".yellow());
+                                return Ok(());
                             }
+                            holders
                         } else {
-                            println!("{} Operation cancelled", "×".yellow());
+                            match KillCommand::select_holders(&holders, quiet)? {
+                                Some(selected) => selected,
+                                None => return Ok(()),
+                            }
+                        };
+
+                        let stop_units = KillCommand::confirm_stop_units(&targets)?;
+                        if dry_run {
+                            let plans = KillCommand::plan_holders(
+                                &targets,
+                                stop_units,
+                                KillScope::Process,
+                                protection,
+                            );
+                            KillCommand::print_plan(
+                                &plans,
+                                KillScope::Process,
+                                &Escalation::default(),
+                            );
+                            if let Some(e) = plans.into_iter().find_map(|(_, plan)| plan.err()) {
+                                return Err(e);
+                            }
+                            return Ok(());
+                        }
+                        let results = KillCommand::kill_holders(
+                            &targets,
+                            &SystemctlStopper,
+                            stop_units,
+                            KillScope::Process,
+                            &Escalation::default(),
+                            protection,
+                            quiet,
+                            json,
+                        )
+                        .await;
+                        if let Some(e) = results.into_iter().find_map(|(_, r)| r.err()) {
+                            return Err(e);
                         }
                     }
                 }
             }
-            Ok(None) => {
+            Ok(_) if Self::is_stale_socket(target) => {
                 if json {
-                    let json_output = serde_json::json!({
-                        "port": port,
-                        "protocol": protocol,
-                        "status": "available"
-                    });
+                    let mut json_output = Self::target_json(target, protocol);
+                    json_output["status"] = "stale".into();
+                    println!("{}", serde_json::to_string_pretty(&json_output)?);
+                } else if !quiet {
+                    println!(
+                        "{} {}:{} is a stale socket file (no process is listening)",
+                        "!".yellow(),
+                        protocol.to_uppercase().blue(),
+                        target.to_string().yellow()
+                    );
+                }
+            }
+            Ok(_) => {
+                if json {
+                    let mut json_output = Self::target_json(target, protocol);
+                    json_output["status"] = "available".into();
                     println!("{}", serde_json::to_string_pretty(&json_output)?);
                 } else if !quiet {
                     println!(
                         "{} {}:{} is available",
                         "○".blue(),
                         protocol.to_uppercase().blue(),
-                        port.to_string().yellow()
+                        target.to_string().yellow()
                     );
                 }
             }
             Err(e) => {
                 if json {
-                    let json_output = serde_json::json!({
-                        "port": port,
-                        "protocol": protocol,
-                        "status": "error",
-                        "error": e.to_string()
-                    });
+                    let mut json_output = Self::target_json(target, protocol);
+                    json_output["status"] = "error".into();
+                    json_output["error"] = e.to_string().into();
                     println!("{}", serde_json::to_string_pretty(&json_output)?);
                 } else {
                     eprintln!("{} {}", "Error:".red(), e);
@@ -149,12 +315,127 @@ impl CheckCommand {
 
         Ok(())
     }
+
+    /// Every holder of the port whose socket matches `target` and is in one of `states`
+    pub(crate) async fn find_holders(
+        backend: &dyn PortBackend,
+        target: &Target,
+        protocol: &str,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
+        let mut holders = Self::scan_holders(backend, target, protocol, states).await?;
+        crate::port::attach_containers(&mut holders);
+        crate::process::systemd::attach_units(&mut holders).await;
+        crate::process::tree::attach_start_times(&mut holders);
+        Ok(holders)
+    }
+
+    /// Like [`Self::find_holders`], without looking up containers and units
+    pub(crate) async fn scan_holders(
+        backend: &dyn PortBackend,
+        target: &Target,
+        protocol: &str,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
+        let mut holders = match target {
+            Target::Port(port_target) if TcpState::is_listen_only(states) => {
+                backend.check_port_all(port_target.port, protocol).await?
+            }
+            Target::Port(_) => backend.list_sockets(protocol, states).await?,
+            Target::Unix(_) => backend.list_processes("unix").await?,
+        };
+        holders.retain(|p| target.matches(p));
+        Ok(holders)
+    }
+
+    /// The free port nearest to an occupied port, suggested in place of it.
+    ///
+    /// Candidates are judged by binding them; the listener table is only
+    /// read when a bind is inconclusive, e.g. for a privileged port.
+    async fn suggest_port(
+        backend: &dyn PortBackend,
+        target: &Target,
+        protocol: &str,
+    ) -> Option<u16> {
+        let port_target = target.port_target()?;
+        if !matches!(protocol, "tcp" | "udp") {
+            return None;
+        }
+        let leases = LeaseRegistry::default().active(protocol);
+        let unlisted = FreePortFinder::new(protocol, &[]).with_leases(&leases);
+        let mut listed: Option<FreePortFinder> = None;
+        for candidate in FreePortFinder::around(port_target.port) {
+            if !unlisted.is_unclaimed(candidate) {
+                continue;
+            }
+            match probe_bind(candidate, protocol) {
+                Some(true) => return Some(candidate),
+                Some(false) => continue,
+                None => {}
+            }
+            let finder = match listed.as_ref() {
+                Some(finder) => finder,
+                None => listed.insert(
+                    FreePortFinder::scan(backend, protocol)
+                        .await
+                        .ok()?
+                        .with_leases(&leases),
+                ),
+            };
+            if finder.is_unclaimed(candidate) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+
+    /// Whether `target` is a socket file that no process listens on
+    pub(crate) fn is_stale_socket(target: &Target) -> bool {
+        use std::os::unix::fs::FileTypeExt;
+
+        match target {
+            Target::Unix(path) if !path.starts_with('@') => std::fs::symlink_metadata(path)
+                .map(|meta| meta.file_type().is_socket())
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// JSON fields identifying `target`
+    pub(crate) fn target_json(target: &Target, protocol: &str) -> serde_json::Value {
+        match target {
+            Target::Port(port_target) => serde_json::json!({
+                "port": port_target.port,
+                "address": port_target.address,
+                "protocol": protocol
+            }),
+            Target::Unix(path) => serde_json::json!({
+                "path": path,
+                "protocol": "unix"
+            }),
+        }
+    }
+
+    fn process_json(process_info: &ProcessInfo) -> serde_json::Value {
+        serde_json::json!({
+            "pid": process_info.pid,
+            "name": process_info.name,
+            "executable_path": process_info.executable_path,
+            "working_directory": process_info.working_directory,
+            "command": process_info.command,
+            "fd": process_info.fd,
+            "state": process_info.state,
+            "netns": process_info.netns,
+            "container": process_info.container,
+            "unit": process_info.unit,
+            "ancestors": process_info.ancestors
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::port::ProcessInfo;
 
     // Mockポートマネージャーを作成するためのヘルパー関数
     fn create_mock_process_info(port: u16) -> ProcessInfo {
@@ -168,6 +449,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "127.0.0.1".to_string(),
             inode: Some(12345),
+            ..Default::default()
         }
     }
 
@@ -322,6 +604,17 @@ mod tests {
         assert_eq!(json_output["status"].as_str().unwrap(), "occupied");
     }
 
+    #[test]
+    fn test_process_json_includes_fd() {
+        let mut process_info = create_mock_process_info(8080);
+        process_info.fd = Some(7);
+
+        let json_output = CheckCommand::process_json(&process_info);
+        assert_eq!(json_output["pid"].as_u64().unwrap(), 1234);
+        assert_eq!(json_output["fd"].as_u64().unwrap(), 7);
+        assert_eq!(json_output["name"].as_str().unwrap(), "test_process");
+    }
+
     #[test]
     fn test_available_port_json_structure() {
         // 利用可能ポートのJSON出力構造をテスト
@@ -352,4 +645,34 @@ mod tests {
         assert_eq!(json_output["status"].as_str().unwrap(), "error");
         assert_eq!(json_output["error"].as_str().unwrap(), error_msg);
     }
+
+    struct ScanCountingBackend(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl PortBackend for ScanCountingBackend {
+        fn name(&self) -> &'static str {
+            "ScanCountingBackend"
+        }
+
+        async fn list_processes(&self, _protocol: &str) -> Result<Vec<ProcessInfo>> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suggest_port_probes_before_scanning() {
+        // bindで判定できる限りリスナー表は読まない
+        let listener = std::net::TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let backend = ScanCountingBackend(std::sync::atomic::AtomicUsize::new(0));
+        let target = Target::Port(PortTarget::new(port));
+
+        let suggested = CheckCommand::suggest_port(&backend, &target, "tcp")
+            .await
+            .unwrap();
+        assert_ne!(suggested, port);
+        assert!(suggested >= 1024);
+        assert_eq!(backend.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
 }
@@ -0,0 +1,225 @@
+use crate::{
+    port::{procfs::ProcfsPortManager, PortTarget, ProcessInfo},
+    Result,
+};
+use colored::Colorize;
+
+/// Command for finding local processes connected to a port.
+///
+/// This is the reverse of [`CheckCommand`](crate::commands::CheckCommand):
+/// instead of the process listening on a port, it lists every local
+/// process holding an established connection to it (Linux only).
+///
+/// # Example
+///
+/// ```no_run
+/// use kilar::commands::ClientsCommand;
+/// use kilar::port::PortTarget;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     // Who is connected to the local Postgres?
+///     let target = "127.0.0.1:5432".parse::<PortTarget>().unwrap();
+///     ClientsCommand::execute(&target, false, false, false).await.unwrap();
+/// }
+/// ```
+pub struct ClientsCommand;
+
+/// Connections held by one client process
+#[derive(Debug, Clone)]
+pub struct ClientProcess {
+    pub process: ProcessInfo,
+    pub connections: Vec<ProcessInfo>,
+}
+
+impl ClientsCommand {
+    /// Execute the clients command for `target`.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The remote port (optionally narrowed to one address) to look for
+    /// * `quiet` - Suppress output if true
+    /// * `json` - Output in JSON format if true
+    /// * `verbose` - List every connection if true
+    pub async fn execute(
+        target: &PortTarget,
+        quiet: bool,
+        json: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        let manager = ProcfsPortManager::new();
+        let mut connections = manager.list_clients(target).await?;
+        crate::port::attach_containers(&mut connections);
+        let clients = Self::group_by_process(connections);
+        let total: usize = clients.iter().map(|c| c.connections.len()).sum();
+
+        if json {
+            let clients_json: Vec<serde_json::Value> = clients
+                .iter()
+                .map(|client| {
+                    serde_json::json!({
+                        "pid": client.process.pid,
+                        "name": client.process.name,
+                        "executable_path": client.process.executable_path,
+                        "working_directory": client.process.working_directory,
+                        "command": client.process.command,
+                        "container": client.process.container,
+                        "connections": client.connections.len(),
+                        "local_ports": client
+                            .connections
+                            .iter()
+                            .map(|c| c.port)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            let json_output = serde_json::json!({
+                "port": target.port,
+                "address": target.address,
+                "protocol": "tcp",
+                "total_connections": total,
+                "clients": clients_json
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+            return Ok(());
+        }
+
+        if quiet {
+            return Ok(());
+        }
+
+        if clients.is_empty() {
+            println!(
+                "{} No local processes are connected to {}:{}",
+                "○".blue(),
+                "TCP".blue(),
+                target.to_string().yellow()
+            );
+            return Ok(());
+        }
+
+        println!(
+            "{} {} connections to {}:{} from {} processes",
+            "✓".green(),
+            total,
+            "TCP".blue(),
+            target.to_string().yellow(),
+            clients.len()
+        );
+        println!();
+
+        println!(
+            "{:<10} {:<20} {:<12} {}",
+            "PID".cyan().bold(),
+            "PROCESS".cyan().bold(),
+            "CONNECTIONS".cyan().bold(),
+            "PATH".cyan().bold()
+        );
+        println!("{}", "-".repeat(80));
+
+        for client in &clients {
+            println!(
+                "{:<10} {:<20} {:<12} {}",
+                client.process.pid.to_string().blue(),
+                client.process.name.yellow(),
+                client.connections.len(),
+                crate::port::display_path(&client.process).cyan()
+            );
+            if verbose {
+                for connection in &client.connections {
+                    println!(
+                        "  {} {}:{}",
+                        "↳".dimmed(),
+                        connection.display_address(),
+                        connection.port
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Group connections by owning process, busiest client first
+    pub fn group_by_process(connections: Vec<ProcessInfo>) -> Vec<ClientProcess> {
+        let mut clients: Vec<ClientProcess> = Vec::new();
+
+        for connection in connections {
+            match clients.iter_mut().find(|c| c.process.pid == connection.pid) {
+                Some(client) => client.connections.push(connection),
+                None => clients.push(ClientProcess {
+                    process: connection.clone(),
+                    connections: vec![connection],
+                }),
+            }
+        }
+
+        clients.sort_by(|a, b| {
+            b.connections
+                .len()
+                .cmp(&a.connections.len())
+                .then(a.process.pid.cmp(&b.process.pid))
+        });
+        clients
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(pid: u32, name: &str, local_port: u16) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            command: format!("{name} --test"),
+            executable_path: format!("/usr/bin/{name}"),
+            working_directory: "/home/user/app".to_string(),
+            port: local_port,
+            protocol: "tcp".to_string(),
+            address: "127.0.0.1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_process() {
+        // テストランナーが接続をリークしているケース
+        let clients = ClientsCommand::group_by_process(vec![
+            connection(200, "psql", 50000),
+            connection(100, "pytest", 50001),
+            connection(100, "pytest", 50002),
+            connection(100, "pytest", 50003),
+        ]);
+
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].process.pid, 100);
+        assert_eq!(clients[0].connections.len(), 3);
+        assert_eq!(clients[1].process.name, "psql");
+        assert_eq!(clients[1].connections.len(), 1);
+    }
+
+    #[test]
+    fn test_group_by_process_empty() {
+        assert!(ClientsCommand::group_by_process(Vec::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_clients_finds_own_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let _client = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+
+        let manager = ProcfsPortManager::new();
+        match manager.list_clients(&PortTarget::new(port)).await {
+            Ok(connections) => {
+                let clients = ClientsCommand::group_by_process(connections);
+                assert_eq!(clients.len(), 1);
+                assert_eq!(clients[0].process.pid, std::process::id());
+            }
+            Err(_) => {
+                // procfsが使えない環境（macOSなど）
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    config::{Config, EffectiveSetting},
+    Result,
+};
+use colored::Colorize;
+
+/// Command for inspecting the configuration files.
+pub struct ConfigCommand;
+
+impl ConfigCommand {
+    /// Print every option default of `command`, after `config` was applied to
+    /// it, grouped by section and annotated with the file it comes from
+    pub fn show(config: &Config, command: &clap::Command, quiet: bool, json: bool) -> Result<()> {
+        let effective = config.effective(command);
+
+        if json {
+            let settings: Vec<serde_json::Value> = effective
+                .iter()
+                .map(|setting| {
+                    serde_json::json!({
+                        "section": setting.section,
+                        "key": setting.key,
+                        "value": setting.value,
+                        "source": setting.source,
+                    })
+                })
+                .collect();
+            let json_output = serde_json::json!({
+                "files": config.files,
+                "settings": settings
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+            return Ok(());
+        }
+        if quiet {
+            return Ok(());
+        }
+
+        if config.files.is_empty() {
+            println!("{} No configuration files found", "○".blue());
+        } else {
+            println!("{}", "Configuration files:".bold());
+            for file in &config.files {
+                println!("  {}", file.display());
+            }
+        }
+        for line in Self::format_settings(&effective) {
+            if line.starts_with('[') {
+                println!("{}", line.cyan().bold());
+            } else {
+                println!("{line}");
+            }
+        }
+        Ok(())
+    }
+
+    /// `key = value  # source` lines under a `[section]` header per section
+    fn format_settings(effective: &[EffectiveSetting]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for section in effective.chunk_by(|a, b| a.section == b.section) {
+            let assignments: Vec<String> = section
+                .iter()
+                .map(|setting| format!("{} = {}", setting.key, setting.value))
+                .collect();
+            let width = assignments.iter().map(String::len).max().unwrap_or(0);
+
+            lines.push(String::new());
+            lines.push(format!("[{}]", section[0].section));
+            for (assignment, setting) in assignments.iter().zip(section) {
+                let source = match &setting.source {
+                    Some(path) => path.display().to_string(),
+                    None => "built-in".to_string(),
+                };
+                lines.push(format!("{assignment:<width$}  # {source}"));
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_format_settings() {
+        let effective = vec![
+            EffectiveSetting {
+                section: "kill".to_string(),
+                key: "protocol".to_string(),
+                value: "\"udp\"".to_string(),
+                source: Some(PathBuf::from("/work/.kilar.toml")),
+            },
+            EffectiveSetting {
+                section: "kill".to_string(),
+                key: "wait".to_string(),
+                value: "\"10s\"".to_string(),
+                source: None,
+            },
+        ];
+
+        let lines = ConfigCommand::format_settings(&effective);
+        assert_eq!(lines[1], "[kill]");
+        assert_eq!(lines[2], "protocol = \"udp\"  # /work/.kilar.toml");
+        assert_eq!(lines[3], "wait = \"10s\"      # built-in");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{
+    commands::ListCommand,
+    port::{FreePortFinder, LeaseRegistry, PortBackend},
+    Result,
+};
+
+/// Command for finding ports that a new server can use.
+pub struct FreeCommand;
+
+impl FreeCommand {
+    /// Print up to `count` free ports in `range` (e.g. `3000-3999`), one per line.
+    ///
+    /// A port is free when no process listens on it, the kernel does not
+    /// reserve it (`ip_local_reserved_ports`), no lease in `registry` claims
+    /// it and a test bind succeeds. Printed ports are reserved in `registry`
+    /// for a short while, so concurrent calls never print the same port.
+    pub async fn execute_with_backend(
+        backend: &dyn PortBackend,
+        registry: &LeaseRegistry,
+        range: &str,
+        count: usize,
+        protocol: &str,
+        quiet: bool,
+        json: bool,
+    ) -> Result<()> {
+        if !matches!(protocol, "tcp" | "udp") {
+            return Err(crate::Error::Other(format!(
+                "Invalid protocol '{protocol}' for free. Must be tcp or udp"
+            )));
+        }
+        let (start, end) = ListCommand::parse_port_range(range)?;
+        let finder = FreePortFinder::scan(backend, protocol)
+            .await?
+            .with_leases(&registry.active(protocol));
+        let ports = finder.claim(start.max(1)..=end, count, registry)?;
+
+        if json {
+            let json_output = serde_json::json!({
+                "protocol": protocol,
+                "range": { "start": start, "end": end },
+                "ports": ports
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        } else if !quiet {
+            for port in &ports {
+                println!("{port}");
+            }
+        }
+
+        if ports.len() < count {
+            return Err(crate::Error::Other(format!(
+                "Only {} of {} requested {} ports are free in {}-{}",
+                ports.len(),
+                count,
+                protocol.to_uppercase(),
+                start,
+                end
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::testing::{unused_port, EmptyBackend};
+
+    #[tokio::test]
+    async fn test_free_reports_shortage() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let port = unused_port();
+        let range = format!("{port}-{port}");
+
+        // 範囲内のポート数より多くは見つからない
+        let result = FreeCommand::execute_with_backend(
+            &EmptyBackend,
+            &registry,
+            &range,
+            3,
+            "tcp",
+            true,
+            false,
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("of 3 requested"));
+
+        // 直前の呼び出しで予約されたポートは返さない
+        let result = FreeCommand::execute_with_backend(
+            &EmptyBackend,
+            &registry,
+            &range,
+            1,
+            "tcp",
+            true,
+            false,
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("Only 0 of 1"));
+
+        let result = FreeCommand::execute_with_backend(
+            &EmptyBackend,
+            &registry,
+            "4000",
+            1,
+            "tcp",
+            true,
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(crate::Error::InvalidPort(_))));
+    }
+}
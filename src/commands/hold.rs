@@ -0,0 +1,460 @@
+use crate::{
+    commands::{KillCommand, ListCommand},
+    port::{FreePortFinder, Lease, LeaseRegistry, PortBackend, ProcessInfo},
+    process::{tree, Escalation, ProcessManager, Protection},
+    Result,
+};
+use colored::Colorize;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, UdpSocket};
+use std::os::fd::OwnedFd;
+use std::time::Duration;
+
+/// Port for `hold` to bind
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HoldTarget {
+    Port(u16),
+    /// The first free port in a range such as `3000-3999`
+    Any(String),
+}
+
+/// Options for [`HoldCommand::execute_with_backend`]
+#[derive(Debug, Clone)]
+pub struct HoldOptions {
+    pub protocol: String,
+    /// Shown for the lease in `kilar list`
+    pub label: Option<String>,
+    /// Release the port after this long; `None` holds it until released
+    pub ttl: Option<Duration>,
+    pub quiet: bool,
+    pub json: bool,
+}
+
+/// Command for keeping a port bound so that parallel jobs on one machine
+/// do not pick it, with a lease recorded in the [`LeaseRegistry`].
+pub struct HoldCommand;
+
+impl HoldCommand {
+    /// Bind the port, print it and keep it until Ctrl+C, SIGTERM (sent by
+    /// `kilar release`) or the TTL expires.
+    ///
+    /// The port number alone goes to stdout so scripts can read it; the
+    /// status goes to stderr.
+    pub async fn execute_with_backend(
+        backend: &dyn PortBackend,
+        registry: &LeaseRegistry,
+        target: &HoldTarget,
+        options: &HoldOptions,
+    ) -> Result<()> {
+        Self::validate_protocol(&options.protocol)?;
+        let (sockets, lease) = match target {
+            HoldTarget::Port(port) => Self::hold_port(registry, *port, options)?,
+            HoldTarget::Any(range) => Self::hold_any(backend, registry, range, options).await?,
+        };
+        let label = format!("{}:{}", lease.protocol.to_uppercase(), lease.port);
+
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&lease)?);
+        } else if !options.quiet {
+            println!("{}", lease.port);
+            eprintln!(
+                "{} Holding {} as PID {}: {}. Stop with Ctrl+C or `kilar release {}`",
+                "✓".green(),
+                label.yellow(),
+                std::process::id().to_string().cyan(),
+                lease,
+                lease.port
+            );
+        }
+
+        let reason = Self::wait_for_release(options.ttl).await?;
+        drop(sockets);
+        registry.remove(&lease)?;
+
+        if !options.quiet && !options.json {
+            eprintln!("{} Released {} ({})", "✓".green(), label.yellow(), reason);
+        }
+        Ok(())
+    }
+
+    /// Stop the `kilar hold` process keeping `port` bound, or drop a
+    /// reservation made by `kilar free`. With `dry_run`, only report the
+    /// signals the holder would get.
+    ///
+    /// The holder must be a running kilar process started when the lease
+    /// says, and is subject to `protection` like any other kill.
+    pub async fn release(
+        registry: &LeaseRegistry,
+        port: u16,
+        protocol: &str,
+        protection: &Protection,
+        dry_run: bool,
+        quiet: bool,
+        json: bool,
+    ) -> Result<()> {
+        Self::validate_protocol(protocol)?;
+        registry.open()?;
+        let label = format!("{}:{}", protocol.to_uppercase(), port);
+        let Some(lease) = registry.get(protocol, port).filter(Lease::is_active) else {
+            return Err(crate::Error::Other(format!("{label} is not held by kilar")));
+        };
+        let holder = lease
+            .pid
+            .map(|pid| Self::lease_holder(&lease, pid))
+            .transpose()?;
+
+        if dry_run {
+            if let Some(holder) = &holder {
+                protection.check(holder)?;
+            }
+            let pids: Vec<u32> = lease.pid.into_iter().collect();
+            let escalation = Escalation::default();
+            if json {
+                let json_output = serde_json::json!({
+                    "port": port,
+                    "protocol": protocol,
+                    "action": "dry_run",
+                    "lease": lease,
+                    "signals": KillCommand::signals_json(&pids, &escalation)
+                });
+                println!("{}", serde_json::to_string_pretty(&json_output)?);
+            } else if !quiet {
+                println!(
+                    "{} Would release {} ({})",
+                    "[dry run]".cyan(),
+                    label.yellow(),
+                    lease
+                );
+                for step in KillCommand::describe_signals(&pids, &escalation) {
+                    println!("  {step}");
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(holder) = holder {
+            // 保持プロセスは終了時に自分でリースを削除する
+            match ProcessManager::new()
+                .kill_guarded(&holder, protection, &Escalation::default())
+                .await
+            {
+                Ok(())
+                | Err(crate::Error::ProcessNotFound(_) | crate::Error::ProcessReplaced(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        registry.remove(&lease)?;
+
+        if json {
+            let json_output = serde_json::json!({
+                "port": port,
+                "protocol": protocol,
+                "released": true,
+                "lease": lease
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        } else if !quiet {
+            println!("{} Released {} ({})", "✓".green(), label.yellow(), lease);
+        }
+        Ok(())
+    }
+
+    /// The process a lease names, once it is known to be the `kilar hold`
+    /// that wrote it: the lease file alone is not trusted to name a process
+    fn lease_holder(lease: &Lease, pid: u32) -> Result<ProcessInfo> {
+        let refuse = |reason: &str| {
+            crate::Error::Other(format!(
+                "Refusing to signal PID {pid} holding {}:{}: {reason}",
+                lease.protocol.to_uppercase(),
+                lease.port
+            ))
+        };
+        let Some(start_time) = lease.start_time else {
+            return Err(refuse("the lease does not record when it started"));
+        };
+        let exe = std::fs::read_link(format!("/proc/{pid}/exe")).ok();
+        if exe.is_none() || exe != std::env::current_exe().ok() {
+            return Err(refuse("it is not a kilar process"));
+        }
+
+        let name = tree::read_stat(pid).map_or_else(|| "kilar".to_string(), |stat| stat.name);
+        Ok(ProcessInfo {
+            executable_path: exe.unwrap_or_default().to_string_lossy().into_owned(),
+            start_time: Some(start_time),
+            ..ProcessInfo::new(pid, name, lease.port, lease.protocol.as_str())
+        })
+    }
+
+    fn validate_protocol(protocol: &str) -> Result<()> {
+        if matches!(protocol, "tcp" | "udp") {
+            Ok(())
+        } else {
+            Err(crate::Error::Other(format!(
+                "Invalid protocol '{protocol}' for hold. Must be tcp or udp"
+            )))
+        }
+    }
+
+    /// Bind `port` and lease it; an explicitly requested port takes over a
+    /// reservation made by `kilar free`
+    fn hold_port(
+        registry: &LeaseRegistry,
+        port: u16,
+        options: &HoldOptions,
+    ) -> Result<(Vec<OwnedFd>, Lease)> {
+        let sockets = Self::bind(port, &options.protocol).map_err(|e| match e.kind() {
+            ErrorKind::AddrInUse => crate::Error::Other(format!(
+                "{}:{} is already in use",
+                options.protocol.to_uppercase(),
+                port
+            )),
+            ErrorKind::PermissionDenied => {
+                crate::Error::PermissionDenied(format!("Cannot bind port {port}"))
+            }
+            _ => e.into(),
+        })?;
+
+        let lease = Lease::hold(port, &options.protocol, options.label.clone(), options.ttl);
+        if let Some(existing) = registry.acquire(&lease)? {
+            if !existing.is_reservation() {
+                return Err(crate::Error::Other(format!(
+                    "{}:{} is already held by PID {} ({})",
+                    options.protocol.to_uppercase(),
+                    port,
+                    existing.pid.unwrap_or_default(),
+                    existing
+                )));
+            }
+            registry.replace(&lease)?;
+        }
+        Ok((sockets, lease))
+    }
+
+    /// Bind and lease the first free port in `range`, skipping ports that
+    /// another kilar process leased in the meantime
+    async fn hold_any(
+        backend: &dyn PortBackend,
+        registry: &LeaseRegistry,
+        range: &str,
+        options: &HoldOptions,
+    ) -> Result<(Vec<OwnedFd>, Lease)> {
+        let (start, end) = ListCommand::parse_port_range(range)?;
+        let finder = FreePortFinder::scan(backend, &options.protocol)
+            .await?
+            .with_leases(&registry.active(&options.protocol));
+
+        for port in (start.max(1)..=end).filter(|port| finder.is_free(*port)) {
+            let Ok(sockets) = Self::bind(port, &options.protocol) else {
+                continue;
+            };
+            let lease = Lease::hold(port, &options.protocol, options.label.clone(), options.ttl);
+            if registry.acquire(&lease)?.is_none() {
+                return Ok((sockets, lease));
+            }
+        }
+
+        Err(crate::Error::Other(format!(
+            "No free {} port in {}-{}",
+            options.protocol.to_uppercase(),
+            start,
+            end
+        )))
+    }
+
+    /// Bind `port` on the IPv6 wildcard, which on dual-stack hosts also
+    /// covers IPv4, and on the IPv4 wildcard. TCP sockets listen, so the
+    /// port shows up in `kilar list`.
+    fn bind(port: u16, protocol: &str) -> std::io::Result<Vec<OwnedFd>> {
+        let bind = |address: SocketAddr| -> std::io::Result<OwnedFd> {
+            if protocol == "udp" {
+                UdpSocket::bind(address).map(OwnedFd::from)
+            } else {
+                TcpListener::bind(address).map(OwnedFd::from)
+            }
+        };
+
+        let mut sockets = Vec::new();
+        match bind(SocketAddr::from((Ipv6Addr::UNSPECIFIED, port))) {
+            Ok(socket) => sockets.push(socket),
+            // IPv6を使えないホストではIPv4だけを確保する
+            Err(e) if e.kind() != ErrorKind::AddrInUse => {}
+            Err(e) => return Err(e),
+        }
+        match bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))) {
+            Ok(socket) => sockets.push(socket),
+            // デュアルスタックのIPv6ソケットがIPv4も確保している
+            Err(e) if !sockets.is_empty() && e.kind() == ErrorKind::AddrInUse => {}
+            Err(e) => return Err(e),
+        }
+        Ok(sockets)
+    }
+
+    /// Wait for Ctrl+C, SIGTERM or the end of the TTL; returns why
+    async fn wait_for_release(ttl: Option<Duration>) -> Result<&'static str> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        let expiry = async move {
+            match ttl {
+                Some(ttl) => tokio::time::sleep(ttl).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        Ok(tokio::select! {
+            _ = tokio::signal::ctrl_c() => "interrupted",
+            _ = terminate.recv() => "released",
+            _ = expiry => "lease expired",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::{
+        lease::RESERVATION_TTL,
+        testing::{unused_port, EmptyBackend},
+    };
+
+    fn options(ttl: Option<Duration>) -> HoldOptions {
+        HoldOptions {
+            protocol: "tcp".to_string(),
+            label: Some("shard-1".to_string()),
+            ttl,
+            quiet: true,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_hold_port_binds_and_leases() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let port = unused_port();
+
+        let (sockets, lease) = HoldCommand::hold_port(&registry, port, &options(None)).unwrap();
+        assert!(!sockets.is_empty());
+        assert_eq!(lease.label.as_deref(), Some("shard-1"));
+        assert_eq!(registry.active("tcp"), vec![lease]);
+        assert!(!crate::port::free::can_bind(port, "tcp"));
+
+        // 保持中のポートは二重に確保できない
+        let result = HoldCommand::hold_port(&registry, port, &options(None));
+        assert!(result.unwrap_err().to_string().contains("already in use"));
+    }
+
+    #[test]
+    fn test_hold_port_takes_over_reservation() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let port = unused_port();
+        registry
+            .acquire(&Lease::reservation(port, "tcp", RESERVATION_TTL))
+            .unwrap();
+
+        let (_sockets, lease) = HoldCommand::hold_port(&registry, port, &options(None)).unwrap();
+        assert_eq!(registry.get("tcp", port), Some(lease));
+    }
+
+    #[tokio::test]
+    async fn test_hold_any_until_ttl_expires() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let port = unused_port();
+
+        HoldCommand::execute_with_backend(
+            &EmptyBackend,
+            &registry,
+            &HoldTarget::Any(format!("{port}-{port}")),
+            &options(Some(Duration::from_millis(100))),
+        )
+        .await
+        .unwrap();
+        assert!(registry.active("tcp").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_release_reservation() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let port = unused_port();
+        registry
+            .acquire(&Lease::reservation(port, "tcp", RESERVATION_TTL))
+            .unwrap();
+
+        // ドライランでは予約を残す
+        HoldCommand::release(
+            &registry,
+            port,
+            "tcp",
+            &Protection::default(),
+            true,
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(registry.get("tcp", port).is_some());
+
+        HoldCommand::release(
+            &registry,
+            port,
+            "tcp",
+            &Protection::default(),
+            false,
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(registry.get("tcp", port), None);
+
+        let result = HoldCommand::release(
+            &registry,
+            port,
+            "tcp",
+            &Protection::default(),
+            false,
+            true,
+            false,
+        )
+        .await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("not held by kilar"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_release_refuses_planted_lease() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let port = unused_port();
+        let protection = Protection::default();
+
+        let mut lease = Lease::hold(port, "tcp", None, None);
+        lease.pid = Some(child.id());
+        lease.start_time = tree::start_time(child.id());
+        registry.replace(&lease).unwrap();
+        let release = |dry_run| {
+            HoldCommand::release(&registry, port, "tcp", &protection, dry_run, true, false)
+        };
+        let err = release(false).await.unwrap_err().to_string();
+        assert!(err.contains("not a kilar process"), "{err}");
+
+        lease.start_time = None;
+        registry.replace(&lease).unwrap();
+        let err = release(true).await.unwrap_err().to_string();
+        assert!(err.contains("does not record when it started"), "{err}");
+
+        assert!(child.try_wait().unwrap().is_none());
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
@@ -1,9 +1,87 @@
-use crate::{port::PortManager, process::ProcessManager, Result};
+use crate::{
+    commands::{list::ProcessFilter, CheckCommand},
+    port::{
+        create_backend, BackendKind, PortBackend, PortTarget, ProcessInfo, Target, TargetSpec,
+        TcpState,
+    },
+    process::{
+        signal::FINAL_GRACE, tree, Escalation, ProcessManager, Protection, SystemctlStopper,
+        SystemdUnit, UnitStopper,
+    },
+    utils::format_duration,
+    Result,
+};
 use colored::Colorize;
-use dialoguer::Confirm;
+use dialoguer::{Confirm, MultiSelect};
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+/// How often `--wait` re-checks whether the port has been released
+const RELEASE_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 pub struct KillCommand;
 
+/// What `kill` terminates along with the listening process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KillScope {
+    /// Only the process holding the socket
+    #[default]
+    Process,
+    /// The holder's job: its top-most non-shell ancestor and every descendant (`--tree`)
+    Tree,
+    /// Every process in the holder's process group (`--group`)
+    Group,
+    /// Every process in the holder's session (`--group session`)
+    Session,
+}
+
+/// Options for [`KillCommand::execute_with_backend`]
+#[derive(Debug, Clone, Default)]
+pub struct KillOptions {
+    /// Kill every holder without confirmation
+    pub force: bool,
+    /// Stop the owning systemd unit instead of killing its process
+    pub stop_unit: bool,
+    pub scope: KillScope,
+    /// Signals to send and how long to wait for exit after each
+    pub escalation: Escalation,
+    /// After killing, wait up to this long for the port to be released (`--wait`)
+    pub wait: Option<Duration>,
+    /// Report which processes would get which signals instead of killing (`--dry-run`)
+    pub dry_run: bool,
+    /// Only kill holders matching this filter (`--name`, `--cwd`, `--path`, `--range`)
+    pub filter: ProcessFilter,
+    /// Processes that are refused unless `--allow-protected` is given
+    pub protection: Protection,
+    pub quiet: bool,
+    pub json: bool,
+    pub verbose: bool,
+}
+
+/// How one selected holder is killed
+#[derive(Debug, Clone)]
+pub enum KillAction {
+    /// Stop the systemd unit managing the holder instead of signalling it
+    StopUnit(SystemdUnit),
+    /// Walk the escalation for `members`, in this order (children first).
+    /// `spared` are the protected members of the tree, group or session,
+    /// as [`crate::Error::Protected`], which are left running.
+    Signal {
+        members: Vec<ProcessInfo>,
+        spared: Vec<crate::Error>,
+    },
+}
+
+/// What killing one holder did
+#[derive(Debug, Clone, Default)]
+pub struct Killed {
+    /// Every process signalled, the holder included
+    pub pids: Vec<u32>,
+    /// Protected members of its tree, group or session left running
+    pub spared: Vec<crate::Error>,
+}
+
 impl KillCommand {
     pub async fn execute(
         port: u16,
@@ -13,104 +91,1172 @@ impl KillCommand {
         json: bool,
         verbose: bool,
     ) -> Result<()> {
-        let port_manager = PortManager::new();
-        let process_manager = ProcessManager::new();
+        let backend = create_backend(BackendKind::default());
+        let options = KillOptions {
+            force,
+            quiet,
+            json,
+            verbose,
+            ..KillOptions::default()
+        };
+        Self::execute_with_backend(
+            backend.as_ref(),
+            &Target::Port(PortTarget::new(port)),
+            protocol,
+            &options,
+        )
+        .await
+    }
 
-        match port_manager.check_port(port, protocol).await? {
-            Some(process_info) => {
-                if !force && !json {
-                    let prompt = format!(
-                        "Kill process {} (PID: {}) using {}:{}?",
-                        process_info.name.yellow(),
-                        process_info.pid.to_string().cyan(),
-                        protocol.to_uppercase().blue(),
-                        port.to_string().yellow()
+    /// Execute the kill command using the given port backend.
+    ///
+    /// Every process holding a socket that matches `target` (a port or a
+    /// UNIX socket path) is found. With `force` (or `json`) all of them are
+    /// killed; otherwise the user confirms a single holder or picks which of
+    /// several holders to kill.
+    ///
+    /// Processes managed by a systemd service are stopped through the unit
+    /// when `stop_unit` is set or the user agrees to it, since systemd would
+    /// restart a killed service. Socket-activated ports, held by systemd
+    /// itself, are always freed by stopping their socket unit.
+    ///
+    /// `scope` widens each kill to the holder's process tree, group or session,
+    /// and `escalation` sets the signals sent until the processes have exited.
+    /// With `wait`, the port is re-checked until no process holds it any more.
+    pub async fn execute_with_backend(
+        backend: &dyn PortBackend,
+        target: &Target,
+        protocol: &str,
+        options: &KillOptions,
+    ) -> Result<()> {
+        Self::execute_targets(backend, &[target.clone().into()], protocol, options).await
+    }
+
+    /// Execute the kill command, stopping systemd units with `stopper`.
+    ///
+    /// See [`KillCommand::execute_with_backend`].
+    pub async fn execute_with_stopper(
+        backend: &dyn PortBackend,
+        stopper: &dyn UnitStopper,
+        target: &Target,
+        protocol: &str,
+        options: &KillOptions,
+    ) -> Result<()> {
+        Self::execute_targets_with_stopper(
+            backend,
+            stopper,
+            &[target.clone().into()],
+            protocol,
+            options,
+        )
+        .await
+    }
+
+    /// Kill the holders of several ports, sockets and port ranges at once.
+    ///
+    /// The holders of every target are found in one scan, narrowed down to
+    /// those matching `filter`, confirmed together and killed concurrently;
+    /// JSON output reports each target under `targets`. The command fails
+    /// when any kill fails or a port or socket target is not in use, but not
+    /// for a range without listeners.
+    ///
+    /// Confirmation needs a terminal on stdin; without one, nothing is killed
+    /// unless `force` is set.
+    ///
+    /// See [`KillCommand::execute_with_backend`] for the options.
+    pub async fn execute_targets(
+        backend: &dyn PortBackend,
+        targets: &[TargetSpec],
+        protocol: &str,
+        options: &KillOptions,
+    ) -> Result<()> {
+        Self::execute_targets_with_stopper(backend, &SystemctlStopper, targets, protocol, options)
+            .await
+    }
+
+    /// [`KillCommand::execute_targets`], stopping systemd units with `stopper`
+    pub async fn execute_targets_with_stopper(
+        backend: &dyn PortBackend,
+        stopper: &dyn UnitStopper,
+        targets: &[TargetSpec],
+        protocol: &str,
+        options: &KillOptions,
+    ) -> Result<()> {
+        let &KillOptions {
+            force,
+            stop_unit,
+            scope,
+            ref escalation,
+            wait,
+            dry_run,
+            ref filter,
+            ref protection,
+            quiet,
+            json,
+            verbose,
+        } = options;
+        let mut resolved = Self::resolve_targets(backend, targets, protocol).await?;
+        for holders in &mut resolved {
+            holders.retain(|p| filter.matches(p));
+        }
+        let holders = Self::unique_holders(resolved.iter().flatten().cloned().collect());
+        let target_list = Self::target_list(targets, filter);
+
+        if holders.is_empty() {
+            if json {
+                let mut json_output = Self::summary_json(targets, protocol, filter);
+                json_output["action"] = "not_found".into();
+                json_output["targets"] = targets
+                    .iter()
+                    .map(|target| {
+                        let mut target_json = Self::spec_json(target, protocol);
+                        target_json["status"] = "not_found".into();
+                        target_json["error"] =
+                            Self::not_found_message(target, protocol, filter).into();
+                        target_json
+                    })
+                    .collect::<Vec<_>>()
+                    .into();
+                json_output["error"] =
+                    Self::not_found_message(&targets[0], protocol, filter).into();
+                println!("{}", serde_json::to_string_pretty(&json_output)?);
+            } else if !quiet {
+                for target in targets {
+                    eprintln!(
+                        "{} {}",
+                        "×".red(),
+                        Self::not_found_message(target, protocol, filter)
                     );
+                }
+            }
+            return Err(Self::not_found_error(&targets[0], protocol, filter));
+        }
+
+        if (targets.len() > 1 || !filter.is_empty()) && !quiet && !json {
+            Self::print_summary(targets, &resolved, protocol);
+        }
+
+        // ドライランは何も終了しないので確認しない。複数ターゲット・範囲・
+        // フィルタ指定では --json でも確認を省略しない
+        let broad =
+            targets.len() > 1 || targets.iter().any(TargetSpec::is_range) || !filter.is_empty();
+        let selected: Vec<ProcessInfo> = if force || dry_run || (json && !broad) {
+            holders
+        } else if json {
+            return Err(crate::Error::Other(format!(
+                "Not killing {} process(es) matched by {} without confirmation: --json cannot prompt. Use --yes to kill without asking",
+                holders.len(),
+                target_list
+            )));
+        } else if !std::io::stdin().is_terminal() {
+            return Err(crate::Error::Other(format!(
+                "Not killing {} process(es) without confirmation: stdin is not a terminal. Use --yes to kill without asking",
+                holders.len()
+            )));
+        } else if holders.len() == 1 {
+            let process_info = &holders[0];
+            let prompt = format!(
+                "Kill process {} (PID: {}) using {}:{}?",
+                process_info.name.yellow(),
+                process_info.pid.to_string().cyan(),
+                protocol.to_uppercase().blue(),
+                target_list.yellow()
+            );
+
+            let confirmed = Confirm::new()
+                .with_prompt(prompt)
+                .default(false)
+                .interact()?;
 
-                    let confirmed = Confirm::new()
-                        .with_prompt(prompt)
-                        .default(false)
-                        .interact()?;
+            if !confirmed {
+                if !quiet {
+                    println!("{} Operation cancelled", "×".yellow());
+                }
+                return Ok(());
+            }
+            holders
+        } else {
+            if !quiet {
+                println!(
+                    "{} {}:{} is held by {} processes",
+                    "!".yellow(),
+                    protocol.to_uppercase().blue(),
+                    target_list.yellow(),
+                    holders.len()
+                );
+            }
+            match Self::select_holders(&holders, quiet)? {
+                Some(selected) => selected,
+                None => return Ok(()),
+            }
+        };
 
-                    if !confirmed {
-                        if !quiet {
-                            println!("{} Operation cancelled", "×".yellow());
+        let stop_units = stop_unit || (!force && !json && Self::confirm_stop_units(&selected)?);
+        if dry_run {
+            let plans = Self::plan_holders(&selected, stop_units, scope, protection);
+            if json {
+                let mut json_output = Self::summary_json(targets, protocol, filter);
+                json_output["action"] = "dry_run".into();
+                json_output["processes"] = Self::plan_json(&plans, escalation).into();
+                json_output["targets"] = targets
+                    .iter()
+                    .zip(&resolved)
+                    .map(|(target, holders)| {
+                        let mut target_json = Self::spec_json(target, protocol);
+                        target_json["status"] = if holders.is_empty() {
+                            "not_found"
+                        } else {
+                            "dry_run"
                         }
-                        return Ok(());
-                    }
+                        .into();
+                        target_json["pids"] = holders
+                            .iter()
+                            .filter(|h| selected.iter().any(|p| p.pid == h.pid))
+                            .map(|h| h.pid)
+                            .collect::<Vec<_>>()
+                            .into();
+                        target_json
+                    })
+                    .collect::<Vec<_>>()
+                    .into();
+                println!("{}", serde_json::to_string_pretty(&json_output)?);
+            } else if !quiet {
+                Self::print_plan(&plans, scope, escalation);
+            }
+            return match plans.into_iter().find_map(|(_, plan)| plan.err()) {
+                Some(e) => Err(e),
+                None => Ok(()),
+            };
+        }
+
+        let results = Self::kill_holders(
+            &selected, stopper, stop_units, scope, escalation, protection, quiet, json,
+        )
+        .await;
+        let mut first_error = results.iter().find_map(|(_, r)| r.as_ref().err()).cloned();
+
+        // 子プロセスやforkしたワーカーがソケットを保持し続けていないか確認する
+        let remaining = match wait {
+            Some(timeout) if first_error.is_none() => {
+                Self::wait_for_release(backend, targets, protocol, timeout).await?
+            }
+            _ => vec![Vec::new(); targets.len()],
+        };
+        let still_held: Vec<String> = targets
+            .iter()
+            .zip(&remaining)
+            .filter(|(_, holders)| !holders.is_empty())
+            .map(|(target, _)| target.to_string())
+            .collect();
+        if let (Some(timeout), false) = (wait, still_held.is_empty()) {
+            first_error = Some(crate::Error::Timeout(format!(
+                "{}:{} is still held after {}",
+                protocol.to_uppercase(),
+                still_held.join(", "),
+                format_duration(timeout)
+            )));
+        }
+        if first_error.is_none() {
+            first_error = targets
+                .iter()
+                .zip(&resolved)
+                .find(|(target, holders)| holders.is_empty() && !target.is_range())
+                .map(|(target, _)| Self::not_found_error(target, protocol, filter));
+        }
+
+        if json {
+            let processes: Vec<serde_json::Value> = results
+                .iter()
+                .map(|(p, r)| Self::result_json(p, r, stop_units))
+                .collect();
+            let targets_json: Vec<serde_json::Value> = targets
+                .iter()
+                .zip(&resolved)
+                .zip(&remaining)
+                .map(|((target, holders), remaining)| {
+                    Self::target_result_json(
+                        target, holders, remaining, &results, stop_units, protocol, filter,
+                    )
+                })
+                .collect();
+
+            let mut json_output = Self::summary_json(targets, protocol, filter);
+            json_output["action"] = if first_error.is_some() {
+                "failed"
+            } else {
+                "killed"
+            }
+            .into();
+            json_output["process"] = serde_json::json!({
+                "pid": selected[0].pid,
+                "name": selected[0].name
+            });
+            json_output["processes"] = processes.into();
+            json_output["targets"] = targets_json.into();
+            if wait.is_some() {
+                json_output["released"] = first_error.is_none().into();
+                let remaining = Self::unique_holders(remaining.iter().flatten().cloned().collect());
+                if !remaining.is_empty() {
+                    json_output["holders"] = remaining
+                        .iter()
+                        .map(|p| serde_json::json!({ "pid": p.pid, "name": p.name, "fd": p.fd }))
+                        .collect::<Vec<_>>()
+                        .into();
                 }
+            }
+            if let Some(ref e) = first_error {
+                json_output["error"] = serde_json::json!(e.to_string());
+            }
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        } else if !still_held.is_empty() {
+            for (target, holders) in targets.iter().zip(&remaining) {
+                if holders.is_empty() {
+                    continue;
+                }
+                eprintln!(
+                    "{} {}:{} is still held after {} by:",
+                    "×".red(),
+                    protocol.to_uppercase().blue(),
+                    target.to_string().yellow(),
+                    format_duration(wait.unwrap_or_default())
+                );
+                for holder in holders {
+                    eprintln!("  {}", Self::format_holder(holder));
+                }
+            }
+        } else if !quiet && wait.is_some() && first_error.is_none() {
+            println!(
+                "{} {}:{} released",
+                "✓".green(),
+                protocol.to_uppercase().blue(),
+                target_list.yellow()
+            );
+        }
 
-                match process_manager.kill_process(process_info.pid).await {
-                    Ok(()) => {
-                        if json {
-                            let json_output = serde_json::json!({
-                                "port": port,
-                                "protocol": protocol,
-                                "action": "killed",
-                                "process": {
-                                    "pid": process_info.pid,
-                                    "name": process_info.name
-                                }
-                            });
-                            println!("{}", serde_json::to_string_pretty(&json_output)?);
-                        } else if !quiet {
-                            println!(
-                                "{} Killed process {} (PID: {})",
+        if let (false, false, true, None, [TargetSpec::Single(target)]) =
+            (json, quiet, verbose, &first_error, targets)
+        {
+            match target {
+                Target::Port(_) => {
+                    println!("  Process was using port {}", target.to_string().yellow())
+                }
+                Target::Unix(_) => {
+                    println!("  Process was using socket {}", target.to_string().yellow())
+                }
+            }
+            println!("  Protocol: {}", protocol.to_uppercase().blue());
+        }
+
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Sockets matching any of `targets`, found with one scan per protocol.
+    ///
+    /// A single port keeps using [`PortBackend::check_port_all`], which some
+    /// backends answer without listing every socket.
+    async fn scan_targets(
+        backend: &dyn PortBackend,
+        targets: &[TargetSpec],
+        protocol: &str,
+    ) -> Result<Vec<ProcessInfo>> {
+        if let [TargetSpec::Single(target)] = targets {
+            return CheckCommand::scan_holders(backend, target, protocol, &[TcpState::Listen])
+                .await;
+        }
+
+        let mut sockets = Vec::new();
+        if targets.iter().any(|target| !target.is_unix()) {
+            sockets.extend(backend.list_processes(protocol).await?);
+        }
+        if targets.iter().any(TargetSpec::is_unix) {
+            sockets.extend(backend.list_processes("unix").await?);
+        }
+        sockets.retain(|p| targets.iter().any(|target| target.matches(p)));
+        Ok(sockets)
+    }
+
+    /// The holders of each target, one entry per process, with containers,
+    /// units and start times attached
+    pub(crate) async fn resolve_targets(
+        backend: &dyn PortBackend,
+        targets: &[TargetSpec],
+        protocol: &str,
+    ) -> Result<Vec<Vec<ProcessInfo>>> {
+        let mut sockets = Self::scan_targets(backend, targets, protocol).await?;
+        crate::port::attach_containers(&mut sockets);
+        crate::process::systemd::attach_units(&mut sockets).await;
+        crate::process::tree::attach_start_times(&mut sockets);
+        Ok(Self::group_by_target(targets, &sockets))
+    }
+
+    fn group_by_target(targets: &[TargetSpec], sockets: &[ProcessInfo]) -> Vec<Vec<ProcessInfo>> {
+        targets
+            .iter()
+            .map(|target| {
+                Self::unique_holders(
+                    sockets
+                        .iter()
+                        .filter(|p| target.matches(p))
+                        .cloned()
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Re-check `targets` until no process holds any of them or `timeout` passes.
+    ///
+    /// Returns the processes still holding each target.
+    pub(crate) async fn wait_for_release(
+        backend: &dyn PortBackend,
+        targets: &[TargetSpec],
+        protocol: &str,
+        timeout: Duration,
+    ) -> Result<Vec<Vec<ProcessInfo>>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let sockets = Self::scan_targets(backend, targets, protocol).await?;
+            let now = Instant::now();
+            if sockets.is_empty() || now >= deadline {
+                return Ok(Self::group_by_target(targets, &sockets));
+            }
+            tokio::time::sleep(RELEASE_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
+
+    /// `3000, 5173, 8000-8010`, followed by the filter if there is one
+    fn target_list(targets: &[TargetSpec], filter: &ProcessFilter) -> String {
+        let list = targets
+            .iter()
+            .map(TargetSpec::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if filter.is_empty() {
+            list
+        } else {
+            format!("{list} ({filter})")
+        }
+    }
+
+    /// Why nothing was killed for `target`
+    fn not_found_message(target: &TargetSpec, protocol: &str, filter: &ProcessFilter) -> String {
+        if !filter.is_empty() {
+            return format!(
+                "No process on {}:{target} matches {filter}",
+                protocol.to_uppercase()
+            );
+        }
+        match target {
+            TargetSpec::Single(Target::Port(_)) => {
+                format!("Port {}:{target} is not in use", protocol.to_uppercase())
+            }
+            TargetSpec::Single(unix) if CheckCommand::is_stale_socket(unix) => {
+                format!("Socket {target} is stale (no process is listening)")
+            }
+            TargetSpec::Single(Target::Unix(_)) => format!("Socket {target} is not in use"),
+            TargetSpec::Range { .. } => {
+                format!("No port in {}:{target} is in use", protocol.to_uppercase())
+            }
+        }
+    }
+
+    fn not_found_error(
+        target: &TargetSpec,
+        protocol: &str,
+        filter: &ProcessFilter,
+    ) -> crate::Error {
+        match target {
+            _ if !filter.is_empty() => {
+                crate::Error::Other(Self::not_found_message(target, protocol, filter))
+            }
+            TargetSpec::Single(Target::Port(port_target)) => {
+                crate::Error::PortNotFound(port_target.port)
+            }
+            TargetSpec::Single(Target::Unix(path)) => crate::Error::SocketNotFound(path.clone()),
+            TargetSpec::Range { .. } => {
+                crate::Error::Other(Self::not_found_message(target, protocol, filter))
+            }
+        }
+    }
+
+    /// Top-level JSON fields: those of the target when there is only one,
+    /// and the filter if there is one
+    fn summary_json(
+        targets: &[TargetSpec],
+        protocol: &str,
+        filter: &ProcessFilter,
+    ) -> serde_json::Value {
+        let mut json_output = match targets {
+            [TargetSpec::Single(target)] => CheckCommand::target_json(target, protocol),
+            _ => serde_json::json!({ "protocol": protocol }),
+        };
+        if !filter.is_empty() {
+            json_output["filter"] = filter.to_json();
+        }
+        json_output
+    }
+
+    /// JSON fields identifying `target`
+    fn spec_json(target: &TargetSpec, protocol: &str) -> serde_json::Value {
+        match target {
+            TargetSpec::Single(target) => {
+                CheckCommand::target_json(target, if target.is_unix() { "unix" } else { protocol })
+            }
+            TargetSpec::Range { start, end, .. } => serde_json::json!({
+                "range": { "start": start, "end": end },
+                "protocol": protocol
+            }),
+        }
+    }
+
+    /// JSON for the kill of one holder
+    fn result_json(
+        p: &ProcessInfo,
+        result: &Result<Killed>,
+        stop_units: bool,
+    ) -> serde_json::Value {
+        match result {
+            Ok(_) if Self::stops_unit(p, stop_units) => serde_json::json!({
+                "pid": p.pid,
+                "name": p.name,
+                "port": p.port,
+                "fd": p.fd,
+                "action": "stopped_unit",
+                "unit": p.unit
+            }),
+            Ok(killed) => {
+                let mut result_json = serde_json::json!({
+                    "pid": p.pid,
+                    "name": p.name,
+                    "port": p.port,
+                    "fd": p.fd,
+                    "action": "killed",
+                    "killed_pids": killed.pids
+                });
+                if !killed.spared.is_empty() {
+                    result_json["skipped"] = Self::spared_json(&killed.spared).into();
+                }
+                result_json
+            }
+            Err(e) => serde_json::json!({
+                "pid": p.pid,
+                "name": p.name,
+                "port": p.port,
+                "fd": p.fd,
+                "action": "failed",
+                "error": e.to_string()
+            }),
+        }
+    }
+
+    /// JSON for the protected members a tree, group or session kill left running
+    fn spared_json(spared: &[crate::Error]) -> Vec<serde_json::Value> {
+        spared
+            .iter()
+            .map(|e| match e {
+                crate::Error::Protected { pid, name, .. } => serde_json::json!({
+                    "pid": pid,
+                    "name": name,
+                    "status": "skipped",
+                    "error": e.to_string()
+                }),
+                e => serde_json::json!({ "status": "skipped", "error": e.to_string() }),
+            })
+            .collect()
+    }
+
+    /// JSON for one target: `killed`, `failed`, `still_held`, `skipped`
+    /// (no holder selected) or `not_found`, with its holders' results
+    fn target_result_json(
+        target: &TargetSpec,
+        holders: &[ProcessInfo],
+        remaining: &[ProcessInfo],
+        results: &[(ProcessInfo, Result<Killed>)],
+        stop_units: bool,
+        protocol: &str,
+        filter: &ProcessFilter,
+    ) -> serde_json::Value {
+        let own_results: Vec<&(ProcessInfo, Result<Killed>)> = results
+            .iter()
+            .filter(|(p, _)| holders.iter().any(|h| h.pid == p.pid))
+            .collect();
+        let error = own_results.iter().find_map(|(_, r)| r.as_ref().err());
+
+        let mut target_json = Self::spec_json(target, protocol);
+        let status = if holders.is_empty() {
+            target_json["error"] = Self::not_found_message(target, protocol, filter).into();
+            "not_found"
+        } else if own_results.is_empty() {
+            "skipped"
+        } else if let Some(e) = error {
+            target_json["error"] = e.to_string().into();
+            "failed"
+        } else if !remaining.is_empty() {
+            target_json["holders"] = remaining
+                .iter()
+                .map(|p| serde_json::json!({ "pid": p.pid, "name": p.name, "fd": p.fd }))
+                .collect::<Vec<_>>()
+                .into();
+            "still_held"
+        } else {
+            "killed"
+        };
+        target_json["status"] = status.into();
+        target_json["processes"] = own_results
+            .iter()
+            .map(|(p, r)| Self::result_json(p, r, stop_units))
+            .collect::<Vec<_>>()
+            .into();
+        target_json
+    }
+
+    /// List the holders of each target before the combined confirmation
+    fn print_summary(targets: &[TargetSpec], resolved: &[Vec<ProcessInfo>], protocol: &str) {
+        for (target, holders) in targets.iter().zip(resolved) {
+            if holders.is_empty() {
+                println!(
+                    "{} {}:{} is not in use",
+                    "○".blue(),
+                    protocol.to_uppercase().blue(),
+                    target.to_string().yellow()
+                );
+            }
+            for holder in holders {
+                // 範囲指定では実際のポートを表示する
+                let port = if target.is_range() {
+                    holder.port.to_string()
+                } else {
+                    target.to_string()
+                };
+                println!(
+                    "{} {}:{} {}",
+                    "•".cyan(),
+                    protocol.to_uppercase().blue(),
+                    port.yellow(),
+                    Self::format_holder(holder)
+                );
+            }
+        }
+        println!();
+    }
+
+    /// Collapse holders to one entry per PID.
+    ///
+    /// A process listening on both IPv4 and IPv6, or holding a duplicated
+    /// descriptor, only needs to be killed once.
+    pub(crate) fn unique_holders(holders: Vec<ProcessInfo>) -> Vec<ProcessInfo> {
+        let mut seen = HashSet::new();
+        holders.into_iter().filter(|p| seen.insert(p.pid)).collect()
+    }
+
+    /// Let the user pick which holders to kill.
+    ///
+    /// Returns `None` when the selection or confirmation is cancelled.
+    pub(crate) fn select_holders(
+        holders: &[ProcessInfo],
+        quiet: bool,
+    ) -> Result<Option<Vec<ProcessInfo>>> {
+        let options: Vec<String> = holders.iter().map(Self::format_holder).collect();
+        let defaults = vec![true; holders.len()];
+
+        let selections = match MultiSelect::new()
+            .with_prompt("Select processes (Space: select, Enter: confirm, Esc/q: cancel)")
+            .items(&options)
+            .defaults(&defaults)
+            .interact_opt()?
+        {
+            Some(selected) if !selected.is_empty() => selected,
+            Some(_) => {
+                if !quiet {
+                    println!("{} No processes selected", "×".yellow());
+                }
+                return Ok(None);
+            }
+            None => {
+                if !quiet {
+                    println!("{} Operation cancelled", "×".yellow());
+                }
+                return Ok(None);
+            }
+        };
+
+        let prompt = if selections.len() == 1 {
+            "Kill 1 process?".to_string()
+        } else {
+            format!("Kill {} processes?", selections.len())
+        };
+        let confirmed = Confirm::new()
+            .with_prompt(prompt)
+            .default(false)
+            .interact()?;
+
+        if !confirmed {
+            if !quiet {
+                println!("{} Operation cancelled", "×".yellow());
+            }
+            return Ok(None);
+        }
+
+        Ok(Some(
+            selections.into_iter().map(|i| holders[i].clone()).collect(),
+        ))
+    }
+
+    /// Whether `process_info` is freed by stopping its systemd unit instead of a signal
+    pub(crate) fn stops_unit(process_info: &ProcessInfo, stop_units: bool) -> bool {
+        process_info
+            .unit
+            .as_ref()
+            .is_some_and(|unit| stop_units || unit.is_socket())
+    }
+
+    /// Ask whether to stop the systemd services managing `targets` rather than
+    /// killing them. Returns `false` without asking when none is a service.
+    pub(crate) fn confirm_stop_units(targets: &[ProcessInfo]) -> Result<bool> {
+        let Some(process_info) = targets
+            .iter()
+            .find(|p| p.unit.as_ref().is_some_and(|unit| !unit.is_socket()))
+        else {
+            return Ok(false);
+        };
+        let unit = process_info.unit.as_ref().expect("checked above");
+
+        let prompt = format!(
+            "{} is managed by systemd unit {} and would be restarted. Stop the unit instead?",
+            process_info.name.yellow(),
+            unit.to_string().cyan()
+        );
+        Ok(Confirm::new()
+            .with_prompt(prompt)
+            .default(true)
+            .interact()?)
+    }
+
+    /// Decide how `process_info` is killed, without signalling anything.
+    ///
+    /// [`KillCommand::kill_holders`] carries this plan out, and `--dry-run`
+    /// reports it instead. Processes guarded by `protection` fail with
+    /// [`crate::Error::Protected`], except that the socket unit of a
+    /// socket-activated service may still be stopped.
+    pub(crate) fn plan_kill(
+        process_info: &ProcessInfo,
+        stop_units: bool,
+        scope: KillScope,
+        protection: &Protection,
+    ) -> Result<KillAction> {
+        let unit = process_info
+            .unit
+            .as_ref()
+            .filter(|_| Self::stops_unit(process_info, stop_units));
+        if !unit.is_some_and(SystemdUnit::is_socket) {
+            protection.check(process_info)?;
+        }
+        if let Some(unit) = unit {
+            return Ok(KillAction::StopUnit(unit.clone()));
+        }
+
+        // Sending signals to the service manager never frees the socket
+        if process_info.pid == 1 {
+            return Err(crate::Error::Other(
+                "PID 1 holds this socket for socket activation, but no socket unit was found"
+                    .to_string(),
+            ));
+        }
+
+        match scope {
+            KillScope::Process => Ok(KillAction::Signal {
+                members: vec![process_info.clone()],
+                spared: Vec::new(),
+            }),
+            scope => Self::scope_members(process_info, scope, protection)
+                .map(|(members, spared)| KillAction::Signal { members, spared }),
+        }
+    }
+
+    /// Plan the kill of every target; see [`KillCommand::plan_kill`]
+    pub(crate) fn plan_holders(
+        targets: &[ProcessInfo],
+        stop_units: bool,
+        scope: KillScope,
+        protection: &Protection,
+    ) -> Vec<(ProcessInfo, Result<KillAction>)> {
+        targets
+            .iter()
+            .map(|p| (p.clone(), Self::plan_kill(p, stop_units, scope, protection)))
+            .collect()
+    }
+
+    /// Kill each target, reporting progress unless `quiet` or `json`.
+    ///
+    /// Targets owned by a systemd unit are stopped with `stopper` instead
+    /// when [`KillCommand::stops_unit`] says so; each unit is stopped once.
+    /// The remaining targets are signalled concurrently, so one slow
+    /// escalation does not hold up the others. Each result lists the PIDs
+    /// that were signalled.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn kill_holders(
+        targets: &[ProcessInfo],
+        stopper: &dyn UnitStopper,
+        stop_units: bool,
+        scope: KillScope,
+        escalation: &Escalation,
+        protection: &Protection,
+        quiet: bool,
+        json: bool,
+    ) -> Vec<(ProcessInfo, Result<Killed>)> {
+        let process_manager = ProcessManager::new();
+        let plans = Self::plan_holders(targets, stop_units, scope, protection);
+        let mut results: Vec<Option<Result<Killed>>> = vec![None; plans.len()];
+
+        // systemctlは1ユニットずつ呼び出す
+        let mut stopped: Vec<(String, Result<Killed>)> = Vec::new();
+        for (i, (process_info, plan)) in plans.iter().enumerate() {
+            let Ok(KillAction::StopUnit(unit)) = plan else {
+                continue;
+            };
+            let result = match stopped.iter().find(|(name, _)| *name == unit.name) {
+                Some((_, result)) => result.clone(),
+                None => {
+                    let result = stopper.stop(unit).await.map(|()| Killed::default());
+                    if !json {
+                        match &result {
+                            Ok(_) if !quiet => println!(
+                                "{} Stopped systemd unit {} (PID: {})",
                                 "✓".green(),
-                                process_info.name.yellow(),
+                                unit.to_string().yellow(),
                                 process_info.pid.to_string().cyan()
-                            );
-                            if verbose {
-                                println!("  Process was using port {}", port.to_string().yellow());
-                                println!("  Protocol: {}", protocol.to_uppercase().blue());
-                            }
+                            ),
+                            Err(e) => eprintln!(
+                                "{} Failed to stop systemd unit {}: {}",
+                                "×".red(),
+                                unit,
+                                e
+                            ),
+                            _ => {}
                         }
                     }
-                    Err(e) => {
-                        if json {
-                            let json_output = serde_json::json!({
-                                "port": port,
-                                "protocol": protocol,
-                                "action": "failed",
-                                "error": e.to_string(),
-                                "process": {
-                                    "pid": process_info.pid,
-                                    "name": process_info.name
-                                }
-                            });
-                            println!("{}", serde_json::to_string_pretty(&json_output)?);
-                        } else {
-                            eprintln!("{} Failed to kill process: {}", "×".red(), e);
+                    stopped.push((unit.name.clone(), result.clone()));
+                    result
+                }
+            };
+            results[i] = Some(result);
+        }
+
+        let kills = plans
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (process_info, plan))| {
+                let signal = match plan {
+                    Ok(KillAction::StopUnit(_)) => return None,
+                    Ok(KillAction::Signal { members, spared }) => Ok((members, spared)),
+                    Err(e) => Err(e.clone()),
+                };
+                let process_manager = &process_manager;
+                Some(async move {
+                    let result = match signal {
+                        Ok((members, _)) if scope == KillScope::Process => process_manager
+                            .kill_guarded(&members[0], protection, escalation)
+                            .await
+                            .map(|()| Killed {
+                                pids: vec![process_info.pid],
+                                spared: Vec::new(),
+                            }),
+                        Ok((members, spared)) => process_manager
+                            .kill_all_guarded(members, protection, escalation)
+                            .await
+                            .map(|()| Killed {
+                                pids: members.iter().map(|m| m.pid).collect(),
+                                spared: spared.clone(),
+                            }),
+                        Err(e) => Err(e),
+                    };
+                    (i, result)
+                })
+            });
+
+        for (i, result) in futures::future::join_all(kills).await {
+            let process_info = &plans[i].0;
+            if !json {
+                match &result {
+                    Ok(killed) if !quiet => {
+                        println!(
+                            "{} Killed {}",
+                            "✓".green(),
+                            Self::describe_kill(process_info, scope, killed.pids.len())
+                        );
+                        for spared in &killed.spared {
+                            println!("  {} Left running: {}", "!".yellow(), spared);
+                        }
+                        if let Some(unit) = &process_info.unit {
+                            println!(
+                                "  {} {} may restart it; use --stop-unit to stop the unit",
+                                "!".yellow(),
+                                unit
+                            );
                         }
-                        return Err(e);
                     }
+                    Err(e) => eprintln!(
+                        "{} Failed to kill {} (PID: {}): {}",
+                        "×".red(),
+                        process_info.name,
+                        process_info.pid,
+                        e
+                    ),
+                    _ => {}
                 }
             }
-            None => {
-                let error_msg = format!("Port {}:{port} is not in use", protocol.to_uppercase());
-                if json {
-                    let json_output = serde_json::json!({
-                        "port": port,
-                        "protocol": protocol,
-                        "action": "not_found",
-                        "error": error_msg
+            results[i] = Some(result);
+        }
+
+        plans
+            .into_iter()
+            .zip(results)
+            .map(|((process_info, _), result)| {
+                (process_info, result.expect("every plan has a result"))
+            })
+            .collect()
+    }
+
+    /// Print what carrying out `plans` would do (`--dry-run`)
+    pub(crate) fn print_plan(
+        plans: &[(ProcessInfo, Result<KillAction>)],
+        scope: KillScope,
+        escalation: &Escalation,
+    ) {
+        for (process_info, plan) in plans {
+            match plan {
+                Ok(KillAction::StopUnit(unit)) => println!(
+                    "{} Would stop systemd unit {} (PID: {})",
+                    "[dry run]".cyan(),
+                    unit.to_string().yellow(),
+                    process_info.pid.to_string().cyan()
+                ),
+                Ok(KillAction::Signal { members, spared }) => {
+                    println!(
+                        "{} Would kill {}",
+                        "[dry run]".cyan(),
+                        Self::describe_kill(process_info, scope, members.len())
+                    );
+                    let pids: Vec<u32> = members.iter().map(|m| m.pid).collect();
+                    for step in Self::describe_signals(&pids, escalation) {
+                        println!("  {step}");
+                    }
+                    for spared in spared {
+                        println!("  {} Would leave running: {}", "!".yellow(), spared);
+                    }
+                }
+                Err(e) => println!(
+                    "{} Would not kill {} (PID: {}): {}",
+                    "×".red(),
+                    process_info.name,
+                    process_info.pid,
+                    e
+                ),
+            }
+        }
+    }
+
+    /// JSON for each plan in `plans` (`--dry-run --json`)
+    pub(crate) fn plan_json(
+        plans: &[(ProcessInfo, Result<KillAction>)],
+        escalation: &Escalation,
+    ) -> Vec<serde_json::Value> {
+        plans
+            .iter()
+            .map(|(p, plan)| match plan {
+                Ok(KillAction::StopUnit(unit)) => serde_json::json!({
+                    "pid": p.pid,
+                    "name": p.name,
+                    "fd": p.fd,
+                    "action": "stop_unit",
+                    "unit": unit
+                }),
+                Ok(KillAction::Signal { members, spared }) => {
+                    let pids: Vec<u32> = members.iter().map(|m| m.pid).collect();
+                    let mut plan_json = serde_json::json!({
+                        "pid": p.pid,
+                        "name": p.name,
+                        "fd": p.fd,
+                        "action": "kill",
+                        "pids": pids,
+                        "signals": Self::signals_json(&pids, escalation)
                     });
-                    println!("{}", serde_json::to_string_pretty(&json_output)?);
-                } else if !quiet {
-                    eprintln!("{} {}", "×".red(), error_msg);
+                    if !spared.is_empty() {
+                        plan_json["skipped"] = Self::spared_json(spared).into();
+                    }
+                    plan_json
                 }
-                return Err(crate::Error::PortNotFound(port));
+                Err(e) => serde_json::json!({
+                    "pid": p.pid,
+                    "name": p.name,
+                    "fd": p.fd,
+                    "action": "refuse",
+                    "error": e.to_string()
+                }),
+            })
+            .collect()
+    }
+
+    /// The escalation steps `pids` would go through, in order, e.g.
+    /// `1. SIGTERM → 201, 202, then wait up to 5s`
+    pub(crate) fn describe_signals(pids: &[u32], escalation: &Escalation) -> Vec<String> {
+        let pids: Vec<String> = pids.iter().map(u32::to_string).collect();
+        escalation
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| {
+                format!(
+                    "{}. {} → {}{}, then wait up to {}",
+                    i + 1,
+                    step.signal.to_string().yellow(),
+                    pids.join(", ").cyan(),
+                    if i > 0 { " (if still running)" } else { "" },
+                    format_duration(step.wait.unwrap_or(FINAL_GRACE))
+                )
+            })
+            .collect()
+    }
+
+    /// JSON for the escalation steps `pids` would go through
+    pub(crate) fn signals_json(pids: &[u32], escalation: &Escalation) -> serde_json::Value {
+        escalation
+            .steps
+            .iter()
+            .map(|step| {
+                serde_json::json!({
+                    "signal": step.signal.to_string(),
+                    "number": step.signal.number(),
+                    "pids": pids,
+                    "wait_ms": step.wait.unwrap_or(FINAL_GRACE).as_millis() as u64
+                })
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Members of the tree, group or session of `process_info`, children
+    /// first, and the members `protection` guards, which are left out.
+    ///
+    /// kilar and its own ancestors are never included, so `--group session`
+    /// run from the same terminal spares the shell. `process_info` itself was
+    /// checked by the caller.
+    fn scope_members(
+        process_info: &ProcessInfo,
+        scope: KillScope,
+        protection: &Protection,
+    ) -> Result<(Vec<ProcessInfo>, Vec<crate::Error>)> {
+        let pid = process_info.pid;
+        let stats = tree::all_stats();
+        let by_pid: HashMap<u32, &tree::ProcStat> = stats.iter().map(|s| (s.pid, s)).collect();
+
+        // スキャン後にPIDが再利用されていたら、別のプロセスのツリーを辿らない
+        if let (Some(expected), Some(actual)) = (
+            process_info.start_time,
+            by_pid.get(&pid).and_then(|s| s.start_time),
+        ) {
+            if expected != actual {
+                return Err(crate::Error::ProcessReplaced(pid));
             }
         }
 
-        Ok(())
+        let mut members = match scope {
+            KillScope::Process => vec![pid],
+            KillScope::Tree => tree::descendants(tree::tree_root(pid), &stats),
+            KillScope::Group | KillScope::Session => {
+                let stat = stats.iter().find(|s| s.pid == pid).ok_or_else(|| {
+                    crate::Error::Other(format!(
+                        "Cannot read process group of PID {pid} (requires /proc)"
+                    ))
+                })?;
+                let member_pids: Vec<u32> = stats
+                    .iter()
+                    .filter(|s| match scope {
+                        KillScope::Group => s.pgrp == stat.pgrp,
+                        _ => s.session == stat.session,
+                    })
+                    .map(|s| s.pid)
+                    .collect();
+                tree::children_first(&member_pids, &stats)
+            }
+        };
+
+        let protected = tree::protected_pids();
+        members.retain(|pid| !protected.contains(pid));
+        if members.is_empty() {
+            return Err(crate::Error::Other(format!(
+                "Refusing to kill PID {pid}: it belongs to kilar's own process tree"
+            )));
+        }
+
+        let mut spared = Vec::new();
+        let members = members
+            .into_iter()
+            .map(|member| match by_pid.get(&member) {
+                _ if member == pid => process_info.clone(),
+                Some(stat) => ProcessInfo {
+                    executable_path: std::fs::read_link(format!("/proc/{member}/exe"))
+                        .map(|exe| exe.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    start_time: stat.start_time,
+                    ..ProcessInfo::new(member, stat.name.clone(), 0, "")
+                },
+                None => ProcessInfo::new(member, "", 0, ""),
+            })
+            .filter(|member| match protection.check(member) {
+                Ok(()) => true,
+                Err(e) => {
+                    spared.push(e);
+                    false
+                }
+            })
+            .collect();
+        Ok((members, spared))
+    }
+
+    /// What was killed, e.g. `process tree of npm (PID: 201): 4 processes`
+    fn describe_kill(process_info: &ProcessInfo, scope: KillScope, count: usize) -> String {
+        let what = match scope {
+            KillScope::Process => "process",
+            KillScope::Tree => "process tree of",
+            KillScope::Group => "process group of",
+            KillScope::Session => "session of",
+        };
+        let mut description = format!(
+            "{} {} (PID: {})",
+            what,
+            process_info.name.yellow(),
+            process_info.pid.to_string().cyan()
+        );
+        if scope != KillScope::Process {
+            description.push_str(&format!(": {count} processes"));
+        }
+        description
+    }
+
+    fn format_holder(process_info: &ProcessInfo) -> String {
+        let fd = process_info
+            .fd
+            .map(|fd| format!(" fd {fd}"))
+            .unwrap_or_default();
+        format!(
+            "{} (PID:{}{}) | {}",
+            process_info.name.yellow(),
+            process_info.pid.to_string().blue(),
+            fd,
+            process_info.command.dimmed()
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::port::ProcessInfo;
+    use std::os::unix::process::ExitStatusExt;
 
     // テスト用のモックプロセス情報を作成
     fn create_test_process_info(port: u16, pid: u32) -> ProcessInfo {
@@ -124,6 +1270,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "127.0.0.1".to_string(),
             inode: Some(12345),
+            ..Default::default()
         }
     }
 
@@ -249,6 +1396,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unique_holders_one_entry_per_pid() {
+        // IPv4/IPv6の両方で待ち受けるプロセスは1回だけkillすればよい
+        let mut v6 = create_test_process_info(8080, 1000);
+        v6.address = "::".to_string();
+        let holders = vec![
+            create_test_process_info(8080, 1000),
+            v6,
+            create_test_process_info(8080, 1001),
+            create_test_process_info(8080, 1002),
+        ];
+
+        let unique = KillCommand::unique_holders(holders);
+        let pids: Vec<u32> = unique.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1000, 1001, 1002]);
+        assert_eq!(unique[0].address, "127.0.0.1");
+    }
+
+    #[test]
+    fn test_format_holder_includes_fd() {
+        let mut process_info = create_test_process_info(8080, 1234);
+        process_info.fd = Some(6);
+        let formatted = KillCommand::format_holder(&process_info);
+        assert!(formatted.contains("1234"));
+        assert!(formatted.contains("fd 6"));
+
+        process_info.fd = None;
+        let formatted = KillCommand::format_holder(&process_info);
+        assert!(!formatted.contains("fd "));
+    }
+
     #[test]
     fn test_kill_success_json_structure() {
         // killが成功した場合のJSON出力構造をテスト
@@ -364,4 +1542,518 @@ mod tests {
             }
         }
     }
+
+    /// 停止したユニットを記録するだけのストッパー
+    struct RecordingStopper(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl UnitStopper for RecordingStopper {
+        async fn stop(&self, unit: &crate::process::SystemdUnit) -> Result<()> {
+            self.0.lock().unwrap().push(unit.name.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kill_holders_stops_unit_once() {
+        use crate::process::SystemdUnit;
+
+        // 同じサービスに属する2プロセスはユニットを1回だけ停止する
+        let mut main = create_test_process_info(8080, 99990);
+        main.unit = Some(SystemdUnit::new("myapp.service", false));
+        let mut worker = create_test_process_info(8080, 99991);
+        worker.unit = main.unit.clone();
+
+        let stopper = RecordingStopper(Default::default());
+        let results = KillCommand::kill_holders(
+            &[main, worker],
+            &stopper,
+            true,
+            KillScope::Process,
+            &Escalation::default(),
+            &Protection::default(),
+            true,
+            true,
+        )
+        .await;
+
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+        assert_eq!(*stopper.0.lock().unwrap(), vec!["myapp.service"]);
+    }
+
+    #[tokio::test]
+    async fn test_kill_holders_socket_activation() {
+        use crate::process::SystemdUnit;
+
+        // ソケットユニットは--stop-unitなしでも停止する（PID 1は殺さない）
+        let mut init = create_test_process_info(22, 1);
+        init.unit = Some(SystemdUnit::new("sshd.socket", false));
+        assert!(KillCommand::stops_unit(&init, false));
+
+        let stopper = RecordingStopper(Default::default());
+        let results = KillCommand::kill_holders(
+            &[init.clone()],
+            &stopper,
+            false,
+            KillScope::Process,
+            &Escalation::default(),
+            &Protection::default(),
+            true,
+            true,
+        )
+        .await;
+        assert!(results[0].1.is_ok());
+        assert_eq!(*stopper.0.lock().unwrap(), vec!["sshd.socket"]);
+
+        // ユニットが見つからない場合はPID 1にシグナルを送らずエラーにする
+        init.unit = None;
+        let results = KillCommand::kill_holders(
+            &[init],
+            &stopper,
+            true,
+            KillScope::Process,
+            &Escalation::default(),
+            &Protection::default(),
+            true,
+            true,
+        )
+        .await;
+        assert!(results[0].1.is_err());
+        assert_eq!(stopper.0.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_stops_unit_requires_opt_in_for_services() {
+        let mut process = create_test_process_info(8080, 1234);
+        assert!(!KillCommand::stops_unit(&process, true));
+
+        process.unit = Some(crate::process::SystemdUnit::new("myapp.service", false));
+        assert!(!KillCommand::stops_unit(&process, false));
+        assert!(KillCommand::stops_unit(&process, true));
+    }
+
+    /// 指定回数のスキャンまでポートを保持し続けるバックエンド
+    struct ReleasingBackend {
+        holder: ProcessInfo,
+        scans_until_release: usize,
+        scans: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl PortBackend for ReleasingBackend {
+        fn name(&self) -> &'static str {
+            "ReleasingBackend"
+        }
+
+        async fn list_processes(&self, _protocol: &str) -> Result<Vec<ProcessInfo>> {
+            let scans = self.scans.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if scans < self.scans_until_release {
+                Ok(vec![self.holder.clone()])
+            } else {
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_release() {
+        let target = [TargetSpec::from(Target::Port(PortTarget::new(8080)))];
+
+        // 2回目のスキャンまで保持され、その後解放される
+        let backend = ReleasingBackend {
+            holder: create_test_process_info(8080, 99990),
+            scans_until_release: 2,
+            scans: Default::default(),
+        };
+        let remaining =
+            KillCommand::wait_for_release(&backend, &target, "tcp", Duration::from_secs(5))
+                .await
+                .unwrap();
+        assert!(remaining[0].is_empty());
+        assert_eq!(backend.scans.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        // タイムアウトまで保持し続けたプロセスを返す
+        let backend = ReleasingBackend {
+            holder: create_test_process_info(8080, 99990),
+            scans_until_release: usize::MAX,
+            scans: Default::default(),
+        };
+        let started = Instant::now();
+        let remaining =
+            KillCommand::wait_for_release(&backend, &target, "tcp", Duration::from_millis(300))
+                .await
+                .unwrap();
+        assert_eq!(remaining[0].len(), 1);
+        assert_eq!(remaining[0][0].pid, 99990);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_plan_kill() {
+        use crate::process::SystemdUnit;
+
+        let protection = Protection::default();
+        let process = create_test_process_info(8080, 99990);
+        assert!(matches!(
+            KillCommand::plan_kill(&process, false, KillScope::Process, &protection).unwrap(),
+            KillAction::Signal { members, spared }
+                if members.len() == 1 && members[0].pid == 99990 && spared.is_empty()
+        ));
+
+        let mut init = create_test_process_info(22, 1);
+        assert!(KillCommand::plan_kill(&init, false, KillScope::Process, &protection).is_err());
+        init.unit = Some(SystemdUnit::new("sshd.socket", false));
+        assert!(matches!(
+            KillCommand::plan_kill(&init, false, KillScope::Process, &protection),
+            Ok(KillAction::StopUnit(unit)) if unit.name == "sshd.socket"
+        ));
+    }
+
+    #[test]
+    fn test_plan_kill_refuses_protected() {
+        use crate::process::SystemdUnit;
+
+        let mut sshd = create_test_process_info(22, 99990);
+        sshd.name = "sshd".to_string();
+        assert!(matches!(
+            KillCommand::plan_kill(&sshd, false, KillScope::Process, &Protection::default()),
+            Err(crate::Error::Protected { pid: 99990, .. })
+        ));
+        // --stop-unitでも保護されたサービスは止めない
+        sshd.unit = Some(SystemdUnit::new("ssh.service", false));
+        assert!(
+            KillCommand::plan_kill(&sshd, true, KillScope::Process, &Protection::default())
+                .is_err()
+        );
+
+        let allow = Protection {
+            allow: true,
+            ..Protection::default()
+        };
+        assert!(matches!(
+            KillCommand::plan_kill(&sshd, true, KillScope::Process, &allow),
+            Ok(KillAction::StopUnit(_))
+        ));
+
+        let protection = Protection {
+            patterns: vec!["port:8080".parse().unwrap()],
+            allow: false,
+        };
+        let app = create_test_process_info(8080, 99991);
+        assert!(KillCommand::plan_kill(&app, false, KillScope::Process, &protection).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tree_kill_spares_protected_members() {
+        let mut shell = std::process::Command::new("sh")
+            .args(["-c", "sleep 30 & wait"])
+            .spawn()
+            .unwrap();
+        let sleep = (0..100)
+            .find_map(|_| {
+                let child = tree::all_stats()
+                    .into_iter()
+                    .find(|s| s.ppid == shell.id() && s.name == "sleep");
+                if child.is_none() {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                child
+            })
+            .unwrap();
+
+        let holder = ProcessInfo::new(shell.id(), "sh", 8080, "tcp");
+        let plan = |patterns: &[&str]| {
+            let protection = Protection {
+                patterns: patterns.iter().map(|p| p.parse().unwrap()).collect(),
+                allow: false,
+            };
+            match KillCommand::plan_kill(&holder, false, KillScope::Tree, &protection) {
+                Ok(KillAction::Signal { members, spared }) => (
+                    members.iter().map(|m| m.pid).collect::<Vec<_>>(),
+                    spared
+                        .iter()
+                        .filter_map(|e| match e {
+                            crate::Error::Protected { pid, .. } => Some(*pid),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+                other => panic!("Expected a signal plan, got {other:?}"),
+            }
+        };
+        let (unprotected, none_spared) = plan(&[]);
+        let (protected, spared) = plan(&["name:sleep"]);
+        let _ = std::process::Command::new("kill")
+            .arg(sleep.pid.to_string())
+            .status();
+        let _ = shell.kill();
+        let _ = shell.wait();
+
+        assert!(unprotected.contains(&sleep.pid));
+        assert!(none_spared.is_empty());
+        assert!(!protected.contains(&sleep.pid));
+        assert!(protected.contains(&holder.pid));
+        // 保護されたメンバーは skipped として報告される
+        assert_eq!(spared, vec![sleep.pid]);
+    }
+
+    #[test]
+    fn test_signals_json_follows_escalation() {
+        let escalation: Escalation = "INT:2s,TERM:10s,KILL".parse().unwrap();
+        let signals = KillCommand::signals_json(&[201, 202], &escalation);
+        assert_eq!(
+            signals,
+            serde_json::json!([
+                { "signal": "SIGINT", "number": 2, "pids": [201, 202], "wait_ms": 2000 },
+                { "signal": "SIGTERM", "number": 15, "pids": [201, 202], "wait_ms": 10000 },
+                { "signal": "SIGKILL", "number": 9, "pids": [201, 202], "wait_ms": 1000 }
+            ])
+        );
+        assert_eq!(
+            KillCommand::describe_signals(&[201, 202], &escalation).len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_does_not_signal() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let backend = ReleasingBackend {
+            holder: create_test_process_info(8080, child.id()),
+            scans_until_release: usize::MAX,
+            scans: Default::default(),
+        };
+        let options = KillOptions {
+            force: true,
+            dry_run: true,
+            quiet: true,
+            ..KillOptions::default()
+        };
+
+        KillCommand::execute_with_backend(
+            &backend,
+            &Target::Port(PortTarget::new(8080)),
+            "tcp",
+            &options,
+        )
+        .await
+        .unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    struct ScanCountingBackend {
+        holders: Vec<ProcessInfo>,
+        scans: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl PortBackend for ScanCountingBackend {
+        fn name(&self) -> &'static str {
+            "ScanCountingBackend"
+        }
+
+        async fn list_processes(&self, _protocol: &str) -> Result<Vec<ProcessInfo>> {
+            self.scans.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(self.holders.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kill_multiple_targets() {
+        let mut children: Vec<_> = (0..2)
+            .map(|_| {
+                std::process::Command::new("sleep")
+                    .arg("30")
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+        let backend = ScanCountingBackend {
+            holders: vec![
+                create_test_process_info(8080, children[0].id()),
+                create_test_process_info(8093, children[1].id()),
+                create_test_process_info(9229, 99990),
+            ],
+            scans: Default::default(),
+        };
+        let targets: Vec<TargetSpec> = ["8080", "8090-8099", "3000"]
+            .iter()
+            .map(|t| t.parse().unwrap())
+            .collect();
+        let options = KillOptions {
+            force: true,
+            quiet: true,
+            ..KillOptions::default()
+        };
+
+        // 使われていないポートがあれば、他を終了した上でエラーを返す
+        let result = KillCommand::execute_targets(&backend, &targets, "tcp", &options).await;
+        assert!(matches!(result, Err(crate::Error::PortNotFound(3000))));
+        assert_eq!(backend.scans.load(std::sync::atomic::Ordering::SeqCst), 1);
+        for child in &mut children {
+            assert!(child.wait().unwrap().signal().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kill_by_filter() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let mut other = create_test_process_info(8081, 99990);
+        other.working_directory = "/srv/other".to_string();
+        let backend = ScanCountingBackend {
+            holders: vec![create_test_process_info(8080, child.id()), other],
+            scans: Default::default(),
+        };
+        let range = [TargetSpec::Range {
+            start: 1,
+            end: u16::MAX,
+            family: None,
+        }];
+        let mut options = KillOptions {
+            force: true,
+            quiet: true,
+            filter: ProcessFilter {
+                cwd: Some("/home/user/project".into()),
+                ..ProcessFilter::default()
+            },
+            ..KillOptions::default()
+        };
+
+        KillCommand::execute_targets(&backend, &range, "tcp", &options)
+            .await
+            .unwrap();
+        assert!(child.wait().unwrap().signal().is_some());
+
+        options.filter.name = Some("nginx".to_string());
+        let result = KillCommand::execute_targets(&backend, &range, "tcp", &options).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("matches name nginx, cwd /home/user/project"));
+    }
+
+    #[tokio::test]
+    async fn test_json_filter_kill_requires_yes() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let backend = ScanCountingBackend {
+            holders: vec![create_test_process_info(8080, child.id())],
+            scans: Default::default(),
+        };
+        let range = [TargetSpec::Range {
+            start: 1,
+            end: u16::MAX,
+            family: None,
+        }];
+        let mut options = KillOptions {
+            json: true,
+            filter: ProcessFilter {
+                name: Some("test".to_string()),
+                ..ProcessFilter::default()
+            },
+            ..KillOptions::default()
+        };
+
+        let result = KillCommand::execute_targets(&backend, &range, "tcp", &options).await;
+        assert!(result.unwrap_err().to_string().contains("Use --yes"));
+        assert!(child.try_wait().unwrap().is_none());
+
+        // 何も終了しないドライランは確認なしで計画を出力する
+        options.dry_run = true;
+        KillCommand::execute_targets(&backend, &range, "tcp", &options)
+            .await
+            .unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_text_dry_run_without_terminal() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let backend = ScanCountingBackend {
+            holders: vec![create_test_process_info(8080, child.id())],
+            scans: Default::default(),
+        };
+        let range = [TargetSpec::Range {
+            start: 1,
+            end: u16::MAX,
+            family: None,
+        }];
+        // テキスト出力のドライランは stdin が端末でなくても --yes を求めない
+        let options = KillOptions {
+            dry_run: true,
+            quiet: true,
+            ..KillOptions::default()
+        };
+
+        KillCommand::execute_targets(&backend, &range, "tcp", &options)
+            .await
+            .unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_target_result_json() {
+        let holder = create_test_process_info(8093, 4242);
+        let range: TargetSpec = "8090-8099".parse().unwrap();
+        let results = vec![(
+            holder.clone(),
+            Ok(Killed {
+                pids: vec![4242],
+                spared: Vec::new(),
+            }),
+        )];
+
+        let json = KillCommand::target_result_json(
+            &range,
+            &[holder],
+            &[],
+            &results,
+            false,
+            "tcp",
+            &ProcessFilter::default(),
+        );
+        assert_eq!(
+            json["range"],
+            serde_json::json!({ "start": 8090, "end": 8099 })
+        );
+        assert_eq!(json["status"], "killed");
+        assert_eq!(
+            json["processes"][0]["killed_pids"],
+            serde_json::json!([4242])
+        );
+
+        let port: TargetSpec = "3000".parse().unwrap();
+        let json = KillCommand::target_result_json(
+            &port,
+            &[],
+            &[],
+            &results,
+            false,
+            "tcp",
+            &ProcessFilter::default(),
+        );
+        assert_eq!(json["status"], "not_found");
+        assert_eq!(json["processes"], serde_json::json!([]));
+    }
 }
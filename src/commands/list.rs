@@ -1,19 +1,121 @@
-use crate::{port::PortManager, process::ProcessManager, Result};
+use crate::{
+    commands::{
+        kill::{KillAction, KillScope},
+        KillCommand,
+    },
+    port::{
+        create_backend, BackendKind, IpFamily, PortBackend, PortRegistry, ProcessInfo, TcpState,
+    },
+    process::{
+        tree::{self, Ancestor},
+        Escalation, ProcessManager, Protection,
+    },
+    Result,
+};
 use colored::Colorize;
 use dialoguer::{Confirm, MultiSelect};
 use indicatif::{ProgressBar, ProgressStyle};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 #[derive(Debug)]
 pub struct ListOptions {
     pub ports_range: Option<String>,
     pub filter: Option<String>,
+    /// Only show processes running in this directory or below it (`--cwd`)
+    pub cwd: Option<PathBuf>,
+    /// Only show processes whose executable is this path or lies below it (`--path`)
+    pub path: Option<PathBuf>,
     pub sort: String,
     pub protocol: String,
     pub kill: bool,
     pub quiet: bool,
     pub json: bool,
     pub watch: bool,
+    /// How often watch mode refreshes (`--interval`)
+    pub watch_interval: Duration,
+    /// Only show sockets of this address family (`-4` / `-6`)
+    pub family: Option<IpFamily>,
+    /// TCP states to show (`--state`); UDP sockets are always shown
+    pub states: Vec<TcpState>,
+    /// Show each listener's parent process chain (`--tree`)
+    pub tree: bool,
+    /// Signals sent to processes killed from the selection
+    pub escalation: Escalation,
+    /// Report the kills of the selection instead of carrying them out
+    pub dry_run: bool,
+    /// Processes the selection may not kill without `--allow-protected`
+    pub protection: Protection,
+    /// Ports declared in `[registry]`, labelled in the table
+    pub registry: PortRegistry,
+}
+
+/// Which processes `list` shows and `kill --name/--cwd/--path/--range` kills
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessFilter {
+    /// Part of the process name, case-insensitive
+    pub name: Option<String>,
+    /// Directory containing the working directory of the process
+    pub cwd: Option<PathBuf>,
+    /// The executable, or a directory containing it
+    pub path: Option<PathBuf>,
+    /// Inclusive port range
+    pub ports: Option<(u16, u16)>,
+}
+
+impl ProcessFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let name = self
+            .name
+            .as_ref()
+            .is_none_or(|name| process.name.to_lowercase().contains(&name.to_lowercase()));
+        let cwd = self
+            .cwd
+            .as_ref()
+            .is_none_or(|cwd| Path::new(&process.working_directory).starts_with(cwd));
+        let path = self
+            .path
+            .as_ref()
+            .is_none_or(|path| Path::new(&process.executable_path).starts_with(path));
+        let ports = self
+            .ports
+            .is_none_or(|(start, end)| (start..=end).contains(&process.port));
+        name && cwd && path && ports
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "cwd": self.cwd,
+            "path": self.path,
+            "ports": self.ports.map(|(start, end)| serde_json::json!({ "start": start, "end": end })),
+        })
+    }
+}
+
+impl fmt::Display for ProcessFilter {
+    /// `name node, cwd /home/me/shop, ports 3000-9000`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(name) = &self.name {
+            parts.push(format!("name {name}"));
+        }
+        if let Some(cwd) = &self.cwd {
+            parts.push(format!("cwd {}", cwd.display()));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("path {}", path.display()));
+        }
+        if let Some((start, end)) = self.ports {
+            parts.push(format!("ports {start}-{end}"));
+        }
+        f.write_str(&parts.join(", "))
+    }
 }
 
 pub struct ListCommand;
@@ -33,36 +135,73 @@ impl ListCommand {
         let options = ListOptions {
             ports_range,
             filter,
+            cwd: None,
+            path: None,
             sort: sort.to_string(),
             protocol: protocol.to_string(),
             kill,
             quiet,
             json,
             watch,
+            watch_interval: Duration::from_secs(1),
+            family: None,
+            states: vec![TcpState::Listen],
+            tree: false,
+            escalation: Escalation::default(),
+            dry_run: false,
+            protection: Protection::default(),
+            registry: PortRegistry::default(),
         };
 
         Self::execute_with_options(options).await
     }
 
     pub async fn execute_with_options(options: ListOptions) -> Result<()> {
+        let backend = create_backend(BackendKind::default());
+        Self::execute_with_backend(options, backend.as_ref()).await
+    }
+
+    /// Execute the list command using the given port backend
+    pub async fn execute_with_backend(
+        options: ListOptions,
+        backend: &dyn PortBackend,
+    ) -> Result<()> {
+        // kill --name/--cwd/--path/--range と同じ絞り込み
+        let filter = ProcessFilter {
+            name: options.filter,
+            cwd: options.cwd,
+            path: options.path,
+            ports: options
+                .ports_range
+                .map(|range| Self::parse_port_range(&range))
+                .transpose()?,
+        };
         if options.watch {
-            // Use simple watch mode with PortManager
             Self::execute_simple_watch_mode(
+                backend,
                 &options.protocol,
-                options.ports_range,
-                options.filter,
+                &filter,
                 &options.sort,
+                options.family,
+                &options.states,
+                options.watch_interval,
                 options.quiet,
             )
             .await
         } else {
-            // Use lightweight PortManager for single runs
             Self::execute_single_run_simple(
-                options.ports_range,
-                options.filter,
+                backend,
+                &filter,
                 &options.sort,
                 &options.protocol,
+                options.family,
+                &options.states,
+                options.tree,
                 options.kill,
+                &options.escalation,
+                options.dry_run,
+                &options.protection,
+                &options.registry,
                 options.quiet,
                 options.json,
             )
@@ -72,11 +211,18 @@ impl ListCommand {
 
     #[allow(clippy::too_many_arguments)]
     async fn execute_single_run_simple(
-        ports_range: Option<String>,
-        filter: Option<String>,
+        backend: &dyn PortBackend,
+        filter: &ProcessFilter,
         sort: &str,
         protocol: &str,
+        family: Option<IpFamily>,
+        states: &[TcpState],
+        tree: bool,
         kill: bool,
+        escalation: &Escalation,
+        dry_run: bool,
+        protection: &Protection,
+        registry: &PortRegistry,
         quiet: bool,
         json: bool,
     ) -> Result<()> {
@@ -96,22 +242,18 @@ impl ListCommand {
             None
         };
 
-        // Use lightweight PortManager directly
-        let manager = PortManager::new();
-
         // Create progress callback for the spinner
-        let mut processes = if let Some(ref pb) = spinner {
+        let mut processes = if !TcpState::is_listen_only(states) {
+            backend.list_sockets(protocol, states).await?
+        } else if let Some(ref pb) = spinner {
             let pb_clone = pb.clone();
-            manager
-                .list_processes_with_progress(
-                    protocol,
-                    Some(move |msg: &str| {
-                        pb_clone.set_message(msg.to_string());
-                    }),
-                )
+            backend
+                .list_processes_with_progress(protocol, &move |msg: &str| {
+                    pb_clone.set_message(msg.to_string());
+                })
                 .await?
         } else {
-            manager.list_processes(protocol).await?
+            backend.list_processes(protocol).await?
         };
 
         // Clear spinner on completion
@@ -119,15 +261,20 @@ impl ListCommand {
             pb.finish_with_message(format!("{} Port scan complete", "✓".green()));
         }
 
-        // ポート範囲フィルタリング
-        if let Some(range) = ports_range {
-            let (start, end) = Self::parse_port_range(&range)?;
-            processes.retain(|p| p.port >= start && p.port <= end);
-        }
+        // ポート範囲・プロセス名・ディレクトリでのフィルタリング
+        processes.retain(|p| filter.matches(p));
+
+        // アドレスファミリーフィルタリング
+        processes.retain(|p| p.matches_family(family));
 
-        // プロセス名フィルタリング
-        if let Some(filter_name) = filter {
-            processes.retain(|p| p.name.to_lowercase().contains(&filter_name.to_lowercase()));
+        // コンテナ情報の付与
+        crate::port::attach_containers(&mut processes);
+        crate::process::systemd::attach_units(&mut processes).await;
+        crate::process::tree::attach_start_times(&mut processes);
+        crate::port::attach_leases(&mut processes);
+        crate::port::attach_registry(&mut processes, registry);
+        if tree {
+            crate::process::tree::attach_ancestors(&mut processes);
         }
 
         // ソート
@@ -145,7 +292,7 @@ impl ListCommand {
                 "processes": processes,
                 "performance": {
                     "mode": "simple",
-                    "manager": "PortManager"
+                    "manager": backend.name()
                 }
             });
             println!("{}", serde_json::to_string_pretty(&json_output)?);
@@ -159,20 +306,25 @@ impl ListCommand {
             }
 
             if kill {
+                // Kernel-owned sockets (TIME_WAIT etc.) have no process to kill
+                processes.retain(|p| p.pid != 0);
+                if !TcpState::is_listen_only(states) {
+                    processes = KillCommand::unique_holders(processes);
+                }
                 if processes.is_empty() {
                     if !quiet {
                         println!("{} No killable processes found", "○".blue());
                     }
                     return Ok(());
                 }
-                Self::interactive_kill(processes, quiet).await?;
+                Self::interactive_kill(processes, escalation, dry_run, protection, quiet).await?;
             }
         }
 
         Ok(())
     }
 
-    pub(crate) fn parse_port_range(range: &str) -> Result<(u16, u16)> {
+    pub fn parse_port_range(range: &str) -> Result<(u16, u16)> {
         if let Some((start_str, end_str)) = range.split_once('-') {
             let start = start_str.parse::<u16>().map_err(|_| {
                 crate::Error::InvalidPort(format!("Invalid start port: {start_str}"))
@@ -195,32 +347,128 @@ impl ListCommand {
         }
     }
 
-    pub(crate) fn print_table(processes: &[crate::port::ProcessInfo]) {
+    pub(crate) fn print_table(processes: &[ProcessInfo]) {
         println!("{}", "Ports in use:".bold().green());
         println!();
 
-        println!(
-            "{:<8} {:<12} {:<20} {:<10} {:<40} {}",
+        // UNIX socket paths are usually longer than IP addresses
+        let address_width = processes
+            .iter()
+            .map(|p| p.display_address().chars().count() + 2)
+            .max()
+            .unwrap_or(0)
+            .clamp(18, 50);
+        // Only shown when scanning other network namespaces
+        let netns_width = if processes.iter().any(|p| p.netns.is_some()) {
+            13
+        } else {
+            0
+        };
+        let unit_width = processes
+            .iter()
+            .filter_map(|p| p.unit.as_ref())
+            .map(|unit| unit.to_string().chars().count() + 2)
+            .max()
+            .map_or(0, |width| width.clamp(8, 32));
+        // Only shown when `kilar hold` keeps a port bound
+        let lease_width = processes
+            .iter()
+            .filter_map(|p| p.lease.as_ref())
+            .map(|lease| lease.to_string().chars().count() + 2)
+            .max()
+            .map_or(0, |width| width.clamp(8, 32));
+        // Only shown when the configuration declares the listed ports
+        let registry_width = processes
+            .iter()
+            .filter_map(|p| p.registry.as_ref())
+            .map(|name| name.chars().count() + 2)
+            .max()
+            .map_or(0, |width| width.clamp(10, 32));
+
+        if netns_width > 0 {
+            print!("{:<netns_width$}", "NETNS".cyan().bold());
+        }
+        print!(
+            "{:<8} {:<12} {:<address_width$} {:<12} {:<20} {:<10} {:<40} ",
             "PORT".cyan().bold(),
             "PROTOCOL".cyan().bold(),
+            "ADDRESS".cyan().bold(),
+            "STATE".cyan().bold(),
             "PROCESS".cyan().bold(),
             "PID".cyan().bold(),
             "PATH".cyan().bold(),
-            "COMMAND".cyan().bold()
         );
-        println!("{}", "-".repeat(130));
+        // Only shown when a listener is managed by systemd
+        if unit_width > 0 {
+            print!("{:<unit_width$} ", "UNIT".cyan().bold());
+        }
+        if lease_width > 0 {
+            print!("{:<lease_width$} ", "LEASE".cyan().bold());
+        }
+        if registry_width > 0 {
+            print!("{:<registry_width$} ", "REGISTRY".cyan().bold());
+        }
+        println!("{}", "COMMAND".cyan().bold());
+        println!(
+            "{}",
+            "-".repeat(
+                144 + address_width + netns_width + unit_width + lease_width + registry_width
+            )
+        );
 
         for process in processes {
             let display_path = Self::get_display_path(process);
-            println!(
-                "{:<8} {:<12} {:<20} {:<10} {:<40} {}",
-                process.port.to_string().white(),
+            if netns_width > 0 {
+                let netns = process.netns.map_or("-".to_string(), |ns| ns.to_string());
+                print!("{netns:<netns_width$}");
+            }
+            print!(
+                "{:<8} {:<12} {:<address_width$} {:<12} {:<20} {:<10} {:<40} ",
+                Self::format_port(process).white(),
                 process.protocol.to_uppercase().green(),
+                process
+                    .display_address()
+                    .truncate_with_ellipsis(address_width - 2),
+                process.state.map_or("-", |state| state.as_str()),
                 process.name.truncate_with_ellipsis(18).yellow(),
-                process.pid.to_string().blue(),
+                Self::format_pid(process.pid).blue(),
                 display_path.truncate_with_ellipsis(38).cyan(),
-                process.command.truncate_with_ellipsis(40).dimmed()
             );
+            if unit_width > 0 {
+                let unit = process
+                    .unit
+                    .as_ref()
+                    .map_or("-".to_string(), |unit| unit.to_string());
+                print!(
+                    "{:<unit_width$} ",
+                    unit.truncate_with_ellipsis(unit_width - 2).magenta()
+                );
+            }
+            if lease_width > 0 {
+                let lease = process
+                    .lease
+                    .as_ref()
+                    .map_or("-".to_string(), |lease| lease.to_string());
+                print!(
+                    "{:<lease_width$} ",
+                    lease.truncate_with_ellipsis(lease_width - 2).magenta()
+                );
+            }
+            if registry_width > 0 {
+                let name = process.registry.clone().unwrap_or("-".to_string());
+                print!(
+                    "{:<registry_width$} ",
+                    name.truncate_with_ellipsis(registry_width - 2).magenta()
+                );
+            }
+            println!("{}", process.command.truncate_with_ellipsis(40).dimmed());
+            if let Some(ancestors) = process.ancestors.as_ref().filter(|a| !a.is_empty()) {
+                println!(
+                    "  {} {}",
+                    "↳".dimmed(),
+                    Self::format_ancestry(process, ancestors)
+                );
+            }
         }
 
         println!();
@@ -231,7 +479,13 @@ impl ListCommand {
         );
     }
 
-    async fn interactive_kill(processes: Vec<crate::port::ProcessInfo>, quiet: bool) -> Result<()> {
+    async fn interactive_kill(
+        processes: Vec<crate::port::ProcessInfo>,
+        escalation: &Escalation,
+        dry_run: bool,
+        protection: &Protection,
+        quiet: bool,
+    ) -> Result<()> {
         if !quiet {
             println!("{}", "Select processes to kill:".bold().yellow());
             println!();
@@ -243,9 +497,10 @@ impl ListCommand {
             .map(|p| {
                 let display_path = Self::get_display_path(p);
                 format!(
-                    "Port {} ({}) | {} (PID:{}) | Path: {} | Cmd: {}",
-                    p.port.to_string().white(),
+                    "Port {} ({} {}) | {} (PID:{}) | Path: {} | Cmd: {}",
+                    Self::format_port(p).white(),
                     p.protocol.to_uppercase().green(),
+                    p.display_address(),
                     p.name.yellow(),
                     p.pid.to_string().blue(),
                     display_path.truncate_with_ellipsis(45).cyan(),
@@ -314,7 +569,10 @@ impl ListCommand {
         }
 
         // プロセス終了実行
-        Self::kill_selected_processes(processes, selections, quiet).await?;
+        Self::kill_selected_processes(
+            processes, selections, escalation, dry_run, protection, quiet,
+        )
+        .await?;
 
         Ok(())
     }
@@ -322,8 +580,34 @@ impl ListCommand {
     async fn kill_selected_processes(
         processes: Vec<crate::port::ProcessInfo>,
         selections: Vec<usize>,
+        escalation: &Escalation,
+        dry_run: bool,
+        protection: &Protection,
         quiet: bool,
     ) -> Result<()> {
+        if dry_run {
+            let plans: Vec<_> = selections
+                .iter()
+                .map(|&idx| {
+                    let process = &processes[idx];
+                    (
+                        process.clone(),
+                        protection.check(process).map(|()| KillAction::Signal {
+                            members: vec![process.clone()],
+                            spared: Vec::new(),
+                        }),
+                    )
+                })
+                .collect();
+            if !quiet {
+                KillCommand::print_plan(&plans, KillScope::Process, escalation);
+            }
+            return match plans.into_iter().find_map(|(_, plan)| plan.err()) {
+                Some(e) => Err(e),
+                None => Ok(()),
+            };
+        }
+
         let process_manager = ProcessManager::new();
         let mut success_count = 0;
         let mut errors = Vec::new();
@@ -331,7 +615,10 @@ impl ListCommand {
         for &idx in &selections {
             let process = &processes[idx];
 
-            match process_manager.kill_process(process.pid).await {
+            let result = process_manager
+                .kill_guarded(process, protection, escalation)
+                .await;
+            match result {
                 Ok(()) => {
                     success_count += 1;
                     if !quiet {
@@ -381,11 +668,15 @@ impl ListCommand {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn execute_simple_watch_mode(
+        backend: &dyn PortBackend,
         protocol: &str,
-        ports_range: Option<String>,
-        filter: Option<String>,
+        filter: &ProcessFilter,
         sort: &str,
+        family: Option<IpFamily>,
+        states: &[TcpState],
+        display_interval: Duration,
         quiet: bool,
     ) -> Result<()> {
         if !quiet {
@@ -396,23 +687,15 @@ impl ListCommand {
             println!();
         }
 
-        let manager = PortManager::new();
-        let display_interval = Duration::from_secs(1);
-
         let result = loop {
             tokio::select! {
                 _ = tokio::time::sleep(display_interval) => {
-                    let mut processes = manager.list_processes(protocol).await?;
+                    let mut processes = backend.list_sockets(protocol, states).await?;
 
                     // Apply same filters as single run
-                    if let Some(ref range) = ports_range {
-                        let (start, end) = Self::parse_port_range(range)?;
-                        processes.retain(|p| p.port >= start && p.port <= end);
-                    }
+                    processes.retain(|p| filter.matches(p));
 
-                    if let Some(ref filter_name) = filter {
-                        processes.retain(|p| p.name.to_lowercase().contains(&filter_name.to_lowercase()));
-                    }
+                    processes.retain(|p| p.matches_family(family));
 
                     match sort {
                         "port" => processes.sort_by_key(|p| p.port),
@@ -459,38 +742,50 @@ trait StringExt {
 }
 
 impl StringExt for String {
+    /// Shorten to `max_len` characters; counting bytes would split
+    /// multi-byte characters in labels and paths
     fn truncate_with_ellipsis(&self, max_len: usize) -> String {
-        if self.len() <= max_len {
+        if self.chars().count() <= max_len {
             self.clone()
         } else {
-            format!("{}...", &self[..max_len.saturating_sub(3)])
+            let kept: String = self.chars().take(max_len.saturating_sub(3)).collect();
+            format!("{kept}...")
         }
     }
 }
 
 impl ListCommand {
-    fn get_display_path(process_info: &crate::port::ProcessInfo) -> String {
-        // Prefer working directory for development processes (when it's not root)
-        if process_info.working_directory != "/" && process_info.working_directory != "Unknown" {
-            // Check if this is likely a development process based on the executable or command
-            let is_dev_process = process_info.executable_path.contains("/node")
-                || process_info.executable_path.contains("/python")
-                || process_info.executable_path.contains("/ruby")
-                || process_info.executable_path.contains("/java")
-                || process_info.command.contains("npm")
-                || process_info.command.contains("yarn")
-                || process_info.command.contains("pnpm")
-                || process_info.command.contains("next")
-                || process_info.command.contains("serve")
-                || process_info.command.contains("dev");
-
-            if is_dev_process {
-                return process_info.working_directory.clone();
-            }
+    fn get_display_path(process_info: &ProcessInfo) -> String {
+        crate::port::display_path(process_info)
+    }
+
+    /// Parent chain down to `process`, e.g. `bash (100) → npm (201) → node (203)`
+    pub(crate) fn format_ancestry(process: &ProcessInfo, ancestors: &[Ancestor]) -> String {
+        tree::format_chain(
+            ancestors
+                .iter()
+                .rev()
+                .map(|a| (a.name.as_str(), a.pid))
+                .chain([(process.name.as_str(), process.pid)]),
+        )
+    }
+
+    /// PORT column value; UNIX sockets have no port
+    fn format_port(process: &ProcessInfo) -> String {
+        if process.protocol == "unix" {
+            "-".to_string()
+        } else {
+            process.port.to_string()
         }
+    }
 
-        // Fallback to executable path for system processes
-        process_info.executable_path.clone()
+    /// PID column value; kernel-owned sockets have no process
+    fn format_pid(pid: u32) -> String {
+        if pid == 0 {
+            "-".to_string()
+        } else {
+            pid.to_string()
+        }
     }
 }
 
@@ -510,6 +805,47 @@ mod tests {
         assert_eq!(result, (8080, 8080));
     }
 
+    #[test]
+    fn test_process_filter() {
+        let process = ProcessInfo {
+            pid: 4242,
+            name: "node".to_string(),
+            command: "node server.js".to_string(),
+            executable_path: "/usr/local/bin/node".to_string(),
+            working_directory: "/home/user/work/shop/web".to_string(),
+            port: 3000,
+            protocol: "tcp".to_string(),
+            address: "127.0.0.1".to_string(),
+            ..Default::default()
+        };
+
+        let filter = ProcessFilter {
+            name: Some("NODE".to_string()),
+            cwd: Some(PathBuf::from("/home/user/work/shop")),
+            path: Some(PathBuf::from("/usr/local/bin")),
+            ports: Some((3000, 9000)),
+        };
+        assert!(filter.matches(&process));
+        assert_eq!(
+            filter.to_string(),
+            "name NODE, cwd /home/user/work/shop, path /usr/local/bin, ports 3000-9000"
+        );
+
+        // パスはコンポーネント単位で比較する
+        let filter = ProcessFilter {
+            cwd: Some(PathBuf::from("/home/user/work/sh")),
+            ..ProcessFilter::default()
+        };
+        assert!(!filter.matches(&process));
+
+        let filter = ProcessFilter {
+            ports: Some((4000, 9000)),
+            ..ProcessFilter::default()
+        };
+        assert!(!filter.matches(&process));
+        assert!(ProcessFilter::default().matches(&process));
+    }
+
     #[test]
     fn test_parse_port_range_invalid() {
         let result = ListCommand::parse_port_range("4000-3000");
@@ -550,6 +886,18 @@ mod tests {
         assert_eq!(s.truncate_with_ellipsis(5), "to...");
     }
 
+    #[test]
+    fn test_truncate_non_ascii() {
+        let label = "aシャード番号テストの長いラベル、三十バイトを超える".to_string();
+        assert_eq!(label.truncate_with_ellipsis(8), "aシャード...");
+        assert_eq!("ポート".to_string().truncate_with_ellipsis(3), "ポート");
+
+        let mut held = ProcessInfo::new(4242, "kilar", 3000, "tcp");
+        held.lease = Some(crate::port::Lease::hold(3000, "tcp", Some(label), None));
+        held.registry = Some("フロントエンド開発サーバー用のポート番号".to_string());
+        ListCommand::print_table(&[held]);
+    }
+
     #[test]
     fn test_string_truncate_with_ellipsis_edge_cases() {
         // Empty string
@@ -577,17 +925,34 @@ mod tests {
         assert_eq!(s.truncate_with_ellipsis(4), "h...");
     }
 
+    #[test]
+    fn test_format_pid() {
+        assert_eq!(ListCommand::format_pid(1234), "1234");
+        // カーネルが保持するソケット（TIME_WAITなど）はPIDなし
+        assert_eq!(ListCommand::format_pid(0), "-");
+    }
+
     #[test]
     fn test_list_options_creation() {
         let options = ListOptions {
             ports_range: Some("3000-4000".to_string()),
             filter: Some("node".to_string()),
+            cwd: None,
+            path: None,
             sort: "port".to_string(),
             protocol: "tcp".to_string(),
             kill: false,
             quiet: false,
             json: false,
             watch: false,
+            watch_interval: Duration::from_secs(1),
+            family: None,
+            states: vec![TcpState::Listen],
+            tree: false,
+            escalation: Escalation::default(),
+            dry_run: false,
+            protection: Protection::default(),
+            registry: PortRegistry::default(),
         };
 
         assert_eq!(options.ports_range, Some("3000-4000".to_string()));
@@ -605,12 +970,22 @@ mod tests {
         let options = ListOptions {
             ports_range: None,
             filter: None,
+            cwd: None,
+            path: None,
             sort: "port".to_string(),
             protocol: "all".to_string(),
             kill: true,
             quiet: true,
             json: true,
             watch: true,
+            watch_interval: Duration::from_secs(1),
+            family: Some(IpFamily::V6),
+            states: TcpState::ALL.to_vec(),
+            tree: false,
+            escalation: Escalation::default(),
+            dry_run: false,
+            protection: Protection::default(),
+            registry: PortRegistry::default(),
         };
 
         // Test Debug trait
@@ -1,7 +1,21 @@
 pub mod check;
+pub mod clients;
+pub mod config;
+pub mod free;
+pub mod hold;
 pub mod kill;
 pub mod list;
+pub mod project;
+pub mod registry;
+pub mod wait;
 
 pub use check::CheckCommand;
+pub use clients::ClientsCommand;
+pub use config::ConfigCommand;
+pub use free::FreeCommand;
+pub use hold::HoldCommand;
 pub use kill::KillCommand;
 pub use list::ListCommand;
+pub use project::ProjectCommand;
+pub use registry::RegistryCommand;
+pub use wait::WaitCommand;
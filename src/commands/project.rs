@@ -0,0 +1,156 @@
+use crate::{
+    commands::{kill::KillOptions, KillCommand},
+    port::{discover_project_ports, PortBackend, ProcessInfo, ProjectPort, Target, TargetSpec},
+    Result,
+};
+use colored::Colorize;
+use std::path::Path;
+
+/// Command for checking, and freeing, the ports a project needs.
+pub struct ProjectCommand;
+
+impl ProjectCommand {
+    /// Discover the ports of the project in `dir`, check each one and print
+    /// a status table.
+    ///
+    /// With `kill`, the processes holding those ports are then killed as by
+    /// `kilar kill` with `options`.
+    pub async fn execute_with_backend(
+        backend: &dyn PortBackend,
+        dir: &Path,
+        protocol: &str,
+        kill: bool,
+        options: &KillOptions,
+    ) -> Result<()> {
+        let KillOptions { quiet, json, .. } = *options;
+        let ports = discover_project_ports(dir);
+        if ports.is_empty() {
+            if json {
+                let json_output = serde_json::json!({
+                    "directory": dir,
+                    "protocol": protocol,
+                    "ports": []
+                });
+                println!("{}", serde_json::to_string_pretty(&json_output)?);
+            } else if !quiet {
+                println!(
+                    "{} No ports found in package.json, compose files, .env, vite.config or config/puma.rb",
+                    "○".blue()
+                );
+            }
+            return Ok(());
+        }
+
+        let processes = backend.list_processes(protocol).await?;
+        let statuses: Vec<(&ProjectPort, Vec<ProcessInfo>)> = ports
+            .iter()
+            .map(|port| {
+                let holders = KillCommand::unique_holders(
+                    processes
+                        .iter()
+                        .filter(|p| p.port == port.port)
+                        .cloned()
+                        .collect(),
+                );
+                (port, holders)
+            })
+            .collect();
+
+        // --json --kill は kill の結果だけを出力する
+        if json && !kill {
+            let ports_json: Vec<serde_json::Value> = statuses
+                .iter()
+                .map(|(port, holders)| {
+                    serde_json::json!({
+                        "port": port.port,
+                        "sources": port.sources,
+                        "status": if holders.is_empty() { "free" } else { "occupied" },
+                        "processes": holders
+                            .iter()
+                            .map(|p| serde_json::json!({ "pid": p.pid, "name": p.name }))
+                            .collect::<Vec<_>>(),
+                    })
+                })
+                .collect();
+            let json_output = serde_json::json!({
+                "directory": dir,
+                "protocol": protocol,
+                "ports": ports_json
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        } else if !quiet && !json {
+            Self::print_table(dir, &statuses);
+        }
+
+        if !kill {
+            return Ok(());
+        }
+        let targets: Vec<TargetSpec> = statuses
+            .iter()
+            .filter(|(_, holders)| !holders.is_empty())
+            .map(|(port, _)| TargetSpec::from(Target::from(port.port)))
+            .collect();
+        if targets.is_empty() {
+            if json {
+                let json_output = serde_json::json!({
+                    "action": "none",
+                    "message": "Every project port is free"
+                });
+                println!("{}", serde_json::to_string_pretty(&json_output)?);
+            } else if !quiet {
+                println!();
+                println!("{} Every project port is free", "✓".green());
+            }
+            return Ok(());
+        }
+        if !quiet && !json {
+            println!();
+        }
+        KillCommand::execute_targets(backend, &targets, protocol, options).await
+    }
+
+    fn print_table(dir: &Path, statuses: &[(&ProjectPort, Vec<ProcessInfo>)]) {
+        println!(
+            "{} {}",
+            "Project ports in".bold().green(),
+            dir.display().to_string().bold().green()
+        );
+        println!();
+        println!(
+            "{:<8} {:<10} {:<10} {:<20} {}",
+            "PORT".cyan().bold(),
+            "STATUS".cyan().bold(),
+            "PID".cyan().bold(),
+            "PROCESS".cyan().bold(),
+            "SOURCE".cyan().bold(),
+        );
+        println!("{}", "-".repeat(80));
+
+        for (port, holders) in statuses {
+            let sources = port.sources.join(", ");
+            let label = port.port.to_string();
+            match holders.as_slice() {
+                [] => println!(
+                    "{:<8} {:<10} {:<10} {:<20} {}",
+                    label.white(),
+                    "free".green(),
+                    "-",
+                    "-",
+                    sources.dimmed()
+                ),
+                holders => {
+                    for holder in holders {
+                        println!(
+                            "{:<8} {:<10} {:<10} {:<20} {}",
+                            label.white(),
+                            "in use".red(),
+                            holder.pid.to_string().blue(),
+                            holder.name.yellow(),
+                            sources.dimmed()
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
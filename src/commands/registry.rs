@@ -0,0 +1,194 @@
+use crate::{
+    commands::KillCommand,
+    port::{PortBackend, PortRegistry, ProcessInfo, RegistryEntry, RegistryStatus},
+    Result,
+};
+use colored::Colorize;
+
+/// Command for checking the ports declared in the `[registry]` section.
+pub struct RegistryCommand;
+
+impl RegistryCommand {
+    /// Report every registry entry as free, held by the expected process or
+    /// held by an impostor.
+    ///
+    /// Fails when an entry is held by an impostor, so that scripts can catch
+    /// a port taken by the wrong service.
+    pub async fn check(
+        backend: &dyn PortBackend,
+        registry: &PortRegistry,
+        protocol: &str,
+        quiet: bool,
+        json: bool,
+    ) -> Result<()> {
+        let processes = if registry.is_empty() {
+            Vec::new()
+        } else {
+            backend.list_processes(protocol).await?
+        };
+        let checks = Self::statuses(registry, &processes);
+
+        if json {
+            let entries: Vec<serde_json::Value> = checks
+                .iter()
+                .map(|(entry, status, holders)| {
+                    let mut entry_json = serde_json::to_value(entry).unwrap_or_default();
+                    entry_json["status"] = status.as_str().into();
+                    entry_json["processes"] = holders
+                        .iter()
+                        .map(|p| {
+                            serde_json::json!({
+                                "pid": p.pid,
+                                "name": p.name,
+                                "executable_path": p.executable_path,
+                                "working_directory": p.working_directory,
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                        .into();
+                    entry_json
+                })
+                .collect();
+            let json_output = serde_json::json!({
+                "protocol": protocol,
+                "entries": entries
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        } else if !quiet {
+            if registry.is_empty() {
+                println!(
+                    "{} No ports registered (declare them in the [registry] section of {})",
+                    "○".blue(),
+                    crate::config::PROJECT_FILE
+                );
+            }
+            for (entry, status, holders) in &checks {
+                let label = format!(
+                    "{} ({}:{})",
+                    entry.name,
+                    protocol.to_uppercase(),
+                    entry.port
+                );
+                let mark = match status {
+                    RegistryStatus::Free => "○".blue(),
+                    RegistryStatus::Owned => "✓".green(),
+                    RegistryStatus::Impostor => "×".red(),
+                };
+                if holders.is_empty() {
+                    println!("{} {} is free", mark, label.yellow());
+                    continue;
+                }
+                for holder in holders {
+                    println!(
+                        "{} {} is held by {} (PID: {})",
+                        mark,
+                        label.yellow(),
+                        holder.name,
+                        holder.pid.to_string().cyan()
+                    );
+                    if !entry.is_owner(holder) {
+                        println!(
+                            "  {} {} in {}",
+                            "Found:".cyan(),
+                            holder.executable_path,
+                            holder.working_directory
+                        );
+                        println!("  {} {}", "Expected:".cyan(), Self::expectation(entry));
+                    }
+                }
+            }
+        }
+
+        let impostors: Vec<&str> = checks
+            .iter()
+            .filter(|(_, status, _)| *status == RegistryStatus::Impostor)
+            .map(|(entry, _, _)| entry.name.as_str())
+            .collect();
+        if !impostors.is_empty() {
+            return Err(crate::Error::Other(format!(
+                "Registered ports held by unexpected processes: {}",
+                impostors.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    /// Each entry with its status and the processes listening on its port
+    fn statuses<'a>(
+        registry: &'a PortRegistry,
+        processes: &[ProcessInfo],
+    ) -> Vec<(&'a RegistryEntry, RegistryStatus, Vec<ProcessInfo>)> {
+        registry
+            .entries()
+            .iter()
+            .map(|entry| {
+                let holders = KillCommand::unique_holders(
+                    processes
+                        .iter()
+                        .filter(|p| p.port == entry.port)
+                        .cloned()
+                        .collect(),
+                );
+                (entry, PortRegistry::status(entry, &holders), holders)
+            })
+            .collect()
+    }
+
+    /// `exe node, cwd /work/shop/web`
+    fn expectation(entry: &RegistryEntry) -> String {
+        let parts: Vec<String> = [("exe", &entry.exe), ("cwd", &entry.cwd)]
+            .into_iter()
+            .filter_map(|(key, pattern)| Some(format!("{key} {}", pattern.as_ref()?)))
+            .collect();
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_statuses() {
+        let config: toml::Table = "web = { port = 3000, exe = \"node\" }\ndb = 5432\napi = 8080\n"
+            .parse()
+            .unwrap();
+        let registry = PortRegistry::new(
+            config
+                .iter()
+                .map(|(name, value)| RegistryEntry::from_toml(name, value, Path::new("/")).unwrap())
+                .collect(),
+        );
+        let holder = |pid, name: &str, port| ProcessInfo {
+            pid,
+            name: name.to_string(),
+            command: name.to_string(),
+            executable_path: format!("/usr/bin/{name}"),
+            working_directory: "/".to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            address: "0.0.0.0".to_string(),
+            ..Default::default()
+        };
+        let processes = vec![holder(10, "python3", 3000), holder(11, "postgres", 5432)];
+
+        let statuses: Vec<(&str, RegistryStatus)> =
+            RegistryCommand::statuses(&registry, &processes)
+                .into_iter()
+                .map(|(entry, status, _)| (entry.name.as_str(), status))
+                .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("web", RegistryStatus::Impostor),
+                ("db", RegistryStatus::Owned),
+                ("api", RegistryStatus::Free),
+            ]
+        );
+        assert_eq!(
+            RegistryCommand::expectation(registry.get("web").unwrap()),
+            "exe node"
+        );
+    }
+}
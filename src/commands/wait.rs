@@ -0,0 +1,346 @@
+use crate::{
+    commands::{CheckCommand, KillCommand},
+    port::{PortBackend, ProcessInfo, Target, TcpState},
+    utils::format_duration,
+    Result,
+};
+use colored::Colorize;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// First delay between two checks; doubled after every check
+const INITIAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest delay between two checks
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// State `wait` waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitCondition {
+    /// Some process listens on the port
+    #[default]
+    Listening,
+    /// No process listens on the port
+    Free,
+}
+
+impl WaitCondition {
+    /// Whether a target with these listeners is in the awaited state
+    pub fn is_satisfied(&self, holders: &[ProcessInfo]) -> bool {
+        match self {
+            WaitCondition::Listening => !holders.is_empty(),
+            WaitCondition::Free => holders.is_empty(),
+        }
+    }
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitCondition::Listening => f.write_str("listening"),
+            WaitCondition::Free => f.write_str("free"),
+        }
+    }
+}
+
+impl FromStr for WaitCondition {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "listening" | "listen" => Ok(WaitCondition::Listening),
+            "free" => Ok(WaitCondition::Free),
+            _ => Err(crate::Error::ParseError(format!(
+                "Invalid condition '{s}' (expected listening or free)"
+            ))),
+        }
+    }
+}
+
+/// Options for [`WaitCommand::execute_with_backend`]
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    pub until: WaitCondition,
+    /// Give up after this long
+    pub timeout: Duration,
+    pub quiet: bool,
+    pub json: bool,
+}
+
+/// Command for waiting until ports are listening or free, for scripts that
+/// start a server and then run tests against it.
+pub struct WaitCommand;
+
+impl WaitCommand {
+    /// Poll every target, backing off between checks, until all of them are
+    /// in the `until` state or the timeout expires.
+    ///
+    /// A timeout is reported as [`crate::Error::Timeout`], which exits with
+    /// code 124.
+    pub async fn execute_with_backend(
+        backend: &dyn PortBackend,
+        targets: &[Target],
+        protocol: &str,
+        options: &WaitOptions,
+    ) -> Result<()> {
+        let WaitOptions {
+            until,
+            timeout,
+            quiet,
+            json,
+        } = *options;
+        let labels: Vec<String> = targets
+            .iter()
+            .map(|target| {
+                format!(
+                    "{}:{}",
+                    Self::protocol_for(target, protocol).to_uppercase(),
+                    target
+                )
+            })
+            .collect();
+
+        if !quiet && !json {
+            println!(
+                "Waiting up to {} for {} to be {}...",
+                format_duration(timeout),
+                labels.join(", ").yellow(),
+                until
+            );
+        }
+
+        let started = Instant::now();
+        let holders = Self::poll(backend, targets, protocol, until, started + timeout).await?;
+        let elapsed = started.elapsed();
+        let satisfied = holders.iter().all(|h| until.is_satisfied(h));
+
+        if json {
+            let targets_json: Vec<serde_json::Value> = targets
+                .iter()
+                .zip(&holders)
+                .map(|(target, holders)| {
+                    let mut target_json =
+                        CheckCommand::target_json(target, Self::protocol_for(target, protocol));
+                    target_json["satisfied"] = until.is_satisfied(holders).into();
+                    target_json["holders"] = holders
+                        .iter()
+                        .map(|p| serde_json::json!({ "pid": p.pid, "name": p.name }))
+                        .collect::<Vec<_>>()
+                        .into();
+                    target_json
+                })
+                .collect();
+            let json_output = serde_json::json!({
+                "until": until.to_string(),
+                "satisfied": satisfied,
+                "elapsed_ms": elapsed.as_millis() as u64,
+                "targets": targets_json
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output)?);
+        } else if !quiet {
+            for (label, holders) in labels.iter().zip(&holders) {
+                let mark = if until.is_satisfied(holders) {
+                    "✓".green()
+                } else {
+                    "×".red()
+                };
+                match holders.first() {
+                    Some(holder) => println!(
+                        "{} {} is listening ({} PID: {})",
+                        mark,
+                        label.yellow(),
+                        holder.name,
+                        holder.pid.to_string().cyan()
+                    ),
+                    None => println!("{} {} is free", mark, label.yellow()),
+                }
+            }
+        }
+
+        if !satisfied {
+            let pending: Vec<&str> = labels
+                .iter()
+                .zip(&holders)
+                .filter(|(_, h)| !until.is_satisfied(h))
+                .map(|(label, _)| label.as_str())
+                .collect();
+            return Err(crate::Error::Timeout(format!(
+                "{} not {} after {}",
+                pending.join(", "),
+                until,
+                format_duration(timeout)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check every target until all satisfy `until` or `deadline` passes.
+    ///
+    /// Returns the listeners of each target from the last check.
+    async fn poll(
+        backend: &dyn PortBackend,
+        targets: &[Target],
+        protocol: &str,
+        until: WaitCondition,
+        deadline: Instant,
+    ) -> Result<Vec<Vec<ProcessInfo>>> {
+        let mut interval = INITIAL_POLL_INTERVAL;
+        loop {
+            let mut holders = Vec::with_capacity(targets.len());
+            for target in targets {
+                let found = CheckCommand::scan_holders(
+                    backend,
+                    target,
+                    Self::protocol_for(target, protocol),
+                    &[TcpState::Listen],
+                )
+                .await?;
+                holders.push(KillCommand::unique_holders(found));
+            }
+
+            let now = Instant::now();
+            if holders.iter().all(|h| until.is_satisfied(h)) || now >= deadline {
+                return Ok(holders);
+            }
+            tokio::time::sleep(interval.min(deadline - now)).await;
+            interval = Self::next_interval(interval);
+        }
+    }
+
+    /// Back off exponentially up to [`MAX_POLL_INTERVAL`]
+    fn next_interval(interval: Duration) -> Duration {
+        (interval * 2).min(MAX_POLL_INTERVAL)
+    }
+
+    /// UNIX socket targets are always checked as `unix`
+    fn protocol_for<'a>(target: &Target, protocol: &'a str) -> &'a str {
+        if target.is_unix() {
+            "unix"
+        } else {
+            protocol
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::PortTarget;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 指定回数のスキャン後にポート3000でリッスンを始めるバックエンド
+    struct StartingBackend {
+        scans_until_listening: usize,
+        scans: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PortBackend for StartingBackend {
+        fn name(&self) -> &'static str {
+            "StartingBackend"
+        }
+
+        async fn list_processes(&self, _protocol: &str) -> Result<Vec<ProcessInfo>> {
+            if self.scans.fetch_add(1, Ordering::SeqCst) < self.scans_until_listening {
+                return Ok(Vec::new());
+            }
+            Ok(vec![ProcessInfo {
+                pid: 4242,
+                name: "node".to_string(),
+                command: "node server.js".to_string(),
+                executable_path: "/usr/bin/node".to_string(),
+                working_directory: "/home/user/app".to_string(),
+                port: 3000,
+                protocol: "tcp".to_string(),
+                address: "*".to_string(),
+                ..Default::default()
+            }])
+        }
+    }
+
+    fn options(until: WaitCondition, timeout: Duration) -> WaitOptions {
+        WaitOptions {
+            until,
+            timeout,
+            quiet: true,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_wait_condition() {
+        assert_eq!(
+            "listening".parse::<WaitCondition>().unwrap(),
+            WaitCondition::Listening
+        );
+        assert_eq!(
+            "FREE".parse::<WaitCondition>().unwrap(),
+            WaitCondition::Free
+        );
+        assert!("up".parse::<WaitCondition>().is_err());
+    }
+
+    #[test]
+    fn test_next_interval_backs_off() {
+        let mut interval = INITIAL_POLL_INTERVAL;
+        for _ in 0..10 {
+            interval = WaitCommand::next_interval(interval);
+        }
+        assert_eq!(interval, MAX_POLL_INTERVAL);
+        assert_eq!(
+            WaitCommand::next_interval(Duration::from_millis(100)),
+            Duration::from_millis(200)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_listening() {
+        let backend = StartingBackend {
+            scans_until_listening: 2,
+            scans: AtomicUsize::new(0),
+        };
+        let targets = [Target::Port(PortTarget::new(3000))];
+
+        WaitCommand::execute_with_backend(
+            &backend,
+            &targets,
+            "tcp",
+            &options(WaitCondition::Listening, Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(backend.scans.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_wait_times_out() {
+        let backend = StartingBackend {
+            scans_until_listening: 0,
+            scans: AtomicUsize::new(0),
+        };
+        // 3000は使用中のまま、3001は空いている
+        let targets = [
+            Target::Port(PortTarget::new(3000)),
+            Target::Port(PortTarget::new(3001)),
+        ];
+
+        let result = WaitCommand::execute_with_backend(
+            &backend,
+            &targets,
+            "tcp",
+            &options(WaitCondition::Free, Duration::from_millis(300)),
+        )
+        .await;
+        match result {
+            Err(e @ crate::Error::Timeout(_)) => {
+                assert_eq!(e.exit_code(), 124);
+                assert!(e.to_string().contains("TCP:3000"));
+                assert!(!e.to_string().contains("TCP:3001"));
+            }
+            other => panic!("Expected a timeout, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,641 @@
+use crate::port::{DisplayRules, PortRegistry, RegistryEntry};
+use crate::process::ProtectPattern;
+use crate::Result;
+use clap::Command;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Project configuration file, looked up from the working directory upwards
+pub const PROJECT_FILE: &str = ".kilar.toml";
+
+/// Keys of the `[display]` section
+const DISPLAY_KEYS: &[&str] = &["dev-executables", "dev-commands"];
+
+/// Options that disable a safeguard; a configuration file, which may come
+/// with a cloned project, must not turn them on behind the user's back
+const SAFEGUARD_KEYS: &[&str] = &["allow-protected", "force", "yes", "dry-run"];
+
+/// A value read from a configuration file, and that file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub value: toml::Value,
+    pub source: PathBuf,
+}
+
+/// One line of `kilar config show`: an option's effective value and where it
+/// comes from (`None` for kilar's built-in default)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveSetting {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+    pub source: Option<PathBuf>,
+}
+
+/// Settings merged from the user and project configuration files.
+///
+/// `[defaults]` sets the global options and every command option of that
+/// name, and a section named after a command (`[kill]`, `[list]`, ...) sets
+/// that command's options; keys are the long option names. `[protect]
+/// patterns` adds protected processes, `[display]` lists what marks a
+/// development process and `[registry]` names the project's ports. Project
+/// settings override user settings, but the protect patterns of both files
+/// apply.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// Files that were read, user file first
+    pub files: Vec<PathBuf>,
+    settings: BTreeMap<(String, String), Setting>,
+    protect: Vec<(ProtectPattern, PathBuf)>,
+}
+
+impl Config {
+    /// Read the user configuration file and the project file of the working directory
+    pub fn load() -> Result<Self> {
+        let project = Self::project_file(&std::env::current_dir()?);
+        Self::load_files(Self::user_file().as_deref(), project.as_deref())
+    }
+
+    /// `$XDG_CONFIG_HOME/kilar/config.toml`, by default `~/.config/kilar/config.toml`
+    pub fn user_file() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("kilar").join("config.toml"))
+    }
+
+    /// The nearest [`PROJECT_FILE`] in `dir` or one of its parents
+    pub fn project_file(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Merge the given files, skipping those that do not exist
+    pub fn load_files(user: Option<&Path>, project: Option<&Path>) -> Result<Self> {
+        let mut config = Self::default();
+        for path in [user, project].into_iter().flatten() {
+            let content = match std::fs::read_to_string(path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(crate::Error::IoError(format!(
+                        "Failed to read {}: {e}",
+                        path.display()
+                    )))
+                }
+            };
+            config.merge(&content, path)?;
+            config.files.push(path.to_path_buf());
+        }
+        Ok(config)
+    }
+
+    /// Merge the contents of one file over the settings read so far
+    pub fn merge(&mut self, content: &str, source: &Path) -> Result<()> {
+        let invalid =
+            |msg: String| crate::Error::ParseError(format!("{}: {msg}", source.display()));
+        let table: toml::Table = content.parse().map_err(|e| invalid(format!("{e}")))?;
+
+        for (section, entries) in table {
+            let toml::Value::Table(entries) = entries else {
+                return Err(invalid(format!(
+                    "'{section}' must be a section such as [defaults] or [kill]"
+                )));
+            };
+            for (key, value) in entries {
+                // レジストリのキーはオプション名ではなくエントリ名
+                let key = if section == "registry" {
+                    key
+                } else {
+                    key.replace('_', "-")
+                };
+                match (section.as_str(), key.as_str()) {
+                    ("protect", "patterns") => {
+                        for pattern in Self::strings(&value).ok_or_else(|| {
+                            invalid("[protect] patterns must be a list of strings".to_string())
+                        })? {
+                            let pattern = pattern.parse().map_err(|e| invalid(format!("{e}")))?;
+                            self.protect.push((pattern, source.to_path_buf()));
+                        }
+                        continue;
+                    }
+                    ("protect", _) => {
+                        return Err(invalid(format!(
+                            "Unknown key '{key}' in [protect] (expected patterns)"
+                        )))
+                    }
+                    ("display", key)
+                        if DISPLAY_KEYS.contains(&key) && Self::strings(&value).is_none() =>
+                    {
+                        return Err(invalid(format!(
+                            "[display] {key} must be a list of strings"
+                        )));
+                    }
+                    ("display", key) if DISPLAY_KEYS.contains(&key) => {}
+                    ("display", _) => {
+                        return Err(invalid(format!(
+                            "Unknown key '{key}' in [display] (expected {})",
+                            DISPLAY_KEYS.join(" or ")
+                        )))
+                    }
+                    ("registry", _) => {
+                        Self::registry_entry(&key, &value, source).map_err(invalid)?;
+                    }
+                    (_, key) if SAFEGUARD_KEYS.contains(&key) => {
+                        return Err(invalid(format!(
+                            "[{section}] {key} cannot be set in a configuration file; pass --{key} on the command line"
+                        )));
+                    }
+                    _ => {}
+                }
+                self.settings.insert(
+                    (section.clone(), key),
+                    Setting {
+                        value,
+                        source: source.to_path_buf(),
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The setting `key` of `section`, if a file sets it
+    pub fn get(&self, section: &str, key: &str) -> Option<&Setting> {
+        self.settings.get(&(section.to_string(), key.to_string()))
+    }
+
+    /// Protect patterns of every file, user file first
+    pub fn protect_patterns(&self) -> Vec<ProtectPattern> {
+        self.protect
+            .iter()
+            .map(|(pattern, _)| pattern.clone())
+            .collect()
+    }
+
+    /// Ports declared in `[registry]`; an entry of the project file replaces
+    /// the user file's entry of the same name
+    pub fn port_registry(&self) -> PortRegistry {
+        PortRegistry::new(
+            self.settings
+                .iter()
+                .filter(|((section, _), _)| section == "registry")
+                .filter_map(|((_, name), setting)| {
+                    Self::registry_entry(name, &setting.value, &setting.source).ok()
+                })
+                .collect(),
+        )
+    }
+
+    /// The built-in display rules with the lists set in `[display]` replaced
+    pub fn display_rules(&self) -> DisplayRules {
+        let list = |key| {
+            self.get("display", key)
+                .and_then(|setting| Self::strings(&setting.value))
+        };
+        let defaults = DisplayRules::default();
+        DisplayRules {
+            dev_executables: list("dev-executables").unwrap_or(defaults.dev_executables),
+            dev_commands: list("dev-commands").unwrap_or(defaults.dev_commands),
+        }
+    }
+
+    /// Make the configured values the defaults of `command`'s options.
+    ///
+    /// `[defaults]` is applied first so that command sections override it.
+    /// Unknown sections and options, and flags, are errors; the values
+    /// themselves are checked by clap when the command line is parsed.
+    pub fn apply(&self, mut command: Command) -> Result<Command> {
+        let (defaults, sections): (Vec<_>, Vec<_>) = self
+            .settings
+            .iter()
+            .filter(|((section, _), _)| section != "display" && section != "registry")
+            .partition(|((section, _), _)| section == "defaults");
+
+        for ((section, key), setting) in defaults.into_iter().chain(sections) {
+            let invalid = |msg: String| {
+                crate::Error::ParseError(format!("{}: {msg}", setting.source.display()))
+            };
+            let id = key.replace('-', "_");
+            let values = Self::arg_values(&setting.value)
+                .ok_or_else(|| invalid(format!("[{section}] {key} has an unsupported value")))?;
+            // 設定で有効にしたフラグはコマンドラインで無効にできない
+            let flag = if section == "defaults" {
+                Self::is_flag(&command, &id)
+                    || command.get_subcommands().any(|sub| Self::is_flag(sub, &id))
+            } else {
+                command
+                    .find_subcommand(section)
+                    .is_some_and(|sub| Self::is_flag(sub, &id))
+            };
+            if flag {
+                return Err(invalid(format!(
+                    "[{section}] {key} is a flag, which could not be turned off on the command line; pass --{key} instead"
+                )));
+            }
+
+            if section == "defaults" {
+                let mut found = false;
+                if Self::has_option(&command, &id) {
+                    let values = values.clone();
+                    command = command.mut_arg(&id, |arg| arg.default_values(values));
+                    found = true;
+                }
+                if command
+                    .get_subcommands()
+                    .any(|sub| Self::has_option(sub, &id))
+                {
+                    command = command.mut_subcommands(|sub| {
+                        if Self::has_option(&sub, &id) {
+                            sub.mut_arg(&id, |arg| arg.default_values(values.clone()))
+                        } else {
+                            sub
+                        }
+                    });
+                    found = true;
+                }
+                if !found {
+                    return Err(invalid(format!("Unknown option '{key}' in [defaults]")));
+                }
+            } else {
+                let Some(sub) = command.find_subcommand(section) else {
+                    return Err(invalid(format!("Unknown section [{section}]")));
+                };
+                if !Self::has_option(sub, &id) {
+                    return Err(invalid(format!(
+                        "Unknown option '{key}' in [{section}] (global options go in [defaults])"
+                    )));
+                }
+                // mut_subcommand would move the command to the end of the help
+                command = command.mut_subcommands(|sub| {
+                    if sub.get_name() == section {
+                        sub.mut_arg(&id, |arg| arg.default_values(values.clone()))
+                    } else {
+                        sub
+                    }
+                });
+            }
+        }
+        Ok(command)
+    }
+
+    /// The default of every option of `command` (after [`Config::apply`]),
+    /// followed by the display rules, protect patterns and registry entries
+    pub fn effective(&self, command: &Command) -> Vec<EffectiveSetting> {
+        let mut effective = Vec::new();
+        let commands = std::iter::once(("defaults", command))
+            .chain(command.get_subcommands().map(|sub| (sub.get_name(), sub)));
+        for (section, command) in commands {
+            for arg in command.get_arguments() {
+                let (Some(long), false) = (arg.get_long(), arg.get_default_values().is_empty())
+                else {
+                    continue;
+                };
+                let source = self
+                    .get(section, long)
+                    .or_else(|| self.get("defaults", long))
+                    .map(|setting| setting.source.clone());
+                let values: Vec<String> = arg
+                    .get_default_values()
+                    .iter()
+                    .map(|value| Self::format_value(&value.to_string_lossy()))
+                    .collect();
+                effective.push(EffectiveSetting {
+                    section: section.to_string(),
+                    key: long.to_string(),
+                    value: match values.as_slice() {
+                        [value] => value.clone(),
+                        values => format!("[{}]", values.join(", ")),
+                    },
+                    source,
+                });
+            }
+        }
+
+        let rules = self.display_rules();
+        for (key, list) in DISPLAY_KEYS
+            .iter()
+            .zip([rules.dev_executables, rules.dev_commands])
+        {
+            let values: Vec<String> = list.iter().map(|value| format!("{value:?}")).collect();
+            effective.push(EffectiveSetting {
+                section: "display".to_string(),
+                key: key.to_string(),
+                value: format!("[{}]", values.join(", ")),
+                source: self
+                    .get("display", key)
+                    .map(|setting| setting.source.clone()),
+            });
+        }
+
+        for file in &self.files {
+            let patterns: Vec<String> = self
+                .protect
+                .iter()
+                .filter(|(_, source)| source == file)
+                .map(|(pattern, _)| format!("{:?}", pattern.to_string()))
+                .collect();
+            if !patterns.is_empty() {
+                effective.push(EffectiveSetting {
+                    section: "protect".to_string(),
+                    key: "patterns".to_string(),
+                    value: format!("[{}]", patterns.join(", ")),
+                    source: Some(file.clone()),
+                });
+            }
+        }
+
+        for ((section, name), setting) in &self.settings {
+            if section == "registry" {
+                effective.push(EffectiveSetting {
+                    section: section.clone(),
+                    key: name.clone(),
+                    value: setting.value.to_string(),
+                    source: Some(setting.source.clone()),
+                });
+            }
+        }
+        effective
+    }
+
+    /// Whether `command` has an option (not a positional argument) with this id
+    fn has_option(command: &Command, id: &str) -> bool {
+        command
+            .get_arguments()
+            .any(|arg| arg.get_id() == id && arg.get_long().is_some())
+    }
+
+    /// Whether `command` has a flag (an option taking no value) with this id
+    fn is_flag(command: &Command, id: &str) -> bool {
+        command
+            .get_arguments()
+            .any(|arg| arg.get_id() == id && !arg.get_action().takes_values())
+    }
+
+    /// A configured value as command-line values; lists give several
+    fn arg_values(value: &toml::Value) -> Option<Vec<String>> {
+        match value {
+            toml::Value::String(s) => Some(vec![s.clone()]),
+            toml::Value::Integer(n) => Some(vec![n.to_string()]),
+            toml::Value::Float(n) => Some(vec![n.to_string()]),
+            toml::Value::Boolean(b) => Some(vec![b.to_string()]),
+            toml::Value::Array(items) => items
+                .iter()
+                .map(|item| Self::arg_values(item)?.into_iter().next())
+                .collect(),
+            _ => None,
+        }
+    }
+
+    /// A `[registry]` entry of the file at `source`
+    fn registry_entry(
+        name: &str,
+        value: &toml::Value,
+        source: &Path,
+    ) -> std::result::Result<RegistryEntry, String> {
+        RegistryEntry::from_toml(name, value, source.parent().unwrap_or(Path::new("/")))
+    }
+
+    fn strings(value: &toml::Value) -> Option<Vec<String>> {
+        value
+            .as_array()?
+            .iter()
+            .map(|item| item.as_str().map(String::from))
+            .collect()
+    }
+
+    /// An option value as it would be written in TOML: numbers and booleans bare
+    fn format_value(value: &str) -> String {
+        if value.parse::<bool>().is_ok() || value.parse::<i64>().is_ok() {
+            value.to_string()
+        } else {
+            format!("{value:?}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::{CommandFactory, FromArgMatches};
+
+    fn merged(files: &[(&str, &str)]) -> Config {
+        let mut config = Config::default();
+        for (path, content) in files {
+            config.merge(content, Path::new(path)).unwrap();
+            config.files.push(PathBuf::from(path));
+        }
+        config
+    }
+
+    fn parse(config: &Config, args: &[&str]) -> Cli {
+        let command = config.apply(Cli::command()).unwrap();
+        Cli::from_arg_matches(&command.try_get_matches_from(args).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_project_overrides_user() {
+        let config = merged(&[
+            (
+                "/home/me/.config/kilar/config.toml",
+                "[defaults]\nprotocol = \"udp\"\nbackend = \"netlink\"\n[protect]\npatterns = [\"user:postgres\"]\n",
+            ),
+            (
+                "/work/shop/.kilar.toml",
+                "[defaults]\nprotocol = \"tcp\"\n[protect]\npatterns = [\"port:5432\"]\n",
+            ),
+        ]);
+        let setting = config.get("defaults", "protocol").unwrap();
+        assert_eq!(setting.value.as_str(), Some("tcp"));
+        assert_eq!(setting.source, PathBuf::from("/work/shop/.kilar.toml"));
+        assert_eq!(
+            config.get("defaults", "backend").unwrap().source,
+            PathBuf::from("/home/me/.config/kilar/config.toml")
+        );
+        assert_eq!(config.protect_patterns().len(), 2);
+    }
+
+    #[test]
+    fn test_apply_defaults_and_sections() {
+        let config = merged(&[(
+            "/work/.kilar.toml",
+            "[defaults]\nprotocol = \"udp\"\nbackend = \"procfs\"\n[list]\nsort = \"pid\"\nprotocol = \"all\"\n[kill]\nescalation = \"INT:2s,KILL\"\n",
+        )]);
+
+        let cli = parse(&config, &["kilar", "check", "3000"]);
+        assert_eq!(cli.backend, crate::port::BackendKind::Procfs);
+        match cli.command {
+            crate::cli::Commands::Check { protocol, .. } => assert_eq!(protocol, "udp"),
+            _ => panic!("Expected Check command"),
+        }
+
+        let cli = parse(&config, &["kilar", "list", "--sort", "name"]);
+        match cli.command {
+            crate::cli::Commands::List { sort, protocol, .. } => {
+                assert_eq!(sort, "name");
+                assert_eq!(protocol, "all");
+            }
+            _ => panic!("Expected List command"),
+        }
+
+        let cli = parse(&config, &["kilar", "kill", "3000"]);
+        match cli.command {
+            crate::cli::Commands::Kill { signals, .. } => {
+                assert_eq!(signals.escalation().to_string(), "INT:2s,KILL")
+            }
+            _ => panic!("Expected Kill command"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_keys_are_errors() {
+        let config = merged(&[("/a.toml", "[kill]\ncolour = true\n")]);
+        let err = config.apply(Cli::command()).unwrap_err().to_string();
+        assert!(err.contains("/a.toml"));
+        assert!(err.contains("colour"));
+
+        let config = merged(&[("/a.toml", "[nuke]\nsort = \"pid\"\n")]);
+        assert!(config.apply(Cli::command()).is_err());
+
+        let mut config = Config::default();
+        assert!(config
+            .merge("[protect]\npatterns = [\"pid:1\"]\n", Path::new("/a.toml"))
+            .is_err());
+        assert!(config
+            .merge("protocol = \"udp\"\n", Path::new("/a.toml"))
+            .is_err());
+        assert!(config
+            .merge("[display]\ndev_commands = \"vite\"\n", Path::new("/a.toml"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_safeguards_and_flags_are_errors() {
+        for content in [
+            "[defaults]\nallow_protected = true\n",
+            "[kill]\nyes = true\n",
+            "[project]\nforce = true\n",
+            "[defaults]\ndry-run = false\n",
+        ] {
+            let err = Config::default()
+                .merge(content, Path::new("/work/.kilar.toml"))
+                .unwrap_err()
+                .to_string();
+            assert!(
+                err.contains("cannot be set in a configuration file"),
+                "{err}"
+            );
+        }
+
+        for content in ["[defaults]\nquiet = true\n", "[list]\nview_only = true\n"] {
+            let config = merged(&[("/work/.kilar.toml", content)]);
+            let err = config.apply(Cli::command()).unwrap_err().to_string();
+            assert!(err.contains("is a flag"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_display_rules() {
+        let config = merged(&[("/a.toml", "[display]\ndev_commands = [\"vite\"]\n")]);
+        let rules = config.display_rules();
+        assert_eq!(rules.dev_commands, vec!["vite"]);
+        assert_eq!(
+            rules.dev_executables,
+            DisplayRules::default().dev_executables
+        );
+    }
+
+    #[test]
+    fn test_effective_sources() {
+        let config = merged(&[("/work/.kilar.toml", "[kill]\nprotocol = \"udp\"\n")]);
+        let effective = config.effective(&config.apply(Cli::command()).unwrap());
+
+        let find = |section: &str, key: &str| {
+            effective
+                .iter()
+                .find(|s| s.section == section && s.key == key)
+                .unwrap()
+                .clone()
+        };
+        let kill = find("kill", "protocol");
+        assert_eq!(kill.value, "\"udp\"");
+        assert_eq!(kill.source, Some(PathBuf::from("/work/.kilar.toml")));
+        let check = find("check", "protocol");
+        assert_eq!(check.value, "\"tcp\"");
+        assert_eq!(check.source, None);
+        assert_eq!(find("defaults", "backend").value, "\"auto\"");
+    }
+
+    #[test]
+    fn test_registry() {
+        let config = merged(&[
+            (
+                "/home/me/.config/kilar/config.toml",
+                "[registry]\ndb = 5432\nfrontend = 3001\n",
+            ),
+            (
+                "/work/shop/.kilar.toml",
+                "[registry]\nfrontend = { port = 3000, exe = \"node\", cwd = \"web\" }\nweb_api = 8080\n",
+            ),
+        ]);
+        let registry = config.port_registry();
+        let names: Vec<&str> = registry.entries().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["frontend", "db", "web_api"]);
+        assert_eq!(
+            registry.get("frontend").unwrap().cwd.as_deref(),
+            Some("/work/shop/web")
+        );
+
+        let command = Cli::configured_command(&config).unwrap();
+        let cli = Cli::from_arg_matches(
+            &command
+                .try_get_matches_from(["kilar", "kill", "frontend", "db", "9000"])
+                .unwrap(),
+        )
+        .unwrap();
+        match cli.command {
+            crate::cli::Commands::Kill { targets, .. } => {
+                let targets: Vec<String> = targets.iter().map(ToString::to_string).collect();
+                assert_eq!(targets, ["3000", "5432", "9000"]);
+            }
+            _ => panic!("Expected Kill command"),
+        }
+        assert!(Cli::configured_command(&config)
+            .unwrap()
+            .try_get_matches_from(["kilar", "check", "backend"])
+            .is_err());
+
+        let effective = config.effective(&Cli::configured_command(&config).unwrap());
+        assert!(effective
+            .iter()
+            .any(|s| s.section == "registry" && s.key == "web_api" && s.value == "8080"));
+
+        let mut config = Config::default();
+        let err = config
+            .merge(
+                "[registry]\ndb = { port = 5432, user = \"pg\" }\n",
+                Path::new("/a.toml"),
+            )
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("/a.toml: [registry] db: unknown key 'user'"));
+    }
+
+    #[test]
+    fn test_load_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join(PROJECT_FILE);
+        std::fs::write(&project, "[list]\nsort = \"pid\"\n").unwrap();
+        let nested = dir.path().join("src").join("app");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(Config::project_file(&nested), Some(project.clone()));
+        let config =
+            Config::load_files(Some(&dir.path().join("missing.toml")), Some(&project)).unwrap();
+        assert_eq!(config.files, vec![project]);
+        assert!(config.get("list", "sort").is_some());
+    }
+}
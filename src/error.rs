@@ -12,14 +12,26 @@ pub enum Error {
     ParseError(String),
     /// The specified port is not in use
     PortNotFound(u16),
+    /// No process listens on the specified UNIX socket
+    SocketNotFound(String),
     /// Operation requires elevated privileges
     PermissionDenied(String),
     /// Process with the specified PID was not found
     ProcessNotFound(u32),
+    /// The process exited and its PID now belongs to a different process
+    ProcessReplaced(u32),
+    /// The process is protected and `--allow-protected` was not given
+    Protected {
+        pid: u32,
+        name: String,
+        reason: String,
+    },
     /// Invalid port number or range
     InvalidPort(String),
     /// System command execution failed
     CommandFailed(String),
+    /// A wait for a port to change state ran out of time
+    Timeout(String),
     /// Other generic error
     Other(String),
 }
@@ -30,6 +42,7 @@ impl fmt::Display for Error {
             Error::IoError(msg) => write!(f, "I/O error: {msg}"),
             Error::ParseError(msg) => write!(f, "Parse error: {msg}"),
             Error::PortNotFound(port) => write!(f, "Port {port} is not in use"),
+            Error::SocketNotFound(path) => write!(f, "Socket {path} is not in use"),
             Error::PermissionDenied(msg) => {
                 write!(
                     f,
@@ -37,6 +50,14 @@ impl fmt::Display for Error {
                 )
             }
             Error::ProcessNotFound(pid) => write!(f, "Process with PID {pid} not found"),
+            Error::ProcessReplaced(pid) => write!(
+                f,
+                "Process with PID {pid} has exited and the PID was reused by another process"
+            ),
+            Error::Protected { pid, name, reason } => write!(
+                f,
+                "Refusing to kill protected process {name} (PID {pid}): {reason}. Use --allow-protected to kill it anyway"
+            ),
             Error::InvalidPort(msg) => {
                 write!(f, "Invalid port: {msg}. Port must be between 1 and 65535")
             }
@@ -50,11 +71,22 @@ impl fmt::Display for Error {
                     write!(f, "Command execution failed: {msg}")
                 }
             }
+            Error::Timeout(msg) => write!(f, "Timed out: {msg}"),
             Error::Other(msg) => write!(f, "{msg}"),
         }
     }
 }
 
+impl Error {
+    /// Process exit code for this error: 124 for timeouts, as with `timeout(1)`, else 1
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Timeout(_) => 124,
+            _ => 1,
+        }
+    }
+}
+
 impl std::error::Error for Error {}
 
 impl From<std::io::Error> for Error {
@@ -107,6 +139,25 @@ mod tests {
         assert_eq!(err.to_string(), "Process with PID 1234 not found");
     }
 
+    #[test]
+    fn test_exit_code() {
+        assert_eq!(Error::Timeout("port 3000".to_string()).exit_code(), 124);
+        assert_eq!(Error::PortNotFound(3000).exit_code(), 1);
+    }
+
+    #[test]
+    fn test_protected_message() {
+        let err = Error::Protected {
+            pid: 812,
+            name: "sshd".to_string(),
+            reason: "it matches the built-in pattern sshd".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Refusing to kill protected process sshd (PID 812): it matches the built-in pattern sshd. Use --allow-protected to kill it anyway"
+        );
+    }
+
     #[test]
     fn test_permission_denied_message() {
         let err = Error::PermissionDenied("Operation not permitted".to_string());
@@ -195,6 +246,10 @@ mod tests {
                 Error::ProcessNotFound(9999),
                 "Process with PID 9999 not found",
             ),
+            (
+                Error::ProcessReplaced(4242),
+                "Process with PID 4242 has exited and the PID was reused by another process",
+            ),
             (
                 Error::InvalidPort("99999".to_string()),
                 "Invalid port: 99999. Port must be between 1 and 65535",
@@ -203,6 +258,10 @@ mod tests {
                 Error::CommandFailed("general failure".to_string()),
                 "Command execution failed: general failure",
             ),
+            (
+                Error::Timeout("port 3000 is still in use".to_string()),
+                "Timed out: port 3000 is still in use",
+            ),
             (Error::Other("custom error".to_string()), "custom error"),
         ];
 
@@ -6,6 +6,7 @@
 //!
 //! - Check port usage status
 //! - Kill processes using specific ports
+//! - Find local processes connected to a port
 //! - List all ports in use with filtering and sorting options
 //! - Interactive process selection for termination
 //! - JSON output support for scripting
@@ -24,6 +25,7 @@
 
 pub mod cli;
 pub mod commands;
+pub mod config;
 pub mod error;
 pub mod port;
 pub mod process;
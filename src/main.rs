@@ -1,70 +1,336 @@
 use colored::Colorize;
 use kilar::{
-    cli::{Cli, Commands},
-    commands::{CheckCommand, KillCommand, ListCommand},
-    utils::{validate_port, validate_protocol, validate_sort_option},
+    cli::{Cli, Commands, ConfigAction, RegistryAction},
+    commands::{
+        hold::{HoldOptions, HoldTarget},
+        kill::{KillOptions, KillScope},
+        list::{ListOptions, ProcessFilter},
+        wait::WaitOptions,
+        CheckCommand, ClientsCommand, ConfigCommand, FreeCommand, HoldCommand, KillCommand,
+        ListCommand, ProjectCommand, RegistryCommand, WaitCommand,
+    },
+    port::{create_backend_for, set_display_rules, LeaseRegistry, Target, TargetSpec, TcpState},
+    utils::{validate_list_protocol, validate_port, validate_protocol, validate_sort_option},
     Result,
 };
+use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() {
     if let Err(e) = run().await {
         eprintln!("{} {}", "Error:".red(), e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
 
 async fn run() -> Result<()> {
-    let cli = Cli::parse_args();
+    let cli = Cli::parse_args()?;
+    set_display_rules(cli.config.display_rules());
+    let backend = create_backend_for(cli.backend, cli.netns_scope()?)?;
+    let family = cli.ip_family();
+    let protection = cli.protection();
 
     match cli.command {
         Commands::Check {
-            port,
+            target,
             protocol,
             interactive,
+            state,
         } => {
-            validate_port(port)?;
-            validate_protocol(&protocol)?;
+            let protocol = target_protocol(&target, protocol)?;
+            let states = TcpState::parse_list(&state)?;
 
-            CheckCommand::execute(
-                port,
+            CheckCommand::execute_with_backend(
+                backend.as_ref(),
+                &target.with_family(family),
                 &protocol,
+                &states,
                 cli.quiet,
                 cli.json,
                 cli.verbose,
                 interactive,
+                cli.dry_run,
+                &protection,
             )
             .await?;
         }
         Commands::Kill {
-            port,
+            targets,
             force,
+            name,
+            cwd,
+            path,
+            range,
+            stop_unit,
+            tree,
+            group,
+            signals,
+            wait,
             protocol,
         } => {
-            validate_port(port)?;
-            validate_protocol(&protocol)?;
+            let (cwd, path) = filter_paths(cwd, path)?;
+            let filter = ProcessFilter {
+                name,
+                cwd,
+                path,
+                ports: range
+                    .map(|range| ListCommand::parse_port_range(&range))
+                    .transpose()?,
+            };
+            // フィルタだけが指定された場合はすべてのポートから探す
+            let targets = if targets.is_empty() {
+                let (start, end) = filter.ports.unwrap_or((1, u16::MAX));
+                vec![TargetSpec::Range {
+                    start: start.max(1),
+                    end,
+                    family: None,
+                }]
+            } else {
+                targets
+            };
+            // UNIXソケットだけを指定した場合はプロトコルを問わない
+            let protocol = if targets.iter().all(TargetSpec::is_unix) {
+                "unix".to_string()
+            } else {
+                validate_protocol(&protocol)?;
+                protocol
+            };
+            for target in &targets {
+                if let TargetSpec::Single(Target::Port(port_target)) = target {
+                    validate_port(port_target.port)?;
+                }
+            }
+            let targets: Vec<TargetSpec> = targets
+                .into_iter()
+                .map(|target| target.with_family(family))
+                .collect();
+            let scope = match (tree, group.as_deref()) {
+                (true, _) => KillScope::Tree,
+                (_, Some("session")) => KillScope::Session,
+                (_, Some(_)) => KillScope::Group,
+                (false, None) => KillScope::Process,
+            };
+
+            let options = KillOptions {
+                force,
+                stop_unit,
+                scope,
+                escalation: signals.escalation(),
+                wait,
+                dry_run: cli.dry_run,
+                filter,
+                protection,
+                quiet: cli.quiet,
+                json: cli.json,
+                verbose: cli.verbose,
+            };
+            KillCommand::execute_targets(backend.as_ref(), &targets, &protocol, &options).await?;
+        }
+        Commands::Clients { target } => {
+            validate_port(target.port)?;
 
-            KillCommand::execute(port, &protocol, force, cli.quiet, cli.json, cli.verbose).await?;
+            ClientsCommand::execute(
+                &target.with_family(family.or(target.family)),
+                cli.quiet,
+                cli.json,
+                cli.verbose,
+            )
+            .await?;
+        }
+        Commands::Free {
+            range,
+            count,
+            protocol,
+        } => {
+            FreeCommand::execute_with_backend(
+                backend.as_ref(),
+                &LeaseRegistry::default(),
+                &range,
+                count.into(),
+                &protocol,
+                cli.quiet,
+                cli.json,
+            )
+            .await?;
+        }
+        Commands::Hold {
+            port,
+            any,
+            ttl,
+            label,
+            protocol,
+        } => {
+            // clapがポートか--anyのどちらか一方を保証する
+            let target = port.map_or_else(
+                || HoldTarget::Any(any.unwrap_or_default()),
+                HoldTarget::Port,
+            );
+            let options = HoldOptions {
+                protocol,
+                label,
+                ttl,
+                quiet: cli.quiet,
+                json: cli.json,
+            };
+            HoldCommand::execute_with_backend(
+                backend.as_ref(),
+                &LeaseRegistry::default(),
+                &target,
+                &options,
+            )
+            .await?;
+        }
+        Commands::Release { port, protocol } => {
+            HoldCommand::release(
+                &LeaseRegistry::default(),
+                port,
+                &protocol,
+                &protection,
+                cli.dry_run,
+                cli.quiet,
+                cli.json,
+            )
+            .await?;
         }
         Commands::List {
             ports,
             filter,
+            cwd,
+            path,
             sort,
             protocol,
             view_only,
             watch,
+            interval,
+            state,
+            tree,
+            signals,
         } => {
-            validate_protocol(&protocol)?;
+            validate_list_protocol(&protocol)?;
             validate_sort_option(&sort)?;
+            let states = TcpState::parse_list(&state)?;
 
             // デフォルトはkill機能付き、--view-onlyで無効化
-            let kill_mode = !view_only;
-            ListCommand::execute(
-                ports, filter, &sort, &protocol, kill_mode, cli.quiet, cli.json, watch,
+            let (cwd, path) = filter_paths(cwd, path)?;
+            let options = ListOptions {
+                ports_range: ports,
+                filter,
+                cwd,
+                path,
+                sort,
+                protocol,
+                kill: !view_only,
+                quiet: cli.quiet,
+                json: cli.json,
+                watch,
+                watch_interval: interval,
+                family,
+                states,
+                tree,
+                escalation: signals.escalation(),
+                dry_run: cli.dry_run,
+                protection,
+                registry: cli.config.port_registry(),
+            };
+            ListCommand::execute_with_backend(options, backend.as_ref()).await?;
+        }
+        Commands::Wait {
+            targets,
+            until,
+            timeout,
+            protocol,
+        } => {
+            for target in &targets {
+                target_protocol(target, protocol.clone())?;
+            }
+            let targets: Vec<Target> = targets
+                .into_iter()
+                .map(|target| target.with_family(family))
+                .collect();
+
+            let options = WaitOptions {
+                until,
+                timeout,
+                quiet: cli.quiet,
+                json: cli.json,
+            };
+            WaitCommand::execute_with_backend(backend.as_ref(), &targets, &protocol, &options)
+                .await?;
+        }
+        Commands::Project {
+            kill,
+            force,
+            signals,
+            protocol,
+        } => {
+            validate_protocol(&protocol)?;
+            let options = KillOptions {
+                force,
+                stop_unit: false,
+                scope: KillScope::Process,
+                escalation: signals.escalation(),
+                wait: None,
+                dry_run: cli.dry_run,
+                filter: ProcessFilter::default(),
+                protection,
+                quiet: cli.quiet,
+                json: cli.json,
+                verbose: cli.verbose,
+            };
+            ProjectCommand::execute_with_backend(
+                backend.as_ref(),
+                &std::env::current_dir()?,
+                &protocol,
+                kill,
+                &options,
+            )
+            .await?;
+        }
+        Commands::Registry {
+            action: RegistryAction::Check { protocol },
+        } => {
+            validate_protocol(&protocol)?;
+            RegistryCommand::check(
+                backend.as_ref(),
+                &cli.config.port_registry(),
+                &protocol,
+                cli.quiet,
+                cli.json,
             )
             .await?;
         }
+        Commands::Config {
+            action: ConfigAction::Show,
+        } => {
+            let command = Cli::configured_command(&cli.config)?;
+            ConfigCommand::show(&cli.config, &command, cli.quiet, cli.json)?;
+        }
     }
 
     Ok(())
 }
+
+/// `--cwd` and `--path` as absolute paths, comparable with what the backends report
+fn filter_paths(
+    cwd: Option<PathBuf>,
+    path: Option<PathBuf>,
+) -> Result<(Option<PathBuf>, Option<PathBuf>)> {
+    // /proc/<pid>/cwd はシンボリックリンクを解決したパスになる
+    let cwd = cwd
+        .map(|cwd| std::fs::canonicalize(&cwd).or_else(|_| std::path::absolute(&cwd)))
+        .transpose()?;
+    let path = path.map(std::path::absolute).transpose()?;
+    Ok((cwd, path))
+}
+
+/// Validate a check/kill target and pick its protocol (`unix` for socket paths)
+fn target_protocol(target: &Target, protocol: String) -> Result<String> {
+    match target {
+        Target::Port(port_target) => {
+            validate_port(port_target.port)?;
+            validate_protocol(&protocol)?;
+            Ok(protocol)
+        }
+        Target::Unix(_) => Ok("unix".to_string()),
+    }
+}
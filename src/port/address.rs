@@ -0,0 +1,643 @@
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use super::ProcessInfo;
+
+/// Address family of a socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IpFamily {
+    #[serde(rename = "ipv4")]
+    V4,
+    #[serde(rename = "ipv6")]
+    V6,
+}
+
+impl IpFamily {
+    pub fn of(ip: &IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(_) => IpFamily::V4,
+            IpAddr::V6(_) => IpFamily::V6,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpFamily::V4 => "ipv4",
+            IpFamily::V6 => "ipv6",
+        }
+    }
+}
+
+impl fmt::Display for IpFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse an address as reported by a backend.
+///
+/// Accepts the forms the scanners produce (`127.0.0.1`, `::1`, `[::1]`,
+/// `fe80::1%eth0`). Returns `None` for the `*` wildcard, whose family is
+/// unknown, and for anything unparsable.
+pub fn parse_bound_address(address: &str) -> Option<IpAddr> {
+    let address = address
+        .strip_prefix('[')
+        .and_then(|a| a.strip_suffix(']'))
+        .unwrap_or(address);
+    // Drop an IPv6 zone index
+    let address = address.split('%').next().unwrap_or(address);
+    address.parse::<IpAddr>().ok()
+}
+
+impl ProcessInfo {
+    /// Address family of the socket, from the backend or the bound address
+    pub fn ip_family(&self) -> Option<IpFamily> {
+        self.family
+            .or_else(|| parse_bound_address(&self.address).map(|ip| IpFamily::of(&ip)))
+    }
+
+    /// Whether the socket is bound to every address (`*`, `0.0.0.0`, `::`)
+    pub fn is_wildcard(&self) -> bool {
+        match parse_bound_address(&self.address) {
+            Some(ip) => ip.is_unspecified(),
+            None => self.address == "*" || self.address.is_empty(),
+        }
+    }
+
+    /// Bound address for display, e.g. `127.0.0.1`, `[::1]`, `0.0.0.0`, `*`
+    pub fn display_address(&self) -> String {
+        if self.is_wildcard() {
+            return match self.ip_family() {
+                Some(IpFamily::V4) => "0.0.0.0".to_string(),
+                Some(IpFamily::V6) => "[::]".to_string(),
+                None => "*".to_string(),
+            };
+        }
+
+        match parse_bound_address(&self.address) {
+            Some(IpAddr::V6(ip)) => format!("[{ip}]"),
+            Some(ip) => ip.to_string(),
+            None => self.address.clone(),
+        }
+    }
+
+    /// Whether the socket belongs to `family`.
+    ///
+    /// Sockets whose family cannot be determined (a bare `*` from `ss`) are
+    /// kept by either filter.
+    pub fn matches_family(&self, family: Option<IpFamily>) -> bool {
+        match (family, self.ip_family()) {
+            (None, _) | (_, None) => true,
+            (Some(wanted), Some(actual)) => wanted == actual,
+        }
+    }
+}
+
+/// A port optionally narrowed to one bound address and address family.
+///
+/// Parsed from `3000`, `:3000`, `*:3000`, `127.0.0.1:3000` or `[::1]:8080`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortTarget {
+    pub address: Option<IpAddr>,
+    pub port: u16,
+    pub family: Option<IpFamily>,
+}
+
+impl PortTarget {
+    pub fn new(port: u16) -> Self {
+        Self {
+            address: None,
+            port,
+            family: None,
+        }
+    }
+
+    /// Restrict the target to an address family (`-4` / `-6`)
+    pub fn with_family(mut self, family: Option<IpFamily>) -> Self {
+        self.family = family;
+        self
+    }
+
+    /// Whether `process` holds a socket matching this target.
+    ///
+    /// A specific address only matches sockets bound to exactly that address;
+    /// `0.0.0.0` and `::` match the wildcard listener of that family.
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        if process.port != self.port || !process.matches_family(self.family) {
+            return false;
+        }
+
+        match self.address {
+            None => true,
+            Some(ip) if ip.is_unspecified() => {
+                process.is_wildcard()
+                    && process
+                        .ip_family()
+                        .is_none_or(|family| family == IpFamily::of(&ip))
+            }
+            Some(ip) => parse_bound_address(&process.address) == Some(ip),
+        }
+    }
+
+    /// Whether the remote end `ip:port` of a connection is this target.
+    ///
+    /// IPv4-mapped peers in `/proc/net/tcp6` (`::ffff:127.0.0.1`) match their
+    /// IPv4 form; `0.0.0.0` and `::` match any peer of that family.
+    pub fn matches_remote(&self, ip: IpAddr, port: u16) -> bool {
+        let ip = ip.to_canonical();
+        if port != self.port || self.family.is_some_and(|f| f != IpFamily::of(&ip)) {
+            return false;
+        }
+
+        match self.address.map(|a| a.to_canonical()) {
+            None => true,
+            Some(target) if target.is_unspecified() => IpFamily::of(&target) == IpFamily::of(&ip),
+            Some(target) => target == ip,
+        }
+    }
+}
+
+impl From<u16> for PortTarget {
+    fn from(port: u16) -> Self {
+        Self::new(port)
+    }
+}
+
+impl fmt::Display for PortTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.address {
+            Some(IpAddr::V6(ip)) => write!(f, "[{ip}]:{}", self.port),
+            Some(ip) => write!(f, "{ip}:{}", self.port),
+            None => write!(f, "{}", self.port),
+        }
+    }
+}
+
+impl FromStr for PortTarget {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            crate::Error::InvalidPort(format!(
+                "Invalid target '{s}' (expected PORT, HOST:PORT or [IPV6]:PORT)"
+            ))
+        };
+
+        let (host, port) = match s.rsplit_once(':') {
+            Some((host, port)) => (Some(host), port),
+            None => (None, s),
+        };
+
+        let port = port.parse::<u16>().map_err(|_| invalid())?;
+
+        let address = match host {
+            None | Some("") | Some("*") => None,
+            Some(host) => {
+                // IPv6 addresses must be bracketed so the port is unambiguous
+                if host.contains(':') && !host.starts_with('[') {
+                    return Err(invalid());
+                }
+                Some(parse_bound_address(host).ok_or_else(invalid)?)
+            }
+        };
+
+        Ok(Self {
+            address,
+            port,
+            family: address.map(|ip| IpFamily::of(&ip)),
+        })
+    }
+}
+
+/// What `check` and `kill` operate on: a TCP/UDP port or a UNIX socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Port(PortTarget),
+    /// Path of a UNIX domain socket (`@name` for the abstract namespace)
+    Unix(String),
+}
+
+impl Target {
+    /// The port target, if this is not a UNIX socket
+    pub fn port_target(&self) -> Option<&PortTarget> {
+        match self {
+            Target::Port(target) => Some(target),
+            Target::Unix(_) => None,
+        }
+    }
+
+    pub fn is_unix(&self) -> bool {
+        matches!(self, Target::Unix(_))
+    }
+
+    /// Restrict a port target to an address family (`-4` / `-6`)
+    pub fn with_family(self, family: Option<IpFamily>) -> Self {
+        match self {
+            Target::Port(target) => {
+                let family = family.or(target.family);
+                Target::Port(target.with_family(family))
+            }
+            unix => unix,
+        }
+    }
+
+    /// Whether `process` holds a socket matching this target
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        match self {
+            Target::Port(target) => target.matches(process),
+            Target::Unix(path) => process.protocol == "unix" && process.address == *path,
+        }
+    }
+}
+
+impl From<PortTarget> for Target {
+    fn from(target: PortTarget) -> Self {
+        Target::Port(target)
+    }
+}
+
+impl From<u16> for Target {
+    fn from(port: u16) -> Self {
+        Target::Port(PortTarget::new(port))
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Port(target) => target.fmt(f),
+            Target::Unix(path) => f.write_str(path),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = crate::Error;
+
+    /// Anything that looks like a path (`/run/app.sock`, `./app.sock`,
+    /// `@abstract`) is a UNIX socket; everything else is parsed as a port.
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(name) = s.strip_prefix('@') {
+            if !name.is_empty() {
+                return Ok(Target::Unix(s.to_string()));
+            }
+        } else if s.contains('/') {
+            // /proc/net/unix records the path as bound, which is usually absolute
+            let path = std::path::absolute(s).map_err(|e| {
+                crate::Error::InvalidPort(format!("Invalid socket path '{s}': {e}"))
+            })?;
+            return Ok(Target::Unix(path.to_string_lossy().into_owned()));
+        }
+
+        s.parse::<PortTarget>().map(Target::Port).map_err(|_| {
+            crate::Error::InvalidPort(format!(
+                "Invalid target '{s}' (expected PORT, HOST:PORT, [IPV6]:PORT or a socket path)"
+            ))
+        })
+    }
+}
+
+/// A `kill` argument: one [`Target`], or every port in a range such as
+/// `8000-8010`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetSpec {
+    Single(Target),
+    Range {
+        start: u16,
+        end: u16,
+        family: Option<IpFamily>,
+    },
+}
+
+impl TargetSpec {
+    pub fn is_unix(&self) -> bool {
+        matches!(self, TargetSpec::Single(target) if target.is_unix())
+    }
+
+    pub fn is_range(&self) -> bool {
+        matches!(self, TargetSpec::Range { .. })
+    }
+
+    /// Restrict port targets to an address family (`-4` / `-6`)
+    pub fn with_family(self, family: Option<IpFamily>) -> Self {
+        match self {
+            TargetSpec::Single(target) => TargetSpec::Single(target.with_family(family)),
+            TargetSpec::Range {
+                start,
+                end,
+                family: own,
+            } => TargetSpec::Range {
+                start,
+                end,
+                family: family.or(own),
+            },
+        }
+    }
+
+    /// Whether `process` holds a socket matching this target
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        match self {
+            TargetSpec::Single(target) => target.matches(process),
+            TargetSpec::Range { start, end, family } => {
+                process.protocol != "unix"
+                    && (*start..=*end).contains(&process.port)
+                    && process.matches_family(*family)
+            }
+        }
+    }
+}
+
+impl From<Target> for TargetSpec {
+    fn from(target: Target) -> Self {
+        TargetSpec::Single(target)
+    }
+}
+
+impl fmt::Display for TargetSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetSpec::Single(target) => target.fmt(f),
+            TargetSpec::Range { start, end, .. } => write!(f, "{start}-{end}"),
+        }
+    }
+}
+
+impl FromStr for TargetSpec {
+    type Err = crate::Error;
+
+    /// `START-END` with two port numbers is a range; anything else is a [`Target`]
+    fn from_str(s: &str) -> Result<Self> {
+        let range = s.split_once('-').and_then(|(start, end)| {
+            let is_port = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+            (is_port(start) && is_port(end)).then_some((start, end))
+        });
+        let Some((start, end)) = range else {
+            return s.parse().map(TargetSpec::Single);
+        };
+
+        let parse = |part: &str| {
+            part.parse::<u16>()
+                .map_err(|_| crate::Error::InvalidPort(format!("Invalid port in range '{s}'")))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start == 0 || start > end {
+            return Err(crate::Error::InvalidPort(format!(
+                "Invalid port range '{s}' (expected START-END with START <= END)"
+            )));
+        }
+        Ok(TargetSpec::Range {
+            start,
+            end,
+            family: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn process_at(address: &str, family: Option<IpFamily>) -> ProcessInfo {
+        ProcessInfo {
+            pid: 1234,
+            name: "node".to_string(),
+            command: "node server.js".to_string(),
+            executable_path: "/usr/bin/node".to_string(),
+            working_directory: "/home/user/app".to_string(),
+            port: 3000,
+            protocol: "tcp".to_string(),
+            address: address.to_string(),
+            family,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_port_target() {
+        assert_eq!("3000".parse::<PortTarget>().unwrap(), PortTarget::new(3000));
+        assert_eq!(
+            ":3000".parse::<PortTarget>().unwrap(),
+            PortTarget::new(3000)
+        );
+        assert_eq!(
+            "*:3000".parse::<PortTarget>().unwrap(),
+            PortTarget::new(3000)
+        );
+
+        let target = "127.0.0.1:3000".parse::<PortTarget>().unwrap();
+        assert_eq!(target.address, Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert_eq!(target.port, 3000);
+        assert_eq!(target.family, Some(IpFamily::V4));
+
+        let target = "[::1]:8080".parse::<PortTarget>().unwrap();
+        assert_eq!(target.address, Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        assert_eq!(target.port, 8080);
+        assert_eq!(target.family, Some(IpFamily::V6));
+    }
+
+    #[test]
+    fn test_parse_port_target_invalid() {
+        for input in [
+            "",
+            "abc",
+            "70000",
+            "localhost:3000",
+            "::1:3000",
+            "[::1]",
+            "1.2.3:80",
+        ] {
+            let result = input.parse::<PortTarget>();
+            assert!(result.is_err(), "{input} should be rejected");
+            assert!(result.unwrap_err().to_string().contains("Invalid target"));
+        }
+    }
+
+    #[test]
+    fn test_port_target_display_round_trip() {
+        for input in ["3000", "127.0.0.1:3000", "[::1]:8080", "[::]:80"] {
+            let target = input.parse::<PortTarget>().unwrap();
+            assert_eq!(target.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_parse_bound_address() {
+        assert_eq!(
+            parse_bound_address("127.0.0.1"),
+            Some(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_bound_address("[::1]"),
+            Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+        );
+        assert_eq!(
+            parse_bound_address("::1"),
+            Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+        );
+        assert!(parse_bound_address("[fe80::1%eth0]").is_some());
+        assert_eq!(parse_bound_address("*"), None);
+    }
+
+    #[test]
+    fn test_target_matches_specific_address() {
+        let target = "127.0.0.1:3000".parse::<PortTarget>().unwrap();
+
+        assert!(target.matches(&process_at("127.0.0.1", Some(IpFamily::V4))));
+        assert!(!target.matches(&process_at("*", Some(IpFamily::V4))));
+        assert!(!target.matches(&process_at("[::1]", Some(IpFamily::V6))));
+    }
+
+    #[test]
+    fn test_target_matches_wildcard_by_family() {
+        // Docker proxyが残したIPv6のワイルドカードだけを狙う
+        let target = "[::]:3000".parse::<PortTarget>().unwrap();
+
+        assert!(target.matches(&process_at("*", Some(IpFamily::V6))));
+        assert!(target.matches(&process_at("[::]", None)));
+        assert!(!target.matches(&process_at("*", Some(IpFamily::V4))));
+        assert!(!target.matches(&process_at("0.0.0.0", None)));
+        assert!(!target.matches(&process_at("127.0.0.1", Some(IpFamily::V4))));
+    }
+
+    #[test]
+    fn test_target_port_only_matches_every_address() {
+        let target = PortTarget::new(3000);
+        assert!(target.matches(&process_at("127.0.0.1", Some(IpFamily::V4))));
+        assert!(target.matches(&process_at("*", Some(IpFamily::V6))));
+
+        let mut other_port = process_at("127.0.0.1", Some(IpFamily::V4));
+        other_port.port = 3001;
+        assert!(!target.matches(&other_port));
+    }
+
+    #[test]
+    fn test_target_family_filter() {
+        let target = PortTarget::new(3000).with_family(Some(IpFamily::V6));
+
+        assert!(target.matches(&process_at("[::1]", None)));
+        assert!(target.matches(&process_at("*", Some(IpFamily::V6))));
+        assert!(!target.matches(&process_at("127.0.0.1", None)));
+        // ファミリー不明のソケットはどちらのフィルタでも残す
+        assert!(target.matches(&process_at("*", None)));
+    }
+
+    #[test]
+    fn test_target_matches_remote() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let mapped: IpAddr = "::ffff:127.0.0.1".parse().unwrap();
+
+        let target = PortTarget::new(5432);
+        assert!(target.matches_remote(localhost, 5432));
+        assert!(!target.matches_remote(localhost, 5433));
+
+        let target = "127.0.0.1:5432".parse::<PortTarget>().unwrap();
+        assert!(target.matches_remote(localhost, 5432));
+        // tcp6ソケットからのIPv4接続はマップドアドレスで現れる
+        assert!(target.matches_remote(mapped, 5432));
+        assert!(!target.matches_remote(IpAddr::V6(Ipv6Addr::LOCALHOST), 5432));
+
+        let target = PortTarget::new(5432).with_family(Some(IpFamily::V6));
+        assert!(!target.matches_remote(localhost, 5432));
+        assert!(target.matches_remote(IpAddr::V6(Ipv6Addr::LOCALHOST), 5432));
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            "3000".parse::<Target>().unwrap(),
+            Target::Port(PortTarget::new(3000))
+        );
+        assert_eq!(
+            "/run/app.sock".parse::<Target>().unwrap(),
+            Target::Unix("/run/app.sock".to_string())
+        );
+        assert_eq!(
+            "@dbus-abstract".parse::<Target>().unwrap(),
+            Target::Unix("@dbus-abstract".to_string())
+        );
+
+        // 相対パスは絶対パスに解決する
+        let target = "./app.sock".parse::<Target>().unwrap();
+        assert!(target.is_unix());
+        assert!(target.to_string().starts_with('/'));
+        assert!(target.to_string().ends_with("app.sock"));
+
+        assert!("[::1]:80"
+            .parse::<Target>()
+            .unwrap()
+            .port_target()
+            .is_some());
+        let result = "app".parse::<Target>();
+        assert!(result.unwrap_err().to_string().contains("socket path"));
+        assert!("@".parse::<Target>().is_err());
+    }
+
+    #[test]
+    fn test_unix_target_matches() {
+        let target = Target::Unix("/run/app.sock".to_string());
+        let mut socket = process_at("/run/app.sock", None);
+        socket.protocol = "unix".to_string();
+        socket.port = 0;
+        assert!(target.matches(&socket));
+
+        socket.address = "/run/other.sock".to_string();
+        assert!(!target.matches(&socket));
+
+        // 同じ文字列でもTCPソケットには一致しない
+        assert!(!target.matches(&process_at("/run/app.sock", None)));
+    }
+
+    #[test]
+    fn test_parse_target_spec() {
+        assert_eq!(
+            "8000-8010".parse::<TargetSpec>().unwrap(),
+            TargetSpec::Range {
+                start: 8000,
+                end: 8010,
+                family: None
+            }
+        );
+        assert_eq!(
+            ":9229".parse::<TargetSpec>().unwrap(),
+            TargetSpec::Single(Target::Port(PortTarget::new(9229)))
+        );
+        assert!("8010-8000".parse::<TargetSpec>().is_err());
+        assert!("0-10".parse::<TargetSpec>().is_err());
+        assert!("8000-70000".parse::<TargetSpec>().is_err());
+        // ハイフンを含むソケットパスは範囲ではない
+        assert!("/run/my-app.sock".parse::<TargetSpec>().unwrap().is_unix());
+    }
+
+    #[test]
+    fn test_range_matches() {
+        let range: TargetSpec = "3000-3010".parse().unwrap();
+        assert!(range.matches(&process_at("127.0.0.1", None)));
+
+        let mut other = process_at("127.0.0.1", None);
+        other.port = 3011;
+        assert!(!range.matches(&other));
+
+        let range = range.with_family(Some(IpFamily::V6));
+        assert!(!range.matches(&process_at("127.0.0.1", Some(IpFamily::V4))));
+    }
+
+    #[test]
+    fn test_display_address() {
+        assert_eq!(
+            process_at("*", Some(IpFamily::V4)).display_address(),
+            "0.0.0.0"
+        );
+        assert_eq!(
+            process_at("*", Some(IpFamily::V6)).display_address(),
+            "[::]"
+        );
+        assert_eq!(process_at("*", None).display_address(), "*");
+        assert_eq!(process_at("::1", None).display_address(), "[::1]");
+        assert_eq!(process_at("[::1]", None).display_address(), "[::1]");
+        assert_eq!(process_at("127.0.0.1", None).display_address(), "127.0.0.1");
+    }
+}
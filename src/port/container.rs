@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+
+use super::ProcessInfo;
+
+/// Where Docker keeps each container's `config.v2.json`
+const DOCKER_CONTAINERS_DIR: &str = "/var/lib/docker/containers";
+
+/// Container a listener belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerInfo {
+    /// Container runtime: `docker`, `podman`, `containerd` or `crio`
+    pub runtime: String,
+    /// Full container ID
+    pub id: String,
+    /// Container name, when the runtime's metadata is readable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// Docker Compose project the container was started from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Container address a `docker-proxy` forwards to, e.g. `172.17.0.2:5432`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarded_to: Option<String>,
+}
+
+impl ContainerInfo {
+    pub fn new(runtime: &str, id: &str) -> Self {
+        Self {
+            runtime: runtime.to_string(),
+            id: id.to_string(),
+            name: None,
+            image: None,
+            project: None,
+            forwarded_to: None,
+        }
+    }
+
+    /// Abbreviated ID as shown by `docker ps`
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(12)]
+    }
+}
+
+impl fmt::Display for ContainerInfo {
+    /// `docker:postgres (myapp)`, or `podman:3f2a9c1b2d4e` when the name is unknown
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match (&self.name, &self.forwarded_to) {
+            (Some(name), _) => name.as_str(),
+            // A docker-proxy whose container could not be looked up
+            (None, Some(forwarded_to)) if self.id.is_empty() => forwarded_to.as_str(),
+            (None, _) => self.short_id(),
+        };
+        write!(f, "{}:{}", self.runtime, label)?;
+        if let Some(project) = &self.project {
+            write!(f, " ({project})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Attach container details to every process that runs in a container or
+/// is a `docker-proxy` forwarding a published port.
+///
+/// Best effort: rows stay untouched when `/proc` or the runtime's metadata
+/// cannot be read (other platforms, insufficient permissions).
+pub fn attach_containers(processes: &mut [ProcessInfo]) {
+    let mut cache: HashMap<u32, Option<ContainerInfo>> = HashMap::new();
+
+    for process in processes.iter_mut().filter(|p| p.pid != 0) {
+        process.container = cache
+            .entry(process.pid)
+            .or_insert_with(|| container_of(process))
+            .clone();
+    }
+}
+
+fn container_of(process: &ProcessInfo) -> Option<ContainerInfo> {
+    if process.name == "docker-proxy" {
+        let (ip, port) = parse_proxy_args(&process.command)?;
+        let mut container =
+            find_docker_container_by_ip(ip).unwrap_or_else(|| ContainerInfo::new("docker", ""));
+        container.forwarded_to = Some(match ip {
+            IpAddr::V6(ip) => format!("[{ip}]:{port}"),
+            ip => format!("{ip}:{port}"),
+        });
+        return Some(container);
+    }
+
+    let cgroup = std::fs::read_to_string(format!("/proc/{}/cgroup", process.pid)).ok()?;
+    let (runtime, id) = parse_cgroup(&cgroup)?;
+    let mut container = ContainerInfo::new(runtime, &id);
+    if runtime == "docker" {
+        if let Some(config) = read_docker_config(&id) {
+            apply_docker_config(&mut container, &config);
+        }
+    }
+    Some(container)
+}
+
+/// Find the runtime and container ID in the contents of `/proc/<pid>/cgroup`.
+///
+/// Handles both cgroup drivers, e.g. `0::/docker/<id>` and
+/// `0::/system.slice/docker-<id>.scope`.
+pub(crate) fn parse_cgroup(content: &str) -> Option<(&'static str, String)> {
+    for line in content.lines() {
+        let path = line.splitn(3, ':').nth(2).unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').collect();
+
+        for (i, segment) in segments.iter().enumerate() {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            let parent = if i > 0 { segments[i - 1] } else { "" };
+
+            let found = [
+                ("docker-", "docker"),
+                ("libpod-conmon-", "podman"),
+                ("libpod-", "podman"),
+                ("cri-containerd-", "containerd"),
+                ("crio-", "crio"),
+            ]
+            .iter()
+            .find_map(|(prefix, runtime)| segment.strip_prefix(prefix).map(|id| (*runtime, id)))
+            .or(match parent {
+                "docker" => Some(("docker", segment)),
+                "libpod" => Some(("podman", segment)),
+                _ => None,
+            });
+
+            if let Some((runtime, id)) = found {
+                if is_container_id(id) {
+                    return Some((runtime, id.to_string()));
+                }
+            }
+        }
+    }
+    None
+}
+
+fn is_container_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Parse `-container-ip` and `-container-port` from a `docker-proxy` command line
+pub(crate) fn parse_proxy_args(command: &str) -> Option<(IpAddr, u16)> {
+    let args: Vec<&str> = command.split_whitespace().collect();
+    let value_of = |flag: &str| {
+        args.iter()
+            .position(|arg| *arg == flag)
+            .and_then(|i| args.get(i + 1))
+            .copied()
+    };
+
+    let ip = value_of("-container-ip")?.parse().ok()?;
+    let port = value_of("-container-port")?.parse().ok()?;
+    Some((ip, port))
+}
+
+fn read_docker_config(id: &str) -> Option<serde_json::Value> {
+    let path = format!("{DOCKER_CONTAINERS_DIR}/{id}/config.v2.json");
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// The running Docker container attached to a network with address `ip`
+fn find_docker_container_by_ip(ip: IpAddr) -> Option<ContainerInfo> {
+    let entries = std::fs::read_dir(DOCKER_CONTAINERS_DIR).ok()?;
+
+    entries.flatten().find_map(|entry| {
+        let id = entry.file_name().into_string().ok()?;
+        let config = read_docker_config(&id)?;
+        if !docker_config_has_ip(&config, ip) {
+            return None;
+        }
+        let mut container = ContainerInfo::new("docker", &id);
+        apply_docker_config(&mut container, &config);
+        Some(container)
+    })
+}
+
+/// Whether a running container described by `config.v2.json` has address `ip`
+pub(crate) fn docker_config_has_ip(config: &serde_json::Value, ip: IpAddr) -> bool {
+    if config["State"]["Running"].as_bool() != Some(true) {
+        return false;
+    }
+
+    let ip = ip.to_string();
+    config["NetworkSettings"]["Networks"]
+        .as_object()
+        .is_some_and(|networks| {
+            networks.values().any(|network| {
+                network["IPAddress"].as_str() == Some(ip.as_str())
+                    || network["GlobalIPv6Address"].as_str() == Some(ip.as_str())
+            })
+        })
+}
+
+/// Copy name, image and Compose project from a Docker `config.v2.json`
+pub(crate) fn apply_docker_config(container: &mut ContainerInfo, config: &serde_json::Value) {
+    container.name = config["Name"]
+        .as_str()
+        .map(|name| name.trim_start_matches('/').to_string());
+    container.image = config["Config"]["Image"].as_str().map(str::to_string);
+    container.project = config["Config"]["Labels"]["com.docker.compose.project"]
+        .as_str()
+        .map(str::to_string);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "3f2a9c1b2d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8";
+
+    #[test]
+    fn test_parse_cgroup() {
+        // cgroup v2 + systemdドライバ
+        let content = format!("0::/system.slice/docker-{ID}.scope\n");
+        assert_eq!(parse_cgroup(&content), Some(("docker", ID.to_string())));
+
+        // cgroupfsドライバ（v1）
+        let content = format!("12:pids:/docker/{ID}\n11:cpu:/docker/{ID}\n");
+        assert_eq!(parse_cgroup(&content), Some(("docker", ID.to_string())));
+
+        // rootless podman
+        let content = format!(
+            "0::/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{ID}.scope/container\n"
+        );
+        assert_eq!(parse_cgroup(&content), Some(("podman", ID.to_string())));
+
+        let content = format!("0::/kubepods.slice/kubepods-pod1.slice/cri-containerd-{ID}.scope\n");
+        assert_eq!(parse_cgroup(&content), Some(("containerd", ID.to_string())));
+    }
+
+    #[test]
+    fn test_parse_cgroup_host_process() {
+        assert_eq!(
+            parse_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+        assert_eq!(parse_cgroup("0::/system.slice/docker.service\n"), None);
+        assert_eq!(parse_cgroup(""), None);
+    }
+
+    #[test]
+    fn test_parse_proxy_args() {
+        let command = "/usr/bin/docker-proxy -proto tcp -host-ip 0.0.0.0 -host-port 5432 \
+                       -container-ip 172.17.0.2 -container-port 5432";
+        assert_eq!(
+            parse_proxy_args(command),
+            Some(("172.17.0.2".parse().unwrap(), 5432))
+        );
+        assert_eq!(parse_proxy_args("/usr/bin/docker-proxy -proto tcp"), None);
+    }
+
+    #[test]
+    fn test_docker_config() {
+        let config = serde_json::json!({
+            "Name": "/myapp-db-1",
+            "State": { "Running": true },
+            "Config": {
+                "Image": "postgres:16",
+                "Labels": { "com.docker.compose.project": "myapp" }
+            },
+            "NetworkSettings": {
+                "Networks": { "myapp_default": { "IPAddress": "172.18.0.2" } }
+            }
+        });
+
+        assert!(docker_config_has_ip(&config, "172.18.0.2".parse().unwrap()));
+        assert!(!docker_config_has_ip(
+            &config,
+            "172.18.0.3".parse().unwrap()
+        ));
+
+        let mut container = ContainerInfo::new("docker", ID);
+        apply_docker_config(&mut container, &config);
+        assert_eq!(container.name.as_deref(), Some("myapp-db-1"));
+        assert_eq!(container.image.as_deref(), Some("postgres:16"));
+        assert_eq!(container.to_string(), "docker:myapp-db-1 (myapp)");
+    }
+
+    #[test]
+    fn test_stopped_container_is_ignored() {
+        // 停止中のコンテナはIPを再利用されている可能性がある
+        let config = serde_json::json!({
+            "State": { "Running": false },
+            "NetworkSettings": { "Networks": { "bridge": { "IPAddress": "172.17.0.2" } } }
+        });
+        assert!(!docker_config_has_ip(
+            &config,
+            "172.17.0.2".parse().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_container_display_without_name() {
+        let container = ContainerInfo::new("podman", ID);
+        assert_eq!(container.short_id(), "3f2a9c1b2d4e");
+        assert_eq!(container.to_string(), "podman:3f2a9c1b2d4e");
+
+        let mut proxy = ContainerInfo::new("docker", "");
+        proxy.forwarded_to = Some("172.17.0.2:5432".to_string());
+        assert_eq!(proxy.to_string(), "docker:172.17.0.2:5432");
+    }
+}
@@ -0,0 +1,252 @@
+use super::lease::{Lease, LeaseRegistry, RESERVATION_TTL};
+use super::{PortBackend, ProcessInfo};
+use crate::Result;
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, UdpSocket};
+use std::ops::RangeInclusive;
+
+/// Ports the administrator keeps out of ephemeral allocation (Linux only)
+const RESERVED_PORTS_PATH: &str = "/proc/sys/net/ipv4/ip_local_reserved_ports";
+
+/// First port that does not need root to bind
+const FIRST_UNPRIVILEGED_PORT: u16 = 1024;
+
+/// Parse `ip_local_reserved_ports`, e.g. `8080,9000-9010`
+pub(crate) fn parse_reserved_ports(content: &str) -> Vec<RangeInclusive<u16>> {
+    content
+        .trim()
+        .split(',')
+        .filter_map(|part| {
+            let part = part.trim();
+            match part.split_once('-') {
+                Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+                None => {
+                    let port = part.parse().ok()?;
+                    Some(port..=port)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Reserved port ranges, or none where the kernel does not report them
+pub fn reserved_ports() -> Vec<RangeInclusive<u16>> {
+    std::fs::read_to_string(RESERVED_PORTS_PATH)
+        .map(|content| parse_reserved_ports(&content))
+        .unwrap_or_default()
+}
+
+/// Whether a server could bind `port` right now.
+///
+/// Both wildcard addresses are tried, so a listener on a single address or
+/// on IPv6 only also makes the port unusable. Hosts without IPv6 only test
+/// IPv4.
+pub fn can_bind(port: u16, protocol: &str) -> bool {
+    probe_bind(port, protocol) == Some(true)
+}
+
+/// Like [`can_bind`], but `None` when the bind fails for another reason than
+/// the port being in use, e.g. a privileged port without root
+pub fn probe_bind(port: u16, protocol: &str) -> Option<bool> {
+    let bind = |address: SocketAddr| -> std::io::Result<()> {
+        if protocol == "udp" {
+            UdpSocket::bind(address).map(drop)
+        } else {
+            TcpListener::bind(address).map(drop)
+        }
+    };
+
+    match bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, port))) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => return Some(false),
+        Err(_) => return None,
+    }
+    match bind(SocketAddr::from((Ipv6Addr::UNSPECIFIED, port))) {
+        Ok(()) => Some(true),
+        Err(e) => Some(e.kind() != std::io::ErrorKind::AddrInUse),
+    }
+}
+
+/// Finds ports that no process listens on, the kernel does not reserve,
+/// no kilar lease claims and that can actually be bound
+#[derive(Debug, Clone)]
+pub struct FreePortFinder {
+    protocol: String,
+    used: HashSet<u16>,
+    reserved: Vec<RangeInclusive<u16>>,
+    leased: HashSet<u16>,
+}
+
+impl FreePortFinder {
+    pub fn new(protocol: &str, listeners: &[ProcessInfo]) -> Self {
+        Self {
+            protocol: protocol.to_string(),
+            used: listeners.iter().map(|p| p.port).collect(),
+            reserved: reserved_ports(),
+            leased: HashSet::new(),
+        }
+    }
+
+    /// Also skip the ports of `leases`
+    pub fn with_leases(mut self, leases: &[Lease]) -> Self {
+        self.leased = leases.iter().map(|lease| lease.port).collect();
+        self
+    }
+
+    /// Build a finder from the listener table of `backend`
+    pub async fn scan(backend: &dyn PortBackend, protocol: &str) -> Result<Self> {
+        Ok(Self::new(
+            protocol,
+            &backend.list_processes(protocol).await?,
+        ))
+    }
+
+    /// Whether the listener table, the reserved ranges and the leases allow `port`
+    pub(crate) fn is_unclaimed(&self, port: u16) -> bool {
+        port != 0
+            && !self.used.contains(&port)
+            && !self.leased.contains(&port)
+            && !self.reserved.iter().any(|range| range.contains(&port))
+    }
+
+    pub fn is_free(&self, port: u16) -> bool {
+        self.is_unclaimed(port) && can_bind(port, &self.protocol)
+    }
+
+    /// Up to `count` free ports in `range`, lowest first
+    pub fn find(&self, range: RangeInclusive<u16>, count: usize) -> Vec<u16> {
+        range
+            .filter(|port| self.is_free(*port))
+            .take(count)
+            .collect()
+    }
+
+    /// Like [`find`](Self::find), but reserve every port in `registry` for
+    /// [`RESERVATION_TTL`] so that no concurrent caller gets it as well
+    pub fn claim(
+        &self,
+        range: RangeInclusive<u16>,
+        count: usize,
+        registry: &LeaseRegistry,
+    ) -> Result<Vec<u16>> {
+        let mut ports = Vec::new();
+        for port in range.filter(|port| self.is_free(*port)) {
+            if ports.len() == count {
+                break;
+            }
+            let reservation = Lease::reservation(port, &self.protocol, RESERVATION_TTL);
+            if registry.acquire(&reservation)?.is_none() {
+                ports.push(port);
+            }
+        }
+        Ok(ports)
+    }
+
+    /// The free port closest to `port`, preferring the higher one on a tie.
+    ///
+    /// Privileged ports are only suggested for a privileged `port`.
+    pub fn nearest(&self, port: u16) -> Option<u16> {
+        Self::around(port).find(|candidate| self.is_free(*candidate))
+    }
+
+    /// Candidates for [`nearest`](Self::nearest), closest to `port` first
+    pub(crate) fn around(port: u16) -> impl Iterator<Item = u16> {
+        let floor = if port >= FIRST_UNPRIVILEGED_PORT {
+            FIRST_UNPRIVILEGED_PORT
+        } else {
+            1
+        };
+        (1..=u16::MAX)
+            .flat_map(move |distance| [port.checked_add(distance), port.checked_sub(distance)])
+            .flatten()
+            .filter(move |candidate| *candidate >= floor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::testing::unused_port;
+
+    fn finder_with(used: &[u16], reserved: &str) -> FreePortFinder {
+        FreePortFinder {
+            protocol: "tcp".to_string(),
+            used: used.iter().copied().collect(),
+            reserved: parse_reserved_ports(reserved),
+            leased: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_reserved_ports() {
+        let ranges = parse_reserved_ports("8080,9000-9010\n");
+        assert_eq!(ranges, vec![8080..=8080, 9000..=9010]);
+        assert!(parse_reserved_ports("\n").is_empty());
+    }
+
+    #[test]
+    fn test_find_skips_used_and_reserved() {
+        let used = [unused_port(), unused_port()];
+        let reserved = unused_port();
+        let finder = finder_with(&used, &reserved.to_string());
+
+        for port in used.into_iter().chain([reserved]) {
+            assert!(finder.find(port..=port, 1).is_empty());
+        }
+        let range = 45000..=45999;
+        let found = finder.find(range.clone(), 3);
+        assert_eq!(found.len(), 3);
+        assert!(found.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(found.iter().all(|port| range.contains(port)));
+    }
+
+    #[test]
+    fn test_find_skips_unbindable_ports() {
+        // リスナー表に載っていなくても、bindできないポートは除外する
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert!(!can_bind(port, "tcp"));
+
+        let finder = finder_with(&[], "");
+        assert!(!finder.find(port..=port, 1).contains(&port));
+        assert_eq!(probe_bind(port, "tcp"), Some(false));
+    }
+
+    #[test]
+    fn test_claim_never_hands_out_a_port_twice() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        let finder = finder_with(&[], "");
+        let range = 45000..=45999;
+
+        let first = finder.claim(range.clone(), 2, &registry).unwrap();
+        let second = finder.claim(range.clone(), 2, &registry).unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 2);
+        assert!(second.iter().all(|port| !first.contains(port)));
+        assert!(first.iter().chain(&second).all(|port| range.contains(port)));
+
+        // 予約済みのポートは提案しない
+        let finder = finder.with_leases(&registry.active("tcp"));
+        let found = finder.find(range, 1);
+        assert_eq!(found.len(), 1);
+        assert!(!first.contains(&found[0]) && !second.contains(&found[0]));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let port = unused_port();
+        let used = [port, port.saturating_add(1)];
+        let finder = finder_with(&used, "");
+        let nearest = finder.nearest(port).unwrap();
+        assert!(!used.contains(&nearest));
+        assert!(FreePortFinder::around(port)
+            .take_while(|candidate| *candidate != nearest)
+            .all(|candidate| !finder.is_free(candidate)));
+
+        // 同じ距離なら上のポートを先に試す
+        let candidates: Vec<u16> = FreePortFinder::around(45300).take(4).collect();
+        assert_eq!(candidates, vec![45301, 45299, 45302, 45298]);
+        assert!(FreePortFinder::around(1030).all(|candidate| candidate >= 1024));
+    }
+}
@@ -0,0 +1,457 @@
+use super::ProcessInfo;
+use crate::process::{self, ProcessRef};
+use crate::Result;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a port printed by `kilar free` stays reserved for the caller
+pub const RESERVATION_TTL: Duration = Duration::from_secs(30);
+
+/// A claim on a port recorded in the [`LeaseRegistry`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lease {
+    pub port: u16,
+    pub protocol: String,
+    /// `kilar hold` process keeping the port bound; `None` for a reservation
+    /// made by `kilar free`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// Start time of `pid`, so that a recycled PID does not keep the lease alive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// When the lease lapses; `None` until it is released
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Lease {
+    fn new(port: u16, protocol: &str, ttl: Option<Duration>) -> Self {
+        let created_at = Utc::now();
+        Self {
+            port,
+            protocol: protocol.to_string(),
+            pid: None,
+            start_time: None,
+            label: None,
+            created_at,
+            expires_at: ttl.and_then(|ttl| {
+                created_at.checked_add_signed(chrono::Duration::from_std(ttl).ok()?)
+            }),
+        }
+    }
+
+    /// Lease for a port the current process keeps bound
+    pub fn hold(port: u16, protocol: &str, label: Option<String>, ttl: Option<Duration>) -> Self {
+        let pid = std::process::id();
+        Self {
+            pid: Some(pid),
+            start_time: process::tree::start_time(pid),
+            label,
+            ..Self::new(port, protocol, ttl)
+        }
+    }
+
+    /// Short reservation for a port handed out without binding it
+    pub fn reservation(port: u16, protocol: &str, ttl: Duration) -> Self {
+        Self::new(port, protocol, Some(ttl))
+    }
+
+    pub fn is_reservation(&self) -> bool {
+        self.pid.is_none()
+    }
+
+    /// Whether the lease still protects its port: it has not expired and
+    /// its holder is still running
+    pub fn is_active(&self) -> bool {
+        let expired = self.expires_at.is_some_and(|at| at <= Utc::now());
+        let holder_running = self.pid.is_none_or(|pid| {
+            process::is_running(ProcessRef {
+                pid,
+                start_time: self.start_time,
+            })
+        });
+        !expired && holder_running
+    }
+}
+
+impl fmt::Display for Lease {
+    /// `ci-shard-1 (until 14:05:00)`, or `held` / `reserved` without a label
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.label, self.is_reservation()) {
+            (Some(label), _) => f.write_str(label)?,
+            (None, false) => f.write_str("held")?,
+            (None, true) => f.write_str("reserved")?,
+        }
+        if let Some(expires_at) = self.expires_at {
+            write!(
+                f,
+                " (until {})",
+                expires_at.with_timezone(&Local).format("%H:%M:%S")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Leases shared by every kilar process of the current user, one JSON file
+/// per port.
+///
+/// A lease is created by hard-linking a fully written temporary file into
+/// place, which fails atomically when the port is already leased, so
+/// concurrent `kilar free` and `kilar hold` calls never claim the same port.
+/// Stale leases are only cleared under a lock on the directory.
+#[derive(Debug, Clone)]
+pub struct LeaseRegistry {
+    dir: PathBuf,
+}
+
+impl Default for LeaseRegistry {
+    fn default() -> Self {
+        Self::new(Self::default_dir())
+    }
+}
+
+impl LeaseRegistry {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `$XDG_RUNTIME_DIR/kilar/leases`, or a per-user directory in the
+    /// temporary directory where no runtime directory is set
+    pub fn default_dir() -> PathBuf {
+        match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+            Some(runtime_dir) => PathBuf::from(runtime_dir).join("kilar").join("leases"),
+            None => std::env::temp_dir()
+                .join(format!("kilar-{}", unsafe { libc::getuid() }))
+                .join("leases"),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, protocol: &str, port: u16) -> PathBuf {
+        self.dir.join(format!("{protocol}-{port}.json"))
+    }
+
+    fn read(path: &Path) -> Option<Lease> {
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+
+    /// Create the lease directory, or check that an existing one is private
+    /// to the current user.
+    ///
+    /// The default directory may lie in the shared temporary directory, where
+    /// another user could create it first and plant leases naming processes
+    /// for `kilar release` to kill.
+    pub fn open(&self) -> Result<()> {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&self.dir)?;
+        self.check_dir()
+    }
+
+    /// Fail unless the lease directory belongs to the current user, no one
+    /// else can write to it, and no one else can replace it in its parent
+    fn check_dir(&self) -> Result<()> {
+        let uid = unsafe { libc::getuid() };
+        let refuse = |path: &Path, reason: &str| {
+            crate::Error::Other(format!(
+                "Refusing to use the lease directory {}: {reason}",
+                path.display()
+            ))
+        };
+
+        let metadata = fs::symlink_metadata(&self.dir)?;
+        if !metadata.is_dir() {
+            return Err(refuse(&self.dir, "it is not a directory"));
+        }
+        if metadata.uid() != uid {
+            return Err(refuse(&self.dir, "it is owned by another user"));
+        }
+        if metadata.mode() & 0o022 != 0 {
+            return Err(refuse(&self.dir, "other users can write to it"));
+        }
+        // /tmp のようなスティッキービット付きの親なら他人は名前を変えられない
+        if let Some(parent) = self.dir.parent().filter(|p| !p.as_os_str().is_empty()) {
+            let metadata = fs::metadata(parent)?;
+            let foreign_owner = metadata.uid() != uid && metadata.uid() != 0;
+            let shared = metadata.mode() & 0o022 != 0 && metadata.mode() & 0o1000 == 0;
+            if foreign_owner || shared {
+                return Err(refuse(
+                    &self.dir,
+                    "other users can replace it in its parent directory",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `lease` to a temporary file next to its final path
+    fn write_temp(&self, lease: &Lease) -> Result<PathBuf> {
+        self.open()?;
+        let temp = self.dir.join(format!(
+            ".{}-{}.{}.{}.tmp",
+            lease.protocol,
+            lease.port,
+            std::process::id(),
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::write(&temp, serde_json::to_vec(lease)?)?;
+        Ok(temp)
+    }
+
+    /// Record `lease` unless an active lease already covers its port.
+    ///
+    /// Returns the lease in the way, or `None` once `lease` is recorded.
+    /// Expired leases and leases of exited holders are replaced.
+    pub fn acquire(&self, lease: &Lease) -> Result<Option<Lease>> {
+        let temp = self.write_temp(lease)?;
+        let result = self.link(&temp, &self.path(&lease.protocol, lease.port));
+        let _ = fs::remove_file(&temp);
+        result
+    }
+
+    fn link(&self, temp: &Path, path: &Path) -> Result<Option<Lease>> {
+        match fs::hard_link(temp, path) {
+            Ok(()) => return Ok(None),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        // 期限切れのリースの片付けと取り直しは、他のプロセスと同時に行わない
+        let _lock = self.lock()?;
+        match Self::read(path) {
+            Some(existing) if existing.is_active() => return Ok(Some(existing)),
+            _ => {
+                let _ = fs::remove_file(path);
+            }
+        }
+        match fs::hard_link(temp, path) {
+            Ok(()) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(Self::read(path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Take the registry's lock, held until the returned file is dropped.
+    ///
+    /// Removing a lease is only safe under the lock: otherwise two callers
+    /// that read the same stale lease could each remove the other's new one.
+    fn lock(&self) -> Result<fs::File> {
+        use std::os::fd::AsRawFd;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .mode(0o600)
+            .open(self.dir.join(".lock"))?;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(file)
+    }
+
+    /// Record `lease`, replacing any lease on its port
+    pub fn replace(&self, lease: &Lease) -> Result<()> {
+        let temp = self.write_temp(lease)?;
+        fs::rename(&temp, self.path(&lease.protocol, lease.port)).inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })?;
+        Ok(())
+    }
+
+    /// The recorded lease on `port`, active or not
+    pub fn get(&self, protocol: &str, port: u16) -> Option<Lease> {
+        self.check_dir().ok()?;
+        Self::read(&self.path(protocol, port))
+    }
+
+    /// Remove `lease` if it is still the one recorded for its port, so a
+    /// holder that lost its lease never removes its successor's
+    pub fn remove(&self, lease: &Lease) -> Result<bool> {
+        let path = self.path(&lease.protocol, lease.port);
+        let _lock = self.lock()?;
+        if Self::read(&path).as_ref() != Some(lease) {
+            return Ok(false);
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Active leases for `protocol` (`all` for every protocol), lowest port first
+    pub fn active(&self, protocol: &str) -> Vec<Lease> {
+        if self.check_dir().is_err() {
+            return Vec::new();
+        }
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut leases: Vec<Lease> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| Self::read(&path))
+            .filter(|lease| protocol == "all" || lease.protocol == protocol)
+            .filter(Lease::is_active)
+            .collect();
+        leases.sort_by_key(|lease| lease.port);
+        leases
+    }
+}
+
+/// Attach the lease to every listener that `kilar hold` keeps bound
+pub fn attach_leases(processes: &mut [ProcessInfo]) {
+    let leases = LeaseRegistry::default().active("all");
+    if leases.is_empty() {
+        return;
+    }
+
+    for process in processes.iter_mut() {
+        process.lease = leases
+            .iter()
+            .find(|lease| lease.pid == Some(process.pid) && lease.port == process.port)
+            .cloned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 終了済みプロセスのPID
+    fn exited_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn test_acquire_conflicts_with_active_lease() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+
+        let first = Lease::hold(45500, "tcp", Some("shard-1".to_string()), None);
+        assert_eq!(registry.acquire(&first).unwrap(), None);
+
+        let second = Lease::hold(45500, "tcp", Some("shard-2".to_string()), None);
+        assert_eq!(registry.acquire(&second).unwrap(), Some(first.clone()));
+
+        // プロトコルが異なれば別のリース
+        let udp = Lease::reservation(45500, "udp", RESERVATION_TTL);
+        assert_eq!(registry.acquire(&udp).unwrap(), None);
+        assert_eq!(registry.active("tcp"), vec![first]);
+        assert_eq!(registry.active("all").len(), 2);
+    }
+
+    #[test]
+    fn test_acquire_replaces_stale_leases() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+
+        let mut exited = Lease::hold(45501, "tcp", None, None);
+        exited.pid = Some(exited_pid());
+        exited.start_time = None;
+        registry.replace(&exited).unwrap();
+
+        let expired = Lease::reservation(45502, "tcp", Duration::ZERO);
+        registry.replace(&expired).unwrap();
+        assert!(registry.active("tcp").is_empty());
+
+        for port in [45501, 45502] {
+            let lease = Lease::reservation(port, "tcp", RESERVATION_TTL);
+            assert_eq!(registry.acquire(&lease).unwrap(), None);
+        }
+        assert_eq!(registry.active("tcp").len(), 2);
+    }
+
+    #[test]
+    fn test_concurrent_reclaim_hands_out_port_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+        registry
+            .replace(&Lease::reservation(45505, "tcp", Duration::ZERO))
+            .unwrap();
+
+        let winners = std::thread::scope(|scope| {
+            let claims: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let lease = Lease::reservation(45505, "tcp", RESERVATION_TTL);
+                        registry.acquire(&lease).unwrap().is_none()
+                    })
+                })
+                .collect();
+            claims
+                .into_iter()
+                .map(|claim| claim.join().unwrap())
+                .filter(|won| *won)
+                .count()
+        });
+        assert_eq!(winners, 1);
+        assert_eq!(registry.active("tcp").len(), 1);
+    }
+
+    #[test]
+    fn test_remove_only_own_lease() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path());
+
+        let reservation = Lease::reservation(45503, "tcp", RESERVATION_TTL);
+        registry.acquire(&reservation).unwrap();
+        let hold = Lease::hold(45503, "tcp", None, None);
+        registry.replace(&hold).unwrap();
+
+        assert!(!registry.remove(&reservation).unwrap());
+        assert_eq!(registry.get("tcp", 45503), Some(hold.clone()));
+        assert!(registry.remove(&hold).unwrap());
+        assert_eq!(registry.get("tcp", 45503), None);
+    }
+
+    #[test]
+    fn test_refuses_shared_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LeaseRegistry::new(dir.path().join("leases"));
+        let lease = Lease::reservation(45504, "tcp", RESERVATION_TTL);
+        assert_eq!(registry.acquire(&lease).unwrap(), None);
+
+        fs::set_permissions(registry.dir(), fs::Permissions::from_mode(0o777)).unwrap();
+        let err = registry.acquire(&lease).unwrap_err().to_string();
+        assert!(err.contains("other users can write to it"), "{err}");
+        assert_eq!(registry.get("tcp", 45504), None);
+        assert!(registry.active("all").is_empty());
+
+        fs::set_permissions(registry.dir(), fs::Permissions::from_mode(0o700)).unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(registry.open().is_err());
+    }
+
+    #[test]
+    fn test_lease_display() {
+        let mut lease = Lease::hold(3000, "tcp", Some("shard-1".to_string()), None);
+        assert_eq!(lease.to_string(), "shard-1");
+        lease.label = None;
+        assert_eq!(lease.to_string(), "held");
+        let reservation = Lease::reservation(3000, "tcp", RESERVATION_TTL);
+        assert!(reservation.to_string().starts_with("reserved (until "));
+    }
+}
@@ -1,11 +1,42 @@
+use crate::process::{systemd::SystemdUnit, tree::Ancestor};
 use crate::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::OnceLock;
 use tokio::process::Command as TokioCommand;
 
+pub mod address;
+pub mod backend;
+pub mod container;
+pub mod free;
+pub mod lease;
+pub mod netlink;
+pub mod netns;
 pub mod procfs;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod project;
+pub mod registry;
+pub mod state;
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub use address::{IpFamily, PortTarget, Target, TargetSpec};
+pub use backend::{
+    create_backend, create_backend_for, AutoBackend, BackendKind, PortBackend, ProgressFn,
+};
+pub use container::{attach_containers, ContainerInfo};
+pub use free::FreePortFinder;
+pub use lease::{attach_leases, Lease, LeaseRegistry};
+pub use netns::NetnsScope;
+pub use project::{discover_project_ports, ProjectPort};
+pub use registry::{attach_registry, PortRegistry, RegistryEntry, RegistryStatus};
+pub use state::TcpState;
+
+/// A socket and the process holding it.
+///
+/// Backends fill in the fields they know; the optional ones are attached
+/// later or left empty. Build one with [`ProcessInfo::new`] or with
+/// `..Default::default()` so that new fields do not break callers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
@@ -17,6 +48,50 @@ pub struct ProcessInfo {
     pub address: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inode: Option<u64>, // For procfs-based implementation
+    /// File descriptor the process holds the socket on, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fd: Option<u32>,
+    /// Address family of the socket, when the backend reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family: Option<IpFamily>,
+    /// TCP connection state; `None` for UDP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<TcpState>,
+    /// Network namespace inode, set when scanning with `--all-namespaces` / `--netns`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netns: Option<u64>,
+    /// Container the process runs in or forwards to (`docker-proxy`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<ContainerInfo>,
+    /// systemd unit that manages the process or, for socket activation, the socket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<SystemdUnit>,
+    /// Parent processes, nearest first, when the tree view is requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ancestors: Option<Vec<Ancestor>>,
+    /// Start time of the process (clock ticks since boot) recorded at scan
+    /// time, used to detect a recycled PID before signalling it
+    #[serde(skip)]
+    pub start_time: Option<u64>,
+    /// Lease of a port that `kilar hold` keeps bound
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lease: Option<Lease>,
+    /// Names of the `[registry]` entries declaring this port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+}
+
+impl ProcessInfo {
+    /// A process holding `port`, with every optional field empty
+    pub fn new(pid: u32, name: impl Into<String>, port: u16, protocol: impl Into<String>) -> Self {
+        Self {
+            pid,
+            name: name.into(),
+            port,
+            protocol: protocol.into(),
+            ..Self::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -46,6 +121,11 @@ impl PortManager {
         self.check_port_unix_optimized(port, protocol).await
     }
 
+    /// Find every process holding a socket on `port`
+    pub async fn check_port_all(&self, port: u16, protocol: &str) -> Result<Vec<ProcessInfo>> {
+        self.check_port_all_unix(port, protocol).await
+    }
+
     pub async fn list_processes(&self, protocol: &str) -> Result<Vec<ProcessInfo>> {
         self.list_processes_unix(protocol).await
     }
@@ -76,16 +156,18 @@ impl PortManager {
         let ss_result = self.try_ss(protocol).await;
 
         // Merge results from both sources, preferring lsof data when available
-        // but including ports only found by ss
+        // but including ports only found by ss. Every process holding a port
+        // is kept, and a process bound to several addresses on the same port
+        // (e.g. 127.0.0.1 and [::1]) keeps one row per address.
         let mut merged_results: Vec<ProcessInfo> = Vec::new();
-        let mut seen_ports: HashSet<(u16, String)> = HashSet::new();
+        let mut seen_sockets: HashSet<(u16, String, String, u32)> = HashSet::new();
+        let mut lsof_ports: HashSet<(u16, String)> = HashSet::new();
 
         // Add lsof results first (they tend to have more complete process info)
         if let Ok(lsof_processes) = lsof_result {
             for process in lsof_processes {
-                let key = (process.port, process.protocol.clone());
-                if !seen_ports.contains(&key) {
-                    seen_ports.insert(key);
+                lsof_ports.insert((process.port, process.protocol.clone()));
+                if seen_sockets.insert(socket_key(&process)) {
                     merged_results.push(process);
                 }
             }
@@ -94,9 +176,10 @@ impl PortManager {
         // Add ss results for ports not found by lsof
         if let Ok(ss_processes) = ss_result {
             for process in ss_processes {
-                let key = (process.port, process.protocol.clone());
-                if !seen_ports.contains(&key) {
-                    seen_ports.insert(key);
+                if lsof_ports.contains(&(process.port, process.protocol.clone())) {
+                    continue;
+                }
+                if seen_sockets.insert(socket_key(&process)) {
                     merged_results.push(process);
                 }
             }
@@ -117,15 +200,28 @@ impl PortManager {
         port: u16,
         protocol: &str,
     ) -> Result<Option<ProcessInfo>> {
+        Ok(self
+            .check_port_all_unix(port, protocol)
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Find every holder of a specific port, falling back lsof → ss → netstat
+    async fn check_port_all_unix(&self, port: u16, protocol: &str) -> Result<Vec<ProcessInfo>> {
         // Try lsof for specific port first - much faster than scanning all ports
         // Only return if we found a process, otherwise fall through to next method
-        if let Ok(Some(result)) = self.try_lsof_specific_port(port, protocol).await {
-            return Ok(Some(result));
+        if let Ok(result) = self.try_lsof_specific_port(port, protocol).await {
+            if !result.is_empty() {
+                return Ok(result);
+            }
         }
 
         // Fallback to ss for specific port
-        if let Ok(Some(result)) = self.try_ss_specific_port(port, protocol).await {
-            return Ok(Some(result));
+        if let Ok(result) = self.try_ss_specific_port(port, protocol).await {
+            if !result.is_empty() {
+                return Ok(result);
+            }
         }
 
         // Final fallback: netstat for specific port
@@ -157,16 +253,18 @@ impl PortManager {
         let ss_result = self.try_ss(protocol).await;
 
         // Merge results from both sources, preferring lsof data when available
-        // but including ports only found by ss
+        // but including ports only found by ss. Every process holding a port
+        // is kept, and a process bound to several addresses on the same port
+        // (e.g. 127.0.0.1 and [::1]) keeps one row per address.
         let mut merged_results: Vec<ProcessInfo> = Vec::new();
-        let mut seen_ports: HashSet<(u16, String)> = HashSet::new();
+        let mut seen_sockets: HashSet<(u16, String, String, u32)> = HashSet::new();
+        let mut lsof_ports: HashSet<(u16, String)> = HashSet::new();
 
         // Add lsof results first (they tend to have more complete process info)
         if let Ok(lsof_processes) = lsof_result {
             for process in lsof_processes {
-                let key = (process.port, process.protocol.clone());
-                if !seen_ports.contains(&key) {
-                    seen_ports.insert(key);
+                lsof_ports.insert((process.port, process.protocol.clone()));
+                if seen_sockets.insert(socket_key(&process)) {
                     merged_results.push(process);
                 }
             }
@@ -175,9 +273,10 @@ impl PortManager {
         // Add ss results for ports not found by lsof
         if let Ok(ss_processes) = ss_result {
             for process in ss_processes {
-                let key = (process.port, process.protocol.clone());
-                if !seen_ports.contains(&key) {
-                    seen_ports.insert(key);
+                if lsof_ports.contains(&(process.port, process.protocol.clone())) {
+                    continue;
+                }
+                if seen_sockets.insert(socket_key(&process)) {
                     merged_results.push(process);
                 }
             }
@@ -277,12 +376,8 @@ impl PortManager {
             .await
     }
 
-    /// Fast lsof check for specific port
-    async fn try_lsof_specific_port(
-        &self,
-        port: u16,
-        protocol: &str,
-    ) -> Result<Option<ProcessInfo>> {
+    /// Fast lsof check for specific port, returning every holder
+    async fn try_lsof_specific_port(&self, port: u16, protocol: &str) -> Result<Vec<ProcessInfo>> {
         let protocol_flag = match protocol.to_lowercase().as_str() {
             "tcp" => "-iTCP",
             "udp" => "-iUDP",
@@ -297,14 +392,14 @@ impl PortManager {
             .await?;
 
         if !output.status.success() {
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let lines: Vec<&str> = stdout.lines().collect();
+        let mut processes = Vec::new();
 
         // Skip header line
-        for line in lines.iter().skip(1) {
+        for line in stdout.lines().skip(1) {
             if line.trim().is_empty() {
                 continue;
             }
@@ -312,9 +407,8 @@ impl PortManager {
             let parts: Vec<&str> = line.split_whitespace().collect();
             if parts.len() >= 9 {
                 if let Ok(pid) = parts[1].parse::<u32>() {
-                    // Parse address:port from lsof output
-                    let name_col = parts[8];
-                    if name_col.contains(&format!(":{}", port)) {
+                    let (address, local_port) = parse_lsof_name(parts[8]);
+                    if local_port == Some(port) {
                         let full_command = self
                             .get_process_command(pid)
                             .await
@@ -329,7 +423,7 @@ impl PortManager {
                             .await
                             .unwrap_or_else(|_| "Unknown".to_string());
 
-                        return Ok(Some(ProcessInfo {
+                        processes.push(ProcessInfo {
                             pid,
                             name,
                             command: full_command,
@@ -337,15 +431,18 @@ impl PortManager {
                             working_directory,
                             port,
                             protocol: protocol.to_string(),
-                            address: name_col.split(':').next().unwrap_or("*").to_string(),
-                            inode: None,
-                        }));
+                            address: address.to_string(),
+                            fd: parse_lsof_fd(parts[3]),
+                            family: lsof_family(parts[4]),
+                            state: parts.get(9).and_then(|s| TcpState::from_tool_name(s)),
+                            ..Default::default()
+                        });
                     }
                 }
             }
         }
 
-        Ok(None)
+        Ok(processes)
     }
 
     async fn try_lsof_with_callback<F>(
@@ -536,20 +633,23 @@ impl PortManager {
             }
             .to_string();
 
-            basic_process_info.push((pid, command, port, protocol, address));
+            let fd = parse_lsof_fd(fields[3]);
+            let family = lsof_family(type_field);
+
+            basic_process_info.push((pid, command, port, protocol, address, fd, family));
         }
 
         // Extract unique PIDs for batch processing
         let pids: Vec<u32> = basic_process_info
             .iter()
-            .map(|(pid, _, _, _, _)| *pid)
+            .map(|(pid, _, _, _, _, _, _)| *pid)
             .collect();
 
         // Get all process details in a single lsof call
         let process_details = self.get_all_process_details(&pids).await?;
 
         // Second pass: build ProcessInfo with detailed information
-        for (pid, command, port, protocol, address) in basic_process_info {
+        for (pid, command, port, protocol, address, fd, family) in basic_process_info {
             // Use command from lsof as fallback instead of calling ps individually
             let full_command = command.to_string();
 
@@ -574,6 +674,8 @@ impl PortManager {
                     )
                 };
 
+            let state = listen_state(&protocol);
+
             processes.push(ProcessInfo {
                 pid,
                 name,
@@ -583,15 +685,18 @@ impl PortManager {
                 port,
                 protocol,
                 address,
-                inode: None, // Legacy implementation doesn't track inodes
+                fd,
+                family,
+                state,
+                ..Default::default()
             });
         }
 
         Ok(processes)
     }
 
-    /// Fast ss check for specific port
-    async fn try_ss_specific_port(&self, port: u16, protocol: &str) -> Result<Option<ProcessInfo>> {
+    /// Fast ss check for specific port, returning every holder
+    async fn try_ss_specific_port(&self, port: u16, protocol: &str) -> Result<Vec<ProcessInfo>> {
         let mut cmd = TokioCommand::new("ss");
         cmd.arg("-n") // Numeric display
             .arg("-p"); // Process info
@@ -613,10 +718,12 @@ impl PortManager {
 
         let output = cmd.output().await?;
         if !output.status.success() {
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut processes = Vec::new();
+
         for line in stdout.lines().skip(1) {
             if line.trim().is_empty() {
                 continue;
@@ -627,58 +734,52 @@ impl PortManager {
                 continue;
             }
 
-            // Extract PID from process info (usually in format "users:(("command",pid=1234,fd=1))")
-            if let Some(process_part) = parts.iter().find(|&p| p.contains("pid=")) {
-                if let Some(pid_start) = process_part.find("pid=") {
-                    if let Some(pid_end) = process_part[pid_start + 4..].find(',') {
-                        if let Ok(pid) =
-                            process_part[pid_start + 4..pid_start + 4 + pid_end].parse::<u32>()
-                        {
-                            let full_command = self
-                                .get_process_command(pid)
-                                .await
-                                .unwrap_or_else(|_| "Unknown".to_string());
-                            let name = self.extract_process_name(&full_command);
-                            let executable_path = self
-                                .get_process_executable(pid)
-                                .await
-                                .unwrap_or_else(|_| self.extract_executable_path(&full_command));
-                            let working_directory = self
-                                .get_process_working_directory(pid)
-                                .await
-                                .unwrap_or_else(|_| "Unknown".to_string());
-                            {
-                                // Parse the local address to get the port
-                                if let Some(local_addr) = parts.get(3) {
-                                    if local_addr.ends_with(&format!(":{}", port)) {
-                                        let address = if let Some(colon_pos) = local_addr.rfind(':')
-                                        {
-                                            local_addr[..colon_pos].to_string()
-                                        } else {
-                                            "*".to_string()
-                                        };
-
-                                        return Ok(Some(ProcessInfo {
-                                            pid,
-                                            name,
-                                            command: full_command,
-                                            executable_path,
-                                            working_directory,
-                                            port,
-                                            protocol: protocol.to_string(),
-                                            address,
-                                            inode: None,
-                                        }));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            // Parse the local address to get the port
+            let local_addr = parts[3];
+            if !local_addr.ends_with(&format!(":{}", port)) {
+                continue;
+            }
+            let address = if let Some(colon_pos) = local_addr.rfind(':') {
+                local_addr[..colon_pos].to_string()
+            } else {
+                "*".to_string()
+            };
+
+            // Every holder is listed in users:(("a",pid=1,fd=5),("b",pid=2,fd=5))
+            let users = parts[5..].join(" ");
+            for (pid, fd) in parse_ss_users(&users) {
+                let full_command = self
+                    .get_process_command(pid)
+                    .await
+                    .unwrap_or_else(|_| "Unknown".to_string());
+                let name = self.extract_process_name(&full_command);
+                let executable_path = self
+                    .get_process_executable(pid)
+                    .await
+                    .unwrap_or_else(|_| self.extract_executable_path(&full_command));
+                let working_directory = self
+                    .get_process_working_directory(pid)
+                    .await
+                    .unwrap_or_else(|_| "Unknown".to_string());
+
+                processes.push(ProcessInfo {
+                    pid,
+                    name,
+                    command: full_command,
+                    executable_path,
+                    working_directory,
+                    port,
+                    protocol: protocol.to_string(),
+                    address: address.clone(),
+                    fd,
+                    family: address_family(&address),
+                    state: TcpState::from_tool_name(parts[0]),
+                    ..Default::default()
+                });
             }
         }
 
-        Ok(None)
+        Ok(processes)
     }
 
     async fn parse_lsof_output_with_callback<F>(
@@ -758,13 +859,16 @@ impl PortManager {
             }
             .to_string();
 
-            basic_process_info.push((pid, command, port, protocol, address));
+            let fd = parse_lsof_fd(fields[3]);
+            let family = lsof_family(type_field);
+
+            basic_process_info.push((pid, command, port, protocol, address, fd, family));
         }
 
         // Extract unique PIDs for batch processing
         let pids: Vec<u32> = basic_process_info
             .iter()
-            .map(|(pid, _, _, _, _)| *pid)
+            .map(|(pid, _, _, _, _, _, _)| *pid)
             .collect();
 
         // Get all process details in a single lsof call
@@ -777,7 +881,7 @@ impl PortManager {
         if let Some(ref cb) = callback {
             cb("Building process list...");
         }
-        for (pid, command, port, protocol, address) in basic_process_info {
+        for (pid, command, port, protocol, address, fd, family) in basic_process_info {
             // Use command from lsof as fallback instead of calling ps individually
             let full_command = command.to_string();
 
@@ -802,6 +906,8 @@ impl PortManager {
                     )
                 };
 
+            let state = listen_state(&protocol);
+
             processes.push(ProcessInfo {
                 pid,
                 name,
@@ -811,7 +917,10 @@ impl PortManager {
                 port,
                 protocol,
                 address,
-                inode: None, // Legacy implementation doesn't track inodes
+                fd,
+                family,
+                state,
+                ..Default::default()
             });
         }
 
@@ -843,75 +952,69 @@ impl PortManager {
                 continue;
             };
 
-            // プロセス情報からPIDを抽出 (users:(("process",pid=1234,fd=5)))
-            let pid = if let Some(pid_start) = process_info.find("pid=") {
-                let pid_start = pid_start + 4;
-                if let Some(pid_end) = process_info[pid_start..].find(',') {
-                    match process_info[pid_start..pid_start + pid_end].parse::<u32>() {
-                        Ok(pid) => pid,
-                        Err(_) => continue,
-                    }
-                } else {
-                    continue;
-                }
-            } else {
-                continue;
-            };
-
             let address = if let Some(colon_pos) = local_address.rfind(':') {
                 local_address[..colon_pos].to_string()
             } else {
                 "*".to_string()
             };
 
-            let full_command = match self.get_process_command(pid).await {
-                Ok(cmd) => cmd,
-                Err(_) => "Unknown".to_string(),
-            };
+            // プロセス情報から全保持プロセスを抽出 (users:(("process",pid=1234,fd=5),...))
+            for (pid, fd) in parse_ss_users(&process_info) {
+                let full_command = match self.get_process_command(pid).await {
+                    Ok(cmd) => cmd,
+                    Err(_) => "Unknown".to_string(),
+                };
 
-            let name = self.extract_process_name(&full_command);
+                let name = self.extract_process_name(&full_command);
 
-            // Get the actual executable path
-            let executable_path = match self.get_process_executable(pid).await {
-                Ok(path) => {
-                    // If we got the same as command (fallback case), extract it
-                    if path == full_command {
-                        self.extract_executable_path(&full_command)
-                    } else {
-                        path
+                // Get the actual executable path
+                let executable_path = match self.get_process_executable(pid).await {
+                    Ok(path) => {
+                        // If we got the same as command (fallback case), extract it
+                        if path == full_command {
+                            self.extract_executable_path(&full_command)
+                        } else {
+                            path
+                        }
                     }
-                }
-                Err(_) => self.extract_executable_path(&full_command),
-            };
-
-            // Get the working directory
-            let working_directory = self
-                .get_process_working_directory(pid)
-                .await
-                .unwrap_or_else(|_| "Unknown".to_string());
+                    Err(_) => self.extract_executable_path(&full_command),
+                };
 
-            processes.push(ProcessInfo {
-                pid,
-                name,
-                command: full_command,
-                executable_path,
-                working_directory,
-                port,
-                protocol: protocol.to_string(),
-                address,
-                inode: None, // Legacy implementation doesn't track inodes
-            });
+                // Get the working directory
+                let working_directory = self
+                    .get_process_working_directory(pid)
+                    .await
+                    .unwrap_or_else(|_| "Unknown".to_string());
+
+                processes.push(ProcessInfo {
+                    pid,
+                    name,
+                    command: full_command,
+                    executable_path,
+                    working_directory,
+                    port,
+                    protocol: protocol.to_string(),
+                    address: address.clone(),
+                    fd,
+                    family: address_family(&address),
+                    state: TcpState::from_tool_name(parts[0]),
+                    ..Default::default()
+                });
+            }
         }
 
         Ok(processes)
     }
 
-    /// Fast netstat check for specific port
+    /// Fast netstat check for specific port.
+    ///
+    /// netstat only reports one process per socket, so holders that share a
+    /// socket are not all visible here.
     async fn try_netstat_specific_port(
         &self,
         port: u16,
         protocol: &str,
-    ) -> Result<Option<ProcessInfo>> {
+    ) -> Result<Vec<ProcessInfo>> {
         let mut cmd = TokioCommand::new("netstat");
         cmd.arg("-n") // Numeric display
             .arg("-p"); // Process info
@@ -933,10 +1036,12 @@ impl PortManager {
 
         let output = cmd.output().await?;
         if !output.status.success() {
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut processes = Vec::new();
+
         for line in stdout.lines() {
             if line.trim().is_empty() || line.contains("Proto") {
                 continue;
@@ -976,7 +1081,9 @@ impl PortManager {
                             .await
                             .unwrap_or_else(|_| "Unknown".to_string());
 
-                        return Ok(Some(ProcessInfo {
+                        let family = address_family(&address);
+
+                        processes.push(ProcessInfo {
                             pid,
                             name,
                             command: full_command,
@@ -985,14 +1092,16 @@ impl PortManager {
                             port,
                             protocol: protocol.to_string(),
                             address,
-                            inode: None,
-                        }));
+                            family,
+                            state: fields.get(5).and_then(|s| TcpState::from_tool_name(s)),
+                            ..Default::default()
+                        });
                     }
                 }
             }
         }
 
-        Ok(None)
+        Ok(processes)
     }
 
     async fn parse_netstat_unix_output(
@@ -1070,6 +1179,8 @@ impl PortManager {
                 .await
                 .unwrap_or_else(|_| "Unknown".to_string());
 
+            let family = address_family(&address);
+
             processes.push(ProcessInfo {
                 pid,
                 name,
@@ -1079,7 +1190,9 @@ impl PortManager {
                 port,
                 protocol,
                 address,
-                inode: None, // Legacy implementation doesn't track inodes
+                family,
+                state: TcpState::from_tool_name(state),
+                ..Default::default()
             });
         }
 
@@ -1115,27 +1228,7 @@ impl PortManager {
     }
 
     pub fn get_display_path(&self, process_info: &ProcessInfo) -> String {
-        // Prefer working directory for development processes (when it's not root)
-        if process_info.working_directory != "/" && process_info.working_directory != "Unknown" {
-            // Check if this is likely a development process based on the executable or command
-            let is_dev_process = process_info.executable_path.contains("/node")
-                || process_info.executable_path.contains("/python")
-                || process_info.executable_path.contains("/ruby")
-                || process_info.executable_path.contains("/java")
-                || process_info.command.contains("npm")
-                || process_info.command.contains("yarn")
-                || process_info.command.contains("pnpm")
-                || process_info.command.contains("next")
-                || process_info.command.contains("serve")
-                || process_info.command.contains("dev");
-
-            if is_dev_process {
-                return process_info.working_directory.clone();
-            }
-        }
-
-        // Fallback to executable path for system processes
-        process_info.executable_path.clone()
+        display_path(process_info)
     }
 
     async fn get_process_command(&self, pid: u32) -> Result<String> {
@@ -1227,6 +1320,143 @@ impl PortManager {
     }
 }
 
+/// Parse the numeric part of an lsof FD column (`12u` → 12)
+fn parse_lsof_fd(field: &str) -> Option<u32> {
+    let digits: String = field.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Bound address and port of an lsof NAME column such as `[::1]:3000` or
+/// `*:3000`. Only the local side counts; `a:5000->b:3000` is a client of
+/// port 3000, not its listener.
+fn parse_lsof_name(name: &str) -> (&str, Option<u16>) {
+    let local = name.split("->").next().unwrap_or(name);
+    match local.rsplit_once(':') {
+        Some((address, port)) => (address, port.parse().ok()),
+        None => ("*", None),
+    }
+}
+
+/// Key identifying one holder of one bound socket, used to merge scanner output
+fn socket_key(process: &ProcessInfo) -> (u16, String, String, u32) {
+    (
+        process.port,
+        process.protocol.clone(),
+        process.display_address(),
+        process.pid,
+    )
+}
+
+/// State of a socket reported by a listen-only scan (`None` for UDP)
+fn listen_state(protocol: &str) -> Option<TcpState> {
+    protocol
+        .eq_ignore_ascii_case("tcp")
+        .then_some(TcpState::Listen)
+}
+
+/// Address family from an lsof TYPE column (`IPv4` / `IPv6`)
+fn lsof_family(type_field: &str) -> Option<IpFamily> {
+    if type_field.contains("IPv6") {
+        Some(IpFamily::V6)
+    } else if type_field.contains("IPv4") {
+        Some(IpFamily::V4)
+    } else {
+        None
+    }
+}
+
+/// Address family of a bound address as printed by ss/netstat
+fn address_family(address: &str) -> Option<IpFamily> {
+    address::parse_bound_address(address).map(|ip| IpFamily::of(&ip))
+}
+
+/// Parse every `pid=`/`fd=` pair from an ss `users:((...))` column
+fn parse_ss_users(users: &str) -> Vec<(u32, Option<u32>)> {
+    let mut holders = Vec::new();
+
+    for entry in users.split("),(") {
+        let mut pid = None;
+        let mut fd = None;
+        for field in entry.split(',') {
+            let field = field.trim_end_matches(')');
+            if let Some(value) = field.strip_prefix("pid=") {
+                pid = value.parse::<u32>().ok();
+            } else if let Some(value) = field.strip_prefix("fd=") {
+                fd = value.parse::<u32>().ok();
+            }
+        }
+        if let Some(pid) = pid {
+            holders.push((pid, fd));
+        }
+    }
+
+    holders
+}
+
+/// Which processes are shown by working directory instead of executable path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayRules {
+    /// Substrings of the executable path that mark a development process
+    pub dev_executables: Vec<String>,
+    /// Substrings of the command line that mark a development process
+    pub dev_commands: Vec<String>,
+}
+
+impl Default for DisplayRules {
+    fn default() -> Self {
+        Self {
+            dev_executables: ["/node", "/python", "/ruby", "/java"]
+                .map(String::from)
+                .to_vec(),
+            dev_commands: ["npm", "yarn", "pnpm", "next", "serve", "dev"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+static DISPLAY_RULES: OnceLock<DisplayRules> = OnceLock::new();
+
+/// Use `rules` for every display path from now on; only the first call has an effect
+pub fn set_display_rules(rules: DisplayRules) {
+    let _ = DISPLAY_RULES.set(rules);
+}
+
+/// Get display path for process (prefers working directory for dev processes)
+pub(crate) fn display_path(process_info: &ProcessInfo) -> String {
+    display_path_with(
+        process_info,
+        DISPLAY_RULES.get_or_init(DisplayRules::default),
+    )
+}
+
+pub(crate) fn display_path_with(process_info: &ProcessInfo, rules: &DisplayRules) -> String {
+    // A container label says more than `docker-proxy` or a path inside the container
+    if let Some(container) = &process_info.container {
+        return container.to_string();
+    }
+
+    // Prefer working directory for development processes (when it's not root)
+    if process_info.working_directory != "/" && process_info.working_directory != "Unknown" {
+        // Check if this is likely a development process based on the executable or command
+        let is_dev_process = rules
+            .dev_executables
+            .iter()
+            .any(|pattern| process_info.executable_path.contains(pattern.as_str()))
+            || rules
+                .dev_commands
+                .iter()
+                .any(|pattern| process_info.command.contains(pattern.as_str()));
+
+        if is_dev_process {
+            return process_info.working_directory.clone();
+        }
+    }
+
+    // Fallback to executable path for system processes
+    process_info.executable_path.clone()
+}
+
 impl Default for PortManager {
     fn default() -> Self {
         Self::new()
@@ -1249,6 +1479,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "127.0.0.1".to_string(),
             inode: Some(12345),
+            ..Default::default()
         };
 
         assert_eq!(process_info.pid, 1234);
@@ -1270,7 +1501,7 @@ mod tests {
             port: 8080,
             protocol: "tcp".to_string(),
             address: "127.0.0.1".to_string(),
-            inode: None, // Test with None value
+            ..Default::default()
         };
 
         // Test JSON serialization
@@ -1376,6 +1607,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_display_path_with_custom_rules() {
+        let process_info = ProcessInfo {
+            pid: 1234,
+            name: "deno".to_string(),
+            command: "/usr/local/bin/deno run main.ts".to_string(),
+            executable_path: "/usr/local/bin/deno".to_string(),
+            working_directory: "/home/user/api".to_string(),
+            port: 8000,
+            protocol: "tcp".to_string(),
+            address: "127.0.0.1".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            display_path_with(&process_info, &DisplayRules::default()),
+            "/usr/local/bin/deno"
+        );
+        let rules = DisplayRules {
+            dev_executables: vec!["/deno".to_string()],
+            dev_commands: Vec::new(),
+        };
+        assert_eq!(display_path_with(&process_info, &rules), "/home/user/api");
+    }
+
     #[test]
     fn test_get_display_path_development_processes() {
         let port_manager = PortManager::new();
@@ -1391,6 +1647,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "127.0.0.1".to_string(),
             inode: Some(12345),
+            ..Default::default()
         };
 
         // 開発プロセスの場合は作業ディレクトリが返されるべき
@@ -1413,6 +1670,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "0.0.0.0".to_string(),
             inode: Some(12345),
+            ..Default::default()
         };
 
         // システムプロセスの場合は実行ファイルパスが返されるべき
@@ -1434,6 +1692,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "127.0.0.1".to_string(),
             inode: Some(12345),
+            ..Default::default()
         };
 
         // 作業ディレクトリが不明な場合は実行ファイルパスが返されるべき
@@ -1441,6 +1700,35 @@ mod tests {
         assert_eq!(display_path, "/usr/bin/test_process");
     }
 
+    #[test]
+    fn test_get_display_path_container() {
+        let port_manager = PortManager::new();
+
+        let mut container = ContainerInfo::new(
+            "docker",
+            "3f2a9c1b2d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8",
+        );
+        container.name = Some("myapp-db-1".to_string());
+        container.project = Some("myapp".to_string());
+
+        let process_info = ProcessInfo {
+            pid: 1234,
+            name: "docker-proxy".to_string(),
+            command: "/usr/bin/docker-proxy -proto tcp -host-port 5432".to_string(),
+            executable_path: "/usr/bin/docker-proxy".to_string(),
+            working_directory: "/".to_string(),
+            port: 5432,
+            protocol: "tcp".to_string(),
+            address: "0.0.0.0".to_string(),
+            container: Some(container),
+            ..Default::default()
+        };
+
+        // docker-proxyのパスではなくコンテナ名を表示する
+        let display_path = port_manager.get_display_path(&process_info);
+        assert_eq!(display_path, "docker:myapp-db-1 (myapp)");
+    }
+
     #[tokio::test]
     async fn test_list_processes_error_handling() {
         let port_manager = PortManager::new();
@@ -1534,6 +1822,7 @@ mod tests {
                 protocol: protocol.to_string(),
                 address: "127.0.0.1".to_string(),
                 inode: Some(12345),
+                ..Default::default()
             };
 
             assert_eq!(process_info.protocol, protocol);
@@ -1562,6 +1851,7 @@ mod tests {
                 protocol: "tcp".to_string(),
                 address: address.to_string(),
                 inode: Some(12345),
+                ..Default::default()
             };
 
             assert!(process_info.pid >= 1);
@@ -1588,6 +1878,7 @@ LISTEN 0      128          *:3000             *:*     users:(("node",pid=1234,fd
         assert_eq!(processes[0].pid, 1234);
         assert_eq!(processes[0].address, "*");
         assert_eq!(processes[0].protocol, "tcp");
+        assert_eq!(processes[0].state, Some(TcpState::Listen));
     }
 
     #[tokio::test]
@@ -1687,4 +1978,60 @@ LISTEN 0      128          *:3000             *:*     users:(("node",fd=22))"#;
         // PIDがない行はスキップされる
         assert_eq!(processes.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_parse_ss_output_shared_listener() {
+        let port_manager = PortManager::new();
+
+        // SO_REUSEPORTやpre-forkで複数プロセスが同じソケットを保持するケース
+        let ss_output = r#"State  Recv-Q Send-Q Local Address:Port  Peer Address:Port Process
+LISTEN 0      511          *:8080             *:*     users:(("nginx",pid=1002,fd=6),("nginx",pid=1001,fd=6),("nginx",pid=1000,fd=6))"#;
+
+        let processes = port_manager
+            .parse_ss_output(ss_output, "tcp")
+            .await
+            .unwrap();
+
+        let holders: Vec<(u32, Option<u32>)> = processes.iter().map(|p| (p.pid, p.fd)).collect();
+        assert_eq!(
+            holders,
+            vec![(1002, Some(6)), (1001, Some(6)), (1000, Some(6))]
+        );
+        assert!(processes.iter().all(|p| p.port == 8080));
+    }
+
+    #[test]
+    fn test_parse_ss_users() {
+        assert_eq!(
+            parse_ss_users(r#"users:(("node",pid=1234,fd=22))"#),
+            vec![(1234, Some(22))]
+        );
+        assert_eq!(
+            parse_ss_users(r#"users:(("gunicorn",pid=10,fd=5),("gunicorn",pid=11,fd=5))"#),
+            vec![(10, Some(5)), (11, Some(5))]
+        );
+        // PIDがない場合は空
+        assert!(parse_ss_users(r#"users:(("node",fd=22))"#).is_empty());
+        assert!(parse_ss_users("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_lsof_fd() {
+        assert_eq!(parse_lsof_fd("12u"), Some(12));
+        assert_eq!(parse_lsof_fd("3r"), Some(3));
+        assert_eq!(parse_lsof_fd("45"), Some(45));
+        assert_eq!(parse_lsof_fd("cwd"), None);
+        assert_eq!(parse_lsof_fd("txt"), None);
+    }
+
+    #[test]
+    fn test_parse_lsof_name() {
+        assert_eq!(parse_lsof_name("127.0.0.1:3000"), ("127.0.0.1", Some(3000)));
+        assert_eq!(parse_lsof_name("[::1]:3000"), ("[::1]", Some(3000)));
+        assert_eq!(parse_lsof_name("*:8080"), ("*", Some(8080)));
+        assert_eq!(
+            parse_lsof_name("[fe80::1]:5000->[fe80::2]:3000"),
+            ("[fe80::1]", Some(5000))
+        );
+    }
 }
@@ -0,0 +1,464 @@
+use crate::Result;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::procfs::ProcfsPortManager;
+use super::{IpFamily, ProcessInfo, TcpState};
+
+/// `SOCK_DIAG_BY_FAMILY` message type from `linux/sock_diag.h`
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+
+/// Netlink header values from `linux/netlink.h`, defined here because libc
+/// only provides them on Linux and the parser is built everywhere
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+
+const NLMSG_HDR_LEN: usize = 16;
+const INET_DIAG_REQ_V2_LEN: usize = 56;
+const INET_DIAG_MSG_LEN: usize = 72;
+
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+
+/// Port manager that enumerates sockets over NETLINK_SOCK_DIAG.
+///
+/// The kernel filters sockets by state before they are sent, so only
+/// TCP sockets in the requested states cross the netlink boundary. PIDs are resolved
+/// through the same `/proc/<pid>/fd` scan used by [`ProcfsPortManager`].
+pub struct NetlinkPortManager {
+    procfs: ProcfsPortManager,
+}
+
+/// A socket as reported by `inet_diag_msg`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DiagSocket {
+    local: IpAddr,
+    local_port: u16,
+    state: u8,
+    inode: u64,
+}
+
+impl NetlinkPortManager {
+    pub fn new() -> Self {
+        Self {
+            procfs: ProcfsPortManager::new(),
+        }
+    }
+
+    /// List all processes using ports via netlink
+    pub async fn list_processes(&self, protocol: &str) -> Result<Vec<ProcessInfo>> {
+        self.list_sockets(protocol, &[TcpState::Listen]).await
+    }
+
+    /// List sockets whose TCP state is in `states` (UDP sockets are always included)
+    pub async fn list_sockets(
+        &self,
+        protocol: &str,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
+        let protocol = protocol.to_lowercase();
+        if protocol == "unix" {
+            // sock_diag is only wired up for inet sockets
+            return self.procfs.list_sockets(&protocol, states).await;
+        }
+
+        let mut processes = Vec::new();
+
+        if protocol == "tcp" || protocol == "all" {
+            let mask = states
+                .iter()
+                .fold(0u32, |mask, state| mask | 1 << state.kernel_code());
+            let sockets = Self::dump_blocking(IPPROTO_TCP, mask).await?;
+            processes.extend(sockets.iter().map(|s| s.to_process_info("tcp")));
+        }
+
+        if protocol == "udp" || protocol == "all" {
+            let sockets = Self::dump_blocking(IPPROTO_UDP, u32::MAX).await?;
+            processes.extend(sockets.iter().map(|s| s.to_process_info("udp")));
+        }
+
+        self.procfs.enrich_with_process_info(&mut processes).await?;
+
+        Ok(processes)
+    }
+
+    /// Check specific port using netlink
+    pub async fn check_port(&self, port: u16, protocol: &str) -> Result<Option<ProcessInfo>> {
+        let processes = self.list_processes(protocol).await?;
+        Ok(processes.into_iter().find(|p| p.port == port))
+    }
+
+    /// Find every process holding a socket on `port` using netlink
+    pub async fn check_port_all(&self, port: u16, protocol: &str) -> Result<Vec<ProcessInfo>> {
+        let mut processes = self.list_processes(protocol).await?;
+        processes.retain(|p| p.port == port);
+        Ok(processes)
+    }
+
+    /// Dump IPv4 and IPv6 sockets for `protocol` on a blocking thread
+    async fn dump_blocking(protocol: u8, states: u32) -> Result<Vec<DiagSocket>> {
+        tokio::task::spawn_blocking(move || {
+            let mut sockets = sys::dump(AF_INET, protocol, states)?;
+            sockets.extend(sys::dump(AF_INET6, protocol, states)?);
+            Ok(sockets)
+        })
+        .await
+        .map_err(|e| crate::Error::Other(format!("netlink task failed: {e}")))?
+    }
+}
+
+impl Default for NetlinkPortManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiagSocket {
+    fn to_process_info(&self, protocol: &str) -> ProcessInfo {
+        let address = if self.local.is_unspecified() {
+            "*".to_string()
+        } else {
+            self.local.to_string()
+        };
+
+        ProcessInfo {
+            pid: 0, // Will be filled later
+            name: String::new(),
+            command: String::new(),
+            executable_path: String::new(),
+            working_directory: String::new(),
+            port: self.local_port,
+            protocol: protocol.to_string(),
+            address,
+            inode: Some(self.inode),
+            family: Some(IpFamily::of(&self.local)),
+            state: if protocol == "tcp" {
+                TcpState::from_kernel(self.state)
+            } else {
+                None
+            },
+            ..Default::default()
+        }
+    }
+}
+
+/// Build a `SOCK_DIAG_BY_FAMILY` dump request (`nlmsghdr` + `inet_diag_req_v2`)
+fn build_request(family: u8, protocol: u8, states: u32, seq: u32) -> Vec<u8> {
+    let total_len = (NLMSG_HDR_LEN + INET_DIAG_REQ_V2_LEN) as u32;
+    let flags = NLM_F_REQUEST | NLM_F_DUMP;
+
+    let mut buf = Vec::with_capacity(total_len as usize);
+    // nlmsghdr
+    buf.extend_from_slice(&total_len.to_ne_bytes());
+    buf.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    buf.extend_from_slice(&flags.to_ne_bytes());
+    buf.extend_from_slice(&seq.to_ne_bytes());
+    buf.extend_from_slice(&0u32.to_ne_bytes());
+    // inet_diag_req_v2
+    buf.push(family);
+    buf.push(protocol);
+    buf.push(0); // idiag_ext
+    buf.push(0); // pad
+    buf.extend_from_slice(&states.to_ne_bytes());
+    // inet_diag_sockid is left zeroed to match every socket
+    buf.resize(total_len as usize, 0);
+    buf
+}
+
+/// Parse a batch of netlink messages.
+///
+/// Returns the sockets found and whether `NLMSG_DONE` was reached.
+fn parse_messages(buf: &[u8], sockets: &mut Vec<DiagSocket>) -> Result<bool> {
+    let mut offset = 0;
+
+    while offset + NLMSG_HDR_LEN <= buf.len() {
+        let msg_len = read_u32(buf, offset) as usize;
+        let msg_type = u16::from_ne_bytes([buf[offset + 4], buf[offset + 5]]);
+
+        if msg_len < NLMSG_HDR_LEN || offset + msg_len > buf.len() {
+            return Err(crate::Error::ParseError(
+                "Truncated netlink message".to_string(),
+            ));
+        }
+
+        match msg_type {
+            NLMSG_DONE => return Ok(true),
+            NLMSG_ERROR => {
+                let errno = if msg_len >= NLMSG_HDR_LEN + 4 {
+                    -(read_u32(buf, offset + NLMSG_HDR_LEN) as i32)
+                } else {
+                    0
+                };
+                return Err(crate::Error::IoError(format!(
+                    "netlink sock_diag request failed: {}",
+                    std::io::Error::from_raw_os_error(errno)
+                )));
+            }
+            _ if msg_type == SOCK_DIAG_BY_FAMILY => {
+                let payload = &buf[offset + NLMSG_HDR_LEN..offset + msg_len];
+                if let Some(socket) = parse_diag_msg(payload) {
+                    sockets.push(socket);
+                }
+            }
+            _ => {}
+        }
+
+        // Messages are aligned to 4 bytes (NLMSG_ALIGN)
+        offset += (msg_len + 3) & !3;
+    }
+
+    Ok(false)
+}
+
+/// Parse a single `inet_diag_msg` payload
+fn parse_diag_msg(payload: &[u8]) -> Option<DiagSocket> {
+    if payload.len() < INET_DIAG_MSG_LEN {
+        return None;
+    }
+
+    let family = payload[0];
+    let state = payload[1];
+    // inet_diag_sockid starts at offset 4; ports are in network byte order
+    let local_port = u16::from_be_bytes([payload[4], payload[5]]);
+    let local = match family {
+        AF_INET => {
+            let octets: [u8; 4] = payload[8..12].try_into().ok()?;
+            IpAddr::V4(Ipv4Addr::from(octets))
+        }
+        AF_INET6 => {
+            let octets: [u8; 16] = payload[8..24].try_into().ok()?;
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    let inode = read_u32(payload, 68) as u64;
+
+    Some(DiagSocket {
+        local,
+        local_port,
+        state,
+        inode,
+    })
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use super::{build_request, parse_messages, DiagSocket};
+    use crate::Result;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    /// Send a dump request and collect every socket in the reply
+    pub(super) fn dump(family: u8, protocol: u8, states: u32) -> Result<Vec<DiagSocket>> {
+        // SAFETY: plain socket(2) call; the returned fd is owned below
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                libc::NETLINK_SOCK_DIAG,
+            )
+        };
+        if fd < 0 {
+            return Err(last_error("netlink socket"));
+        }
+        // SAFETY: fd is a freshly created, valid descriptor
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let request = build_request(family, protocol, states, 1);
+        // SAFETY: sockaddr_nl is plain old data; zeroed is a valid value
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+
+        // SAFETY: request and addr outlive the call and lengths match the buffers
+        let sent = unsafe {
+            libc::sendto(
+                fd.as_raw_fd(),
+                request.as_ptr() as *const libc::c_void,
+                request.len(),
+                0,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            return Err(last_error("netlink sendto"));
+        }
+
+        let mut sockets = Vec::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            // SAFETY: buf is valid for buf.len() bytes
+            let received = unsafe {
+                libc::recv(
+                    fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(crate::Error::IoError(format!("netlink recv: {err}")));
+            }
+            if received == 0 || parse_messages(&buf[..received as usize], &mut sockets)? {
+                break;
+            }
+        }
+
+        Ok(sockets)
+    }
+
+    fn last_error(context: &str) -> crate::Error {
+        crate::Error::IoError(format!("{context}: {}", std::io::Error::last_os_error()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use super::DiagSocket;
+    use crate::Result;
+
+    pub(super) fn dump(_family: u8, _protocol: u8, _states: u32) -> Result<Vec<DiagSocket>> {
+        Err(crate::Error::Other(
+            "The netlink backend is only available on Linux".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `TCP_LISTEN` from `linux/tcp_states.h`
+    const TCP_LISTEN: u8 = 10;
+
+    fn diag_message(family: u8, state: u8, local: &[u8], port: u16, inode: u32) -> Vec<u8> {
+        let mut payload = vec![0u8; INET_DIAG_MSG_LEN];
+        payload[0] = family;
+        payload[1] = state;
+        payload[4..6].copy_from_slice(&port.to_be_bytes());
+        payload[8..8 + local.len()].copy_from_slice(local);
+        payload[68..72].copy_from_slice(&inode.to_ne_bytes());
+
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&((NLMSG_HDR_LEN + payload.len()) as u32).to_ne_bytes());
+        msg.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        msg.extend_from_slice(&[0u8; 10]);
+        msg.extend_from_slice(&payload);
+        msg
+    }
+
+    fn done_message() -> Vec<u8> {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&(NLMSG_HDR_LEN as u32 + 4).to_ne_bytes());
+        msg.extend_from_slice(&NLMSG_DONE.to_ne_bytes());
+        msg.extend_from_slice(&[0u8; 14]);
+        msg
+    }
+
+    #[test]
+    fn test_build_request_layout() {
+        let request = build_request(AF_INET6, IPPROTO_TCP, 1 << TCP_LISTEN, 7);
+        assert_eq!(request.len(), NLMSG_HDR_LEN + INET_DIAG_REQ_V2_LEN);
+        assert_eq!(read_u32(&request, 0) as usize, request.len());
+        assert_eq!(
+            u16::from_ne_bytes([request[4], request[5]]),
+            SOCK_DIAG_BY_FAMILY
+        );
+        assert_eq!(read_u32(&request, 8), 7);
+        assert_eq!(request[16], AF_INET6);
+        assert_eq!(request[17], IPPROTO_TCP);
+        assert_eq!(read_u32(&request, 20), 1 << 10);
+    }
+
+    #[test]
+    fn test_parse_ipv4_listener() {
+        let mut buf = diag_message(AF_INET, TCP_LISTEN, &[127, 0, 0, 1], 3000, 12345);
+        buf.extend(done_message());
+
+        let mut sockets = Vec::new();
+        let done = parse_messages(&buf, &mut sockets).unwrap();
+        assert!(done);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].local_port, 3000);
+        assert_eq!(sockets[0].local, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(sockets[0].inode, 12345);
+
+        let info = sockets[0].to_process_info("tcp");
+        assert_eq!(info.address, "127.0.0.1");
+        assert_eq!(info.port, 3000);
+        assert_eq!(info.inode, Some(12345));
+        assert_eq!(info.state, Some(TcpState::Listen));
+    }
+
+    #[test]
+    fn test_parse_ipv6_wildcard_listener() {
+        let buf = diag_message(AF_INET6, TCP_LISTEN, &[0u8; 16], 8080, 99);
+
+        let mut sockets = Vec::new();
+        let done = parse_messages(&buf, &mut sockets).unwrap();
+        assert!(!done);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].local, IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+
+        let info = sockets[0].to_process_info("tcp");
+        assert_eq!(info.address, "*");
+        assert_eq!(info.port, 8080);
+    }
+
+    #[test]
+    fn test_parse_error_message() {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&(NLMSG_HDR_LEN as u32 + 4).to_ne_bytes());
+        msg.extend_from_slice(&NLMSG_ERROR.to_ne_bytes());
+        msg.extend_from_slice(&[0u8; 10]);
+        msg.extend_from_slice(&(-libc::EPERM).to_ne_bytes());
+
+        let mut sockets = Vec::new();
+        let result = parse_messages(&msg, &mut sockets);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("sock_diag"));
+    }
+
+    #[test]
+    fn test_parse_truncated_message() {
+        let mut buf = diag_message(AF_INET, TCP_LISTEN, &[127, 0, 0, 1], 3000, 1);
+        buf.truncate(40);
+
+        let mut sockets = Vec::new();
+        assert!(parse_messages(&buf, &mut sockets).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_processes_finds_own_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let manager = NetlinkPortManager::new();
+        match manager.check_port(port, "tcp").await {
+            Ok(Some(process)) => {
+                assert_eq!(process.pid, std::process::id());
+                assert_eq!(process.address, "127.0.0.1");
+                assert!(process.inode.is_some());
+            }
+            Ok(None) => panic!("Own listener on port {port} not found"),
+            Err(_) => {
+                // netlinkが使えない環境（Linux以外やサンドボックス）
+            }
+        }
+    }
+}
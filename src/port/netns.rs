@@ -0,0 +1,144 @@
+use crate::Result;
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+
+/// Which network namespaces the procfs scanner reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NetnsScope {
+    /// Only the namespace kilar runs in (`/proc/net`)
+    #[default]
+    Host,
+    /// Every namespace that has at least one process (`--all-namespaces`)
+    All,
+    /// A single namespace, identified by its inode (`--netns`)
+    Only(u64),
+}
+
+impl NetnsScope {
+    /// Resolve a `--netns` value: a name under `/run/netns` (as created by
+    /// `ip netns add`) or the PID of a process inside the namespace.
+    pub fn resolve(value: &str) -> Result<Self> {
+        if let Ok(pid) = value.parse::<u32>() {
+            return match std::fs::read_link(format!("/proc/{pid}/ns/net")) {
+                Ok(link) => link
+                    .to_str()
+                    .and_then(parse_netns_link)
+                    .map(NetnsScope::Only)
+                    .ok_or_else(|| {
+                        crate::Error::ParseError(format!(
+                            "Unexpected namespace link for PID {pid}: {}",
+                            link.display()
+                        ))
+                    }),
+                Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                    Err(crate::Error::PermissionDenied(format!(
+                        "Cannot read network namespace of PID {pid}"
+                    )))
+                }
+                Err(_) => Err(crate::Error::ProcessNotFound(pid)),
+            };
+        }
+
+        // `ip netns` bind-mounts the namespace file, whose inode is the namespace id
+        let path = format!("/run/netns/{value}");
+        std::fs::metadata(&path)
+            .map(|meta| NetnsScope::Only(meta.ino()))
+            .map_err(|_| {
+                crate::Error::Other(format!(
+                    "Network namespace '{value}' not found (expected a name in /run/netns or a PID)"
+                ))
+            })
+    }
+
+    /// Whether sockets are read per namespace instead of from `/proc/net`
+    pub fn is_host(&self) -> bool {
+        *self == NetnsScope::Host
+    }
+
+    /// Whether this scope includes the namespace `netns`
+    pub fn includes(&self, netns: u64) -> bool {
+        match self {
+            NetnsScope::Host => false,
+            NetnsScope::All => true,
+            NetnsScope::Only(wanted) => *wanted == netns,
+        }
+    }
+}
+
+/// Network namespace of `pid`, from the `/proc/<pid>/ns/net` link
+pub fn netns_of_pid(pid: u32) -> Option<u64> {
+    let link = std::fs::read_link(format!("/proc/{pid}/ns/net")).ok()?;
+    parse_netns_link(link.to_str()?)
+}
+
+/// Parse a namespace link target such as `net:[4026531840]`
+pub(crate) fn parse_netns_link(link: &str) -> Option<u64> {
+    link.strip_prefix("net:[")?.strip_suffix(']')?.parse().ok()
+}
+
+/// Pick one PID per namespace to read `/proc/<pid>/net` from.
+///
+/// Returns `(namespace, pid)` pairs sorted by namespace, using the lowest
+/// PID seen in each namespace.
+pub(crate) fn group_by_netns(pids: impl IntoIterator<Item = (u32, u64)>) -> Vec<(u64, u32)> {
+    let mut namespaces: HashMap<u64, u32> = HashMap::new();
+    for (pid, netns) in pids {
+        namespaces
+            .entry(netns)
+            .and_modify(|first| *first = (*first).min(pid))
+            .or_insert(pid);
+    }
+
+    let mut namespaces: Vec<(u64, u32)> = namespaces.into_iter().collect();
+    namespaces.sort_unstable();
+    namespaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netns_link() {
+        assert_eq!(parse_netns_link("net:[4026531840]"), Some(4026531840));
+        assert_eq!(parse_netns_link("mnt:[4026531840]"), None);
+        assert_eq!(parse_netns_link("net:[abc]"), None);
+        assert_eq!(parse_netns_link("socket:[12345]"), None);
+    }
+
+    #[test]
+    fn test_group_by_netns() {
+        // ホストのプロセスが2つ、コンテナのプロセスが2つ
+        let groups = group_by_netns([(900, 200), (1, 100), (42, 100), (901, 200)]);
+        assert_eq!(groups, vec![(100, 1), (200, 900)]);
+        assert!(group_by_netns([]).is_empty());
+    }
+
+    #[test]
+    fn test_scope_includes() {
+        assert!(!NetnsScope::Host.includes(100));
+        assert!(NetnsScope::All.includes(100));
+        assert!(NetnsScope::Only(100).includes(100));
+        assert!(!NetnsScope::Only(100).includes(200));
+        assert!(NetnsScope::default().is_host());
+    }
+
+    #[test]
+    fn test_resolve() {
+        // Linux以外では/procがないため、解決できなくてもエラーになるだけ
+        let pid = std::process::id().to_string();
+        match NetnsScope::resolve(&pid) {
+            Ok(scope) => assert_eq!(
+                scope,
+                NetnsScope::Only(netns_of_pid(std::process::id()).unwrap())
+            ),
+            #[cfg(target_os = "linux")]
+            Err(e) => panic!("unexpected error: {e}"),
+            #[cfg(not(target_os = "linux"))]
+            Err(_) => {}
+        }
+
+        let result = NetnsScope::resolve("kilar-test-missing-netns");
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+}
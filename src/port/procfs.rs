@@ -1,15 +1,19 @@
 use crate::Result;
 use std::collections::HashMap;
 use std::net::Ipv6Addr;
+use std::sync::Mutex;
 use tokio::fs as tokio_fs;
 
-use super::ProcessInfo;
+use super::address::parse_bound_address;
+use super::netns::{group_by_netns, parse_netns_link};
+use super::{IpFamily, NetnsScope, PortTarget, ProcessInfo, TcpState};
 
 /// High-performance port manager using direct procfs access
 pub struct ProcfsPortManager {
-    pid_cache: HashMap<u32, ProcessDetails>,
-    last_update: std::time::Instant,
+    pid_cache: Mutex<HashMap<u32, ProcessDetails>>,
+    last_update: Mutex<std::time::Instant>,
     cache_ttl: std::time::Duration,
+    netns: NetnsScope,
 }
 
 #[derive(Debug, Clone)]
@@ -23,31 +27,64 @@ struct ProcessDetails {
 impl ProcfsPortManager {
     pub fn new() -> Self {
         Self {
-            pid_cache: HashMap::new(),
-            last_update: std::time::Instant::now(),
+            pid_cache: Mutex::new(HashMap::new()),
+            last_update: Mutex::new(std::time::Instant::now()),
             cache_ttl: std::time::Duration::from_secs(2),
+            netns: NetnsScope::Host,
         }
     }
 
+    /// Scan the network namespaces in `scope` instead of only `/proc/net`
+    pub fn with_netns(mut self, scope: NetnsScope) -> Self {
+        self.netns = scope;
+        self
+    }
+
     /// List all processes using ports with direct procfs access
-    pub async fn list_processes(&mut self, protocol: &str) -> Result<Vec<ProcessInfo>> {
-        let mut processes = Vec::new();
+    pub async fn list_processes(&self, protocol: &str) -> Result<Vec<ProcessInfo>> {
+        self.list_sockets(protocol, &[TcpState::Listen]).await
+    }
 
-        // Read network connections from procfs
-        let tcp_processes = if protocol == "tcp" || protocol == "all" {
-            self.read_tcp_connections().await?
-        } else {
-            Vec::new()
-        };
+    /// List sockets whose TCP state is in `states` (UDP sockets are always included)
+    pub async fn list_sockets(
+        &self,
+        protocol: &str,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
+        let mut processes = Vec::new();
 
-        let udp_processes = if protocol == "udp" || protocol == "all" {
-            self.read_udp_connections().await?
+        if self.netns.is_host() {
+            processes = self.read_net_dir("/proc/net", protocol, states).await?;
         } else {
-            Vec::new()
-        };
+            // Socket tables are per namespace; read each one through a
+            // process that lives in it
+            let namespaces = self.namespace_pids().await;
+            if namespaces.is_empty() {
+                return Err(crate::Error::Other(
+                    "No process found in the selected network namespace".to_string(),
+                ));
+            }
 
-        processes.extend(tcp_processes);
-        processes.extend(udp_processes);
+            let mut read_any = false;
+            let mut last_error = None;
+            for (netns, pid) in namespaces {
+                match self
+                    .read_net_dir(&format!("/proc/{pid}/net"), protocol, states)
+                    .await
+                {
+                    Ok(mut batch) => {
+                        batch.iter_mut().for_each(|p| p.netns = Some(netns));
+                        processes.extend(batch);
+                        read_any = true;
+                    }
+                    // The process may have exited since the namespace scan
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            if let (false, Some(e)) = (read_any, last_error) {
+                return Err(e);
+            }
+        }
 
         // Enrich with process information
         self.enrich_with_process_info(&mut processes).await?;
@@ -55,51 +92,226 @@ impl ProcfsPortManager {
         Ok(processes)
     }
 
+    /// Read the socket tables for `protocol` from one `net` directory
+    async fn read_net_dir(
+        &self,
+        net_dir: &str,
+        protocol: &str,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
+        // UNIX domain sockets are only listed when asked for explicitly
+        if protocol == "unix" {
+            return self.read_unix_sockets(net_dir).await;
+        }
+
+        let mut processes = Vec::new();
+
+        // Read network connections from procfs
+        if protocol == "tcp" || protocol == "all" {
+            processes.extend(self.read_tcp_connections(net_dir, states).await?);
+        }
+
+        if protocol == "udp" || protocol == "all" {
+            processes.extend(self.read_udp_connections(net_dir).await?);
+        }
+
+        Ok(processes)
+    }
+
+    /// One `(namespace, pid)` pair for every namespace in scope
+    async fn namespace_pids(&self) -> Vec<(u64, u32)> {
+        let mut pids = Vec::new();
+
+        if let Ok(mut entries) = tokio_fs::read_dir("/proc").await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let Some(pid) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|n| n.parse::<u32>().ok())
+                else {
+                    continue;
+                };
+                let Ok(link) = tokio_fs::read_link(format!("/proc/{pid}/ns/net")).await else {
+                    continue;
+                };
+                if let Some(netns) = link.to_str().and_then(parse_netns_link) {
+                    if self.netns.includes(netns) {
+                        pids.push((pid, netns));
+                    }
+                }
+            }
+        }
+
+        group_by_netns(pids)
+    }
+
     /// Check specific port using procfs
-    pub async fn check_port(&mut self, port: u16, protocol: &str) -> Result<Option<ProcessInfo>> {
+    pub async fn check_port(&self, port: u16, protocol: &str) -> Result<Option<ProcessInfo>> {
         let processes = self.list_processes(protocol).await?;
         Ok(processes.into_iter().find(|p| p.port == port))
     }
 
-    /// Read TCP connections from /proc/net/tcp and /proc/net/tcp6
-    async fn read_tcp_connections(&self) -> Result<Vec<ProcessInfo>> {
+    /// Find every process holding a socket on `port` using procfs
+    pub async fn check_port_all(&self, port: u16, protocol: &str) -> Result<Vec<ProcessInfo>> {
+        let mut processes = self.list_processes(protocol).await?;
+        processes.retain(|p| p.port == port);
+        Ok(processes)
+    }
+
+    /// List established TCP connections whose remote end matches `target`.
+    ///
+    /// Each entry is the client side of one connection: `address` and `port`
+    /// are the local (usually ephemeral) end, and the entry is attributed to
+    /// every process holding the socket.
+    pub async fn list_clients(&self, target: &PortTarget) -> Result<Vec<ProcessInfo>> {
         let mut processes = Vec::new();
+        let mut readable = false;
+
+        for (path, is_ipv6) in [("/proc/net/tcp", false), ("/proc/net/tcp6", true)] {
+            if let Ok(content) = tokio_fs::read_to_string(path).await {
+                processes.extend(self.parse_client_content(&content, is_ipv6, target));
+                readable = true;
+            }
+        }
+
+        if !readable {
+            return Err(crate::Error::IoError(
+                "Unable to read /proc/net/tcp or /proc/net/tcp6".to_string(),
+            ));
+        }
+
+        self.enrich_with_process_info(&mut processes).await?;
+        // Connections whose owner already exited cannot be attributed
+        processes.retain(|p| p.pid != 0);
+
+        Ok(processes)
+    }
+
+    /// Read TCP connections from `<net_dir>/tcp` and `<net_dir>/tcp6`
+    async fn read_tcp_connections(
+        &self,
+        net_dir: &str,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
+        let mut processes = Vec::new();
+        let mut readable = false;
 
         // Read IPv4 TCP connections
-        if let Ok(content) = tokio_fs::read_to_string("/proc/net/tcp").await {
-            processes.extend(self.parse_tcp_content(&content, false)?);
+        if let Ok(content) = tokio_fs::read_to_string(format!("{net_dir}/tcp")).await {
+            processes.extend(self.parse_tcp_content(&content, false, states)?);
+            readable = true;
         }
 
         // Read IPv6 TCP connections
-        if let Ok(content) = tokio_fs::read_to_string("/proc/net/tcp6").await {
-            processes.extend(self.parse_tcp_content(&content, true)?);
+        if let Ok(content) = tokio_fs::read_to_string(format!("{net_dir}/tcp6")).await {
+            processes.extend(self.parse_tcp_content(&content, true, states)?);
+            readable = true;
         }
 
-        // Filter only listening connections
-        processes.retain(|p| self.is_listening_connection(p));
+        if !readable {
+            return Err(crate::Error::IoError(format!(
+                "Unable to read {net_dir}/tcp or {net_dir}/tcp6"
+            )));
+        }
 
         Ok(processes)
     }
 
-    /// Read UDP connections from /proc/net/udp and /proc/net/udp6
-    async fn read_udp_connections(&self) -> Result<Vec<ProcessInfo>> {
+    /// Read UDP connections from `<net_dir>/udp` and `<net_dir>/udp6`
+    async fn read_udp_connections(&self, net_dir: &str) -> Result<Vec<ProcessInfo>> {
         let mut processes = Vec::new();
+        let mut readable = false;
 
         // Read IPv4 UDP connections
-        if let Ok(content) = tokio_fs::read_to_string("/proc/net/udp").await {
+        if let Ok(content) = tokio_fs::read_to_string(format!("{net_dir}/udp")).await {
             processes.extend(self.parse_udp_content(&content, false)?);
+            readable = true;
         }
 
         // Read IPv6 UDP connections
-        if let Ok(content) = tokio_fs::read_to_string("/proc/net/udp6").await {
+        if let Ok(content) = tokio_fs::read_to_string(format!("{net_dir}/udp6")).await {
             processes.extend(self.parse_udp_content(&content, true)?);
+            readable = true;
+        }
+
+        if !readable {
+            return Err(crate::Error::IoError(format!(
+                "Unable to read {net_dir}/udp or {net_dir}/udp6"
+            )));
         }
 
         Ok(processes)
     }
 
-    /// Parse TCP procfs content
-    fn parse_tcp_content(&self, content: &str, is_ipv6: bool) -> Result<Vec<ProcessInfo>> {
+    /// Read bound UNIX domain sockets from `<net_dir>/unix`
+    async fn read_unix_sockets(&self, net_dir: &str) -> Result<Vec<ProcessInfo>> {
+        let content = tokio_fs::read_to_string(format!("{net_dir}/unix"))
+            .await
+            .map_err(|e| crate::Error::IoError(format!("Unable to read {net_dir}/unix: {e}")))?;
+        Ok(self.parse_unix_content(&content))
+    }
+
+    /// Parse /proc/net/unix, keeping listening stream sockets and bound datagram sockets.
+    ///
+    /// Accepted connections inherit the listener's path, so stream sockets
+    /// are only kept when they carry the `__SO_ACCEPTCON` flag.
+    fn parse_unix_content(&self, content: &str) -> Vec<ProcessInfo> {
+        const SO_ACCEPTCON: u32 = 0x0001_0000;
+        const SOCK_DGRAM: u16 = 2;
+        const SS_UNCONNECTED: u8 = 1;
+
+        let mut processes = Vec::new();
+
+        for line in content.lines().skip(1) {
+            // Num RefCount Protocol Flags Type St Inode Path
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 8 {
+                // Unnamed sockets have no path and cannot be targeted
+                continue;
+            }
+
+            let (Ok(flags), Ok(socket_type), Ok(state), Ok(inode)) = (
+                u32::from_str_radix(parts[3], 16),
+                u16::from_str_radix(parts[4], 16),
+                u8::from_str_radix(parts[5], 16),
+                parts[6].parse::<u64>(),
+            ) else {
+                continue;
+            };
+
+            let listening = if socket_type == SOCK_DGRAM {
+                state == SS_UNCONNECTED
+            } else {
+                flags & SO_ACCEPTCON != 0
+            };
+            if !listening {
+                continue;
+            }
+
+            processes.push(ProcessInfo {
+                pid: 0, // Will be filled later
+                name: String::new(),
+                command: String::new(),
+                executable_path: String::new(),
+                working_directory: String::new(),
+                port: 0,
+                protocol: "unix".to_string(),
+                address: parts[7..].join(" "),
+                inode: Some(inode),
+                ..Default::default()
+            });
+        }
+
+        processes
+    }
+
+    /// Parse TCP procfs content, keeping sockets whose state is in `states`
+    fn parse_tcp_content(
+        &self,
+        content: &str,
+        is_ipv6: bool,
+        states: &[TcpState],
+    ) -> Result<Vec<ProcessInfo>> {
         let mut processes = Vec::new();
 
         for line in content.lines().skip(1) {
@@ -113,10 +325,17 @@ impl ProcfsPortManager {
             let state = parts[3];
             let inode = parts[9];
 
+            // Decode the kernel state (e.g. 0A = LISTEN, 08 = CLOSE_WAIT)
+            let Some(state) = u8::from_str_radix(state, 16)
+                .ok()
+                .and_then(TcpState::from_kernel)
+            else {
+                continue;
+            };
+
             // Parse local address and port
             if let Some((address, port)) = self.parse_address(local_address, is_ipv6) {
-                // Only process listening connections (state 0A = LISTEN)
-                if state == "0A" {
+                if states.contains(&state) {
                     if let Ok(inode_num) = inode.parse::<u64>() {
                         processes.push(ProcessInfo {
                             pid: 0, // Will be filled later
@@ -128,6 +347,9 @@ impl ProcfsPortManager {
                             protocol: "tcp".to_string(),
                             address,
                             inode: Some(inode_num),
+                            family: Some(family_of(is_ipv6)),
+                            state: Some(state),
+                            ..Default::default()
                         });
                     }
                 }
@@ -137,6 +359,66 @@ impl ProcfsPortManager {
         Ok(processes)
     }
 
+    /// Parse TCP procfs content, keeping established connections to `target`
+    fn parse_client_content(
+        &self,
+        content: &str,
+        is_ipv6: bool,
+        target: &PortTarget,
+    ) -> Vec<ProcessInfo> {
+        let mut processes = Vec::new();
+
+        for line in content.lines().skip(1) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 10 {
+                continue;
+            }
+
+            if u8::from_str_radix(parts[3], 16)
+                .ok()
+                .and_then(TcpState::from_kernel)
+                != Some(TcpState::Established)
+            {
+                continue;
+            }
+
+            // Match the remote columns against the target
+            let Some((remote_address, remote_port)) = self.parse_address(parts[2], is_ipv6) else {
+                continue;
+            };
+            let Some(remote_ip) = parse_bound_address(&remote_address) else {
+                continue;
+            };
+            if !target.matches_remote(remote_ip, remote_port) {
+                continue;
+            }
+
+            let (Some((address, port)), Ok(inode)) = (
+                self.parse_address(parts[1], is_ipv6),
+                parts[9].parse::<u64>(),
+            ) else {
+                continue;
+            };
+
+            processes.push(ProcessInfo {
+                pid: 0, // Will be filled later
+                name: String::new(),
+                command: String::new(),
+                executable_path: String::new(),
+                working_directory: String::new(),
+                port,
+                protocol: "tcp".to_string(),
+                address,
+                inode: Some(inode),
+                family: Some(family_of(is_ipv6)),
+                state: Some(TcpState::Established),
+                ..Default::default()
+            });
+        }
+
+        processes
+    }
+
     /// Parse UDP procfs content
     fn parse_udp_content(&self, content: &str, is_ipv6: bool) -> Result<Vec<ProcessInfo>> {
         let mut processes = Vec::new();
@@ -162,6 +444,8 @@ impl ProcfsPortManager {
                         protocol: "udp".to_string(),
                         address,
                         inode: Some(inode_num),
+                        family: Some(family_of(is_ipv6)),
+                        ..Default::default()
                     });
                 }
             }
@@ -214,27 +498,29 @@ impl ProcfsPortManager {
             return "*".to_string();
         }
 
-        // Convert hex string to IPv6 address
+        // The kernel prints the address as four 32-bit words in host byte
+        // order, so each word has to be converted back to network order
         let mut bytes = [0u8; 16];
-        for i in 0..16 {
-            bytes[i] = u8::from_str_radix(&hex[i * 2..(i + 1) * 2], 16).unwrap_or(0);
+        for (word, chunk) in bytes.chunks_exact_mut(4).enumerate() {
+            let value = u32::from_str_radix(&hex[word * 8..(word + 1) * 8], 16).unwrap_or(0);
+            chunk.copy_from_slice(&value.to_ne_bytes());
         }
 
         let addr = Ipv6Addr::from(bytes);
         addr.to_string()
     }
 
-    /// Check if connection is in listening state
-    fn is_listening_connection(&self, _process: &ProcessInfo) -> bool {
-        // For TCP, we already filtered by state in parse_tcp_content
-        // For UDP, all bound sockets are considered "listening"
-        true
-    }
-
-    /// Enrich process info by finding PIDs via inode matching
-    async fn enrich_with_process_info(&mut self, processes: &mut Vec<ProcessInfo>) -> Result<()> {
-        // Create inode to process mapping
-        let mut inode_to_pid: HashMap<u64, u32> = HashMap::new();
+    /// Enrich process info by finding PIDs via inode matching.
+    ///
+    /// A socket can be held by several processes (SO_REUSEPORT, pre-fork
+    /// servers, descriptors inherited across fork), so each socket yields
+    /// one entry per holding process.
+    pub(crate) async fn enrich_with_process_info(
+        &self,
+        processes: &mut Vec<ProcessInfo>,
+    ) -> Result<()> {
+        // Create inode to holders mapping
+        let mut inode_to_holders: HashMap<u64, Vec<(u32, u32)>> = HashMap::new();
 
         // Scan all processes to find socket inodes
         if let Ok(proc_entries) = tokio_fs::read_dir("/proc").await {
@@ -242,42 +528,62 @@ impl ProcfsPortManager {
             while let Ok(Some(entry)) = entries.next_entry().await {
                 if let Some(filename) = entry.file_name().to_str() {
                     if let Ok(pid) = filename.parse::<u32>() {
-                        self.scan_process_fds(pid, &mut inode_to_pid).await;
+                        self.scan_process_fds(pid, &mut inode_to_holders).await;
                     }
                 }
             }
         }
 
-        // Update processes with PID information
-        for process in processes.iter_mut() {
-            if let Some(inode) = process.inode {
-                if let Some(&pid) = inode_to_pid.get(&inode) {
-                    process.pid = pid;
-                    self.update_process_details(process).await?;
+        // Expand each socket into one entry per holder
+        let sockets = std::mem::take(processes);
+        for socket in sockets {
+            let Some(holders) = socket.inode.and_then(|inode| inode_to_holders.get(&inode)) else {
+                // Orphaned socket without an owning process. Listeners are
+                // dropped, but connections in TIME_WAIT/FIN_WAIT2 are owned by
+                // the kernel and still keep the port busy, so they are shown.
+                if socket.state.is_some_and(|state| state != TcpState::Listen) {
+                    processes.push(ProcessInfo {
+                        name: "-".to_string(),
+                        command: "-".to_string(),
+                        executable_path: "-".to_string(),
+                        working_directory: "-".to_string(),
+                        ..socket
+                    });
                 }
+                continue;
+            };
+
+            for &(pid, fd) in holders {
+                let mut process = socket.clone();
+                process.pid = pid;
+                process.fd = Some(fd);
+                self.update_process_details(&mut process).await?;
+                processes.push(process);
             }
         }
 
-        // Filter out processes without PID (orphaned sockets)
-        processes.retain(|p| p.pid != 0);
-
         Ok(())
     }
 
     /// Scan process file descriptors to find socket inodes
-    async fn scan_process_fds(&self, pid: u32, inode_to_pid: &mut HashMap<u64, u32>) {
+    async fn scan_process_fds(
+        &self,
+        pid: u32,
+        inode_to_holders: &mut HashMap<u64, Vec<(u32, u32)>>,
+    ) {
         let fd_path = format!("/proc/{pid}/fd");
         if let Ok(mut fd_entries) = tokio_fs::read_dir(&fd_path).await {
             while let Ok(Some(fd_entry)) = fd_entries.next_entry().await {
+                let Some(fd) = fd_entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.parse::<u32>().ok())
+                else {
+                    continue;
+                };
                 if let Ok(link_target) = tokio_fs::read_link(fd_entry.path()).await {
-                    if let Some(target_str) = link_target.to_str() {
-                        // Look for socket inodes: socket:[12345]
-                        if target_str.starts_with("socket:[") && target_str.ends_with(']') {
-                            let inode_str = &target_str[8..target_str.len() - 1];
-                            if let Ok(inode) = inode_str.parse::<u64>() {
-                                inode_to_pid.insert(inode, pid);
-                            }
-                        }
+                    if let Some(inode) = link_target.to_str().and_then(parse_socket_inode) {
+                        record_holder(inode_to_holders, inode, pid, fd);
                     }
                 }
             }
@@ -285,12 +591,13 @@ impl ProcfsPortManager {
     }
 
     /// Update process details from procfs
-    async fn update_process_details(&mut self, process: &mut ProcessInfo) -> Result<()> {
+    async fn update_process_details(&self, process: &mut ProcessInfo) -> Result<()> {
         let now = std::time::Instant::now();
 
         // Use cache if available and fresh
-        if now.duration_since(self.last_update) < self.cache_ttl {
-            if let Some(cached) = self.pid_cache.get(&process.pid) {
+        let last_update = *self.last_update.lock().unwrap();
+        if now.duration_since(last_update) < self.cache_ttl {
+            if let Some(cached) = self.pid_cache.lock().unwrap().get(&process.pid) {
                 process.name = cached.name.clone();
                 process.command = cached.command.clone();
                 process.executable_path = cached.executable_path.clone();
@@ -307,8 +614,8 @@ impl ProcfsPortManager {
         process.working_directory = details.working_directory.clone();
 
         // Update cache
-        self.pid_cache.insert(process.pid, details);
-        self.last_update = now;
+        self.pid_cache.lock().unwrap().insert(process.pid, details);
+        *self.last_update.lock().unwrap() = now;
 
         Ok(())
     }
@@ -361,31 +668,49 @@ impl ProcfsPortManager {
 
     /// Get display path for process (prefers working directory for dev processes)
     pub fn get_display_path(&self, process_info: &ProcessInfo) -> String {
-        // Same logic as original PortManager
-        if process_info.working_directory != "/" && process_info.working_directory != "Unknown" {
-            let is_dev_process = process_info.executable_path.contains("/node")
-                || process_info.executable_path.contains("/python")
-                || process_info.executable_path.contains("/ruby")
-                || process_info.executable_path.contains("/java")
-                || process_info.command.contains("npm")
-                || process_info.command.contains("yarn")
-                || process_info.command.contains("pnpm")
-                || process_info.command.contains("next")
-                || process_info.command.contains("serve")
-                || process_info.command.contains("dev");
-
-            if is_dev_process {
-                return process_info.working_directory.clone();
-            }
-        }
-
-        process_info.executable_path.clone()
+        super::display_path(process_info)
     }
 
     /// Clear cache (useful for forcing refresh)
-    pub fn clear_cache(&mut self) {
-        self.pid_cache.clear();
-        self.last_update = std::time::Instant::now() - self.cache_ttl;
+    pub fn clear_cache(&self) {
+        self.pid_cache.lock().unwrap().clear();
+        *self.last_update.lock().unwrap() = std::time::Instant::now() - self.cache_ttl;
+    }
+}
+
+fn family_of(is_ipv6: bool) -> IpFamily {
+    if is_ipv6 {
+        IpFamily::V6
+    } else {
+        IpFamily::V4
+    }
+}
+
+/// Parse a `socket:[12345]` fd link target into its inode
+fn parse_socket_inode(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|inode| inode.parse::<u64>().ok())
+}
+
+/// Record that `pid` holds `inode` on `fd`.
+///
+/// A process that duplicated the descriptor is recorded once, with its
+/// lowest fd number.
+fn record_holder(
+    inode_to_holders: &mut HashMap<u64, Vec<(u32, u32)>>,
+    inode: u64,
+    pid: u32,
+    fd: u32,
+) {
+    let holders = inode_to_holders.entry(inode).or_default();
+    match holders
+        .iter_mut()
+        .find(|(holder_pid, _)| *holder_pid == pid)
+    {
+        Some(holder) => holder.1 = holder.1.min(fd),
+        None => holders.push((pid, fd)),
     }
 }
 
@@ -402,14 +727,14 @@ mod tests {
     #[test]
     fn test_procfs_port_manager_creation() {
         let manager = ProcfsPortManager::new();
-        assert!(manager.pid_cache.is_empty());
+        assert!(manager.pid_cache.lock().unwrap().is_empty());
         assert_eq!(manager.cache_ttl, std::time::Duration::from_secs(2));
     }
 
     #[test]
     fn test_procfs_port_manager_default() {
         let manager = ProcfsPortManager::default();
-        assert!(manager.pid_cache.is_empty());
+        assert!(manager.pid_cache.lock().unwrap().is_empty());
     }
 
     #[test]
@@ -451,11 +776,25 @@ mod tests {
     #[test]
     fn test_parse_ipv6_address_localhost() {
         let manager = ProcfsPortManager::new();
-        // ::1 in hex: 00000000000000000000000000000001
-        let result = manager.parse_ipv6_address("00000000000000000000000000000001");
+        // ::1 as printed by the kernel: four host-order 32-bit words
+        let hex = if cfg!(target_endian = "little") {
+            "00000000000000000000000001000000"
+        } else {
+            "00000000000000000000000000000001"
+        };
+        let result = manager.parse_ipv6_address(hex);
         assert_eq!(result, "::1");
     }
 
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn test_parse_ipv6_address_kernel_word_order() {
+        let manager = ProcfsPortManager::new();
+        // fe80::1 in /proc/net/tcp6 on a little-endian host
+        let result = manager.parse_ipv6_address("000080FE000000000000000001000000");
+        assert_eq!(result, "fe80::1");
+    }
+
     #[test]
     fn test_parse_address_ipv4() {
         let manager = ProcfsPortManager::new();
@@ -502,7 +841,7 @@ mod tests {
     fn test_parse_tcp_content_empty() {
         let manager = ProcfsPortManager::new();
         let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
-        let result = manager.parse_tcp_content(content, false);
+        let result = manager.parse_tcp_content(content, false, &[TcpState::Listen]);
         assert!(result.is_ok());
         assert!(result.unwrap().is_empty());
     }
@@ -511,7 +850,7 @@ mod tests {
     fn test_parse_tcp_content_listening() {
         let manager = ProcfsPortManager::new();
         let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 12345 1 0000000000000000 100 0 0 10 0";
-        let result = manager.parse_tcp_content(content, false);
+        let result = manager.parse_tcp_content(content, false, &[TcpState::Listen]);
         assert!(result.is_ok());
         let processes = result.unwrap();
         assert_eq!(processes.len(), 1);
@@ -526,11 +865,74 @@ mod tests {
         let manager = ProcfsPortManager::new();
         // State 01 = ESTABLISHED, should be skipped
         let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   0: 00000000:1F90 00000000:0000 01 00000000:00000000 00:00000000 00000000     0        0 12345 1 0000000000000000 100 0 0 10 0";
-        let result = manager.parse_tcp_content(content, false);
+        let result = manager.parse_tcp_content(content, false, &[TcpState::Listen]);
         assert!(result.is_ok());
         assert!(result.unwrap().is_empty());
     }
 
+    #[test]
+    fn test_parse_tcp_content_with_states() {
+        let manager = ProcfsPortManager::new();
+        // 0A = LISTEN, 08 = CLOSE_WAIT, 06 = TIME_WAIT
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 12345 1 0000000000000000 100 0 0 10 0\n   1: 0100007F:1F90 0100007F:C350 08 00000000:00000000 00:00000000 00000000     0        0 12346 1 0000000000000000 100 0 0 10 0\n   2: 0100007F:1F90 0100007F:C351 06 00000000:00000000 03:00001770 00000000     0        0 0 3 0000000000000000";
+        let processes = manager
+            .parse_tcp_content(content, false, &[TcpState::CloseWait, TcpState::TimeWait])
+            .unwrap();
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].state, Some(TcpState::CloseWait));
+        assert_eq!(processes[0].address, "127.0.0.1");
+        assert_eq!(processes[1].state, Some(TcpState::TimeWait));
+        assert_eq!(processes[1].inode, Some(0));
+
+        let all = manager
+            .parse_tcp_content(content, false, &TcpState::ALL)
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].state, Some(TcpState::Listen));
+    }
+
+    #[test]
+    fn test_parse_client_content() {
+        let manager = ProcfsPortManager::new();
+        // 127.0.0.1:50000 -> 127.0.0.1:5432 (ESTABLISHED)、サーバー側の受付ソケット、LISTEN
+        let content = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n   0: 0100007F:1538 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 100 1 0000000000000000 100 0 0 10 0\n   1: 0100007F:C350 0100007F:1538 01 00000000:00000000 00:00000000 00000000  1000        0 200 1 0000000000000000 20 4 30 10 -1\n   2: 0100007F:1538 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 300 1 0000000000000000 20 4 30 10 -1";
+
+        let clients = manager.parse_client_content(content, false, &PortTarget::new(5432));
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].port, 50000);
+        assert_eq!(clients[0].address, "127.0.0.1");
+        assert_eq!(clients[0].inode, Some(200));
+        assert_eq!(clients[0].state, Some(TcpState::Established));
+
+        let other = "10.0.0.1:5432".parse::<PortTarget>().unwrap();
+        assert!(manager
+            .parse_client_content(content, false, &other)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_unix_content() {
+        let manager = ProcfsPortManager::new();
+        // リスナー、受け付け済み接続、無名ソケット、抽象名前空間、バインド済みDGRAM
+        let content = "Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 2666 /run/app.sock
+0000000000000000: 00000003 00000000 00000000 0001 03 2667 /run/app.sock
+0000000000000000: 00000003 00000000 00000000 0001 03 1031
+0000000000000000: 00000002 00000000 00010000 0001 01 3000 @/tmp/.X11-unix/X0
+0000000000000000: 00000002 00000000 00000000 0002 01 4000 /run/systemd/notify
+0000000000000000: 00000002 00000000 00000000 0002 03 4001 /dev/log";
+
+        let sockets = manager.parse_unix_content(content);
+        let paths: Vec<&str> = sockets.iter().map(|s| s.address.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["/run/app.sock", "@/tmp/.X11-unix/X0", "/run/systemd/notify"]
+        );
+        assert_eq!(sockets[0].inode, Some(2666));
+        assert_eq!(sockets[0].protocol, "unix");
+        assert_eq!(sockets[0].port, 0);
+    }
+
     #[test]
     fn test_parse_udp_content() {
         let manager = ProcfsPortManager::new();
@@ -557,6 +959,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "*".to_string(),
             inode: Some(12345),
+            ..Default::default()
         };
         let result = manager.get_display_path(&process_info);
         assert_eq!(result, "/home/user/project");
@@ -575,6 +978,7 @@ mod tests {
             protocol: "tcp".to_string(),
             address: "*".to_string(),
             inode: Some(12345),
+            ..Default::default()
         };
         let result = manager.get_display_path(&process_info);
         assert_eq!(result, "/usr/sbin/nginx");
@@ -582,8 +986,8 @@ mod tests {
 
     #[test]
     fn test_clear_cache() {
-        let mut manager = ProcfsPortManager::new();
-        manager.pid_cache.insert(
+        let manager = ProcfsPortManager::new();
+        manager.pid_cache.lock().unwrap().insert(
             1234,
             ProcessDetails {
                 name: "test".to_string(),
@@ -592,26 +996,43 @@ mod tests {
                 working_directory: "/".to_string(),
             },
         );
-        assert!(!manager.pid_cache.is_empty());
+        assert!(!manager.pid_cache.lock().unwrap().is_empty());
 
         manager.clear_cache();
-        assert!(manager.pid_cache.is_empty());
+        assert!(manager.pid_cache.lock().unwrap().is_empty());
     }
 
     #[test]
-    fn test_is_listening_connection() {
-        let manager = ProcfsPortManager::new();
-        let process_info = ProcessInfo {
-            pid: 1234,
-            name: "test".to_string(),
-            command: "test".to_string(),
-            executable_path: "/test".to_string(),
-            working_directory: "/".to_string(),
-            port: 3000,
-            protocol: "tcp".to_string(),
-            address: "*".to_string(),
-            inode: Some(12345),
-        };
-        assert!(manager.is_listening_connection(&process_info));
+    fn test_parse_socket_inode() {
+        assert_eq!(parse_socket_inode("socket:[12345]"), Some(12345));
+        assert_eq!(parse_socket_inode("pipe:[12345]"), None);
+        assert_eq!(parse_socket_inode("/dev/null"), None);
+        assert_eq!(parse_socket_inode("socket:[abc]"), None);
+    }
+
+    #[test]
+    fn test_record_holder_keeps_every_process() {
+        let mut holders = HashMap::new();
+
+        // SO_REUSEPORTやforkで複数プロセスが同じソケットを保持するケース
+        record_holder(&mut holders, 100, 1000, 5);
+        record_holder(&mut holders, 100, 1001, 5);
+        record_holder(&mut holders, 100, 1002, 7);
+        record_holder(&mut holders, 200, 1000, 6);
+
+        assert_eq!(holders[&100], vec![(1000, 5), (1001, 5), (1002, 7)]);
+        assert_eq!(holders[&200], vec![(1000, 6)]);
+    }
+
+    #[test]
+    fn test_record_holder_dup_fd_keeps_lowest() {
+        let mut holders = HashMap::new();
+
+        // dup()された同一プロセスのfdは最小のものだけ残す
+        record_holder(&mut holders, 100, 1000, 9);
+        record_holder(&mut holders, 100, 1000, 4);
+        record_holder(&mut holders, 100, 1000, 6);
+
+        assert_eq!(holders[&100], vec![(1000, 4)]);
     }
 }
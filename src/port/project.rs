@@ -0,0 +1,462 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Compose files, in the order docker compose looks for them
+const COMPOSE_FILES: &[&str] = &[
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+/// Extensions of the Vite configuration file
+const VITE_EXTENSIONS: &[&str] = &["ts", "js", "mts", "mjs", "cts", "cjs"];
+
+/// Port Vite serves on when its configuration does not set one
+const VITE_DEFAULT_PORT: u16 = 5173;
+
+/// Port Puma serves on when `config/puma.rb` does not set one
+const PUMA_DEFAULT_PORT: u16 = 3000;
+
+/// A port a project needs, and every place it was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectPort {
+    pub port: u16,
+    /// Where the port is declared, e.g. `package.json (scripts.dev)`
+    pub sources: Vec<String>,
+}
+
+/// Find the ports the project in `dir` declares in `package.json` scripts,
+/// compose files, `.env`, `vite.config.*` and `config/puma.rb`, lowest first
+pub fn discover_project_ports(dir: &Path) -> Vec<ProjectPort> {
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+    let mut found: Vec<(u16, String)> = Vec::new();
+
+    let scripts = read("package.json")
+        .map(|content| package_json_ports(&content))
+        .unwrap_or_default();
+    // フレームワークの既定ポートは、そのツールのポートが他で指定されていないときだけ使う
+    let vite_port_set = scripts.iter().any(|(_, _, script)| script.contains("vite"));
+    found.extend(
+        scripts
+            .into_iter()
+            .map(|(port, name, _)| (port, format!("package.json (scripts.{name})"))),
+    );
+    for name in COMPOSE_FILES {
+        if let Some(content) = read(name) {
+            found.extend(
+                compose_ports(&content)
+                    .into_iter()
+                    .map(|(port, service)| (port, format!("{name} ({service})"))),
+            );
+        }
+    }
+    let env = read(".env")
+        .map(|content| env_ports(&content))
+        .unwrap_or_default();
+    let env_set = !env.is_empty();
+    found.extend(env.into_iter().map(|port| (port, ".env".to_string())));
+    for extension in VITE_EXTENSIONS {
+        let name = format!("vite.config.{extension}");
+        if let Some(content) = read(&name) {
+            match vite_port(&content) {
+                Some(port) => found.push((port, name)),
+                None if !vite_port_set => {
+                    found.push((VITE_DEFAULT_PORT, format!("{name} (default)")))
+                }
+                None => {}
+            }
+        }
+    }
+    if let Some(content) = read("config/puma.rb") {
+        let name = "config/puma.rb";
+        match puma_port(&content) {
+            Some(port) => found.push((port, name.to_string())),
+            // Puma は PORT 環境変数を優先する
+            None if !env_set => found.push((PUMA_DEFAULT_PORT, format!("{name} (default)"))),
+            None => {}
+        }
+    }
+
+    let mut ports: Vec<ProjectPort> = Vec::new();
+    for (port, source) in found {
+        match ports.iter_mut().find(|p| p.port == port) {
+            Some(existing) if existing.sources.contains(&source) => {}
+            Some(existing) => existing.sources.push(source),
+            None => ports.push(ProjectPort {
+                port,
+                sources: vec![source],
+            }),
+        }
+    }
+    ports.sort_by_key(|p| p.port);
+    ports
+}
+
+/// A port number, ignoring surrounding quotes; `0` is not a port
+fn parse_port(s: &str) -> Option<u16> {
+    s.trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .parse::<u16>()
+        .ok()
+        .filter(|&port| port > 0)
+}
+
+/// `--port 5173`, `--port=5173` and `PORT=5173` in the `scripts` of package.json,
+/// with the name and command of the script
+fn package_json_ports(content: &str) -> Vec<(u16, String, String)> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(scripts) = package.get("scripts").and_then(|s| s.as_object()) else {
+        return Vec::new();
+    };
+
+    let mut ports = Vec::new();
+    for (name, script) in scripts {
+        let Some(script) = script.as_str() else {
+            continue;
+        };
+        let mut words = script.split_whitespace();
+        while let Some(word) = words.next() {
+            let port = if word == "--port" {
+                words.next().and_then(parse_port)
+            } else if let Some(value) = word.strip_prefix("--port=") {
+                parse_port(value)
+            } else if let Some(value) = word.strip_prefix("PORT=") {
+                parse_port(value)
+            } else {
+                None
+            };
+            if let Some(port) = port {
+                ports.push((port, name.clone(), script.to_string()));
+            }
+        }
+    }
+    ports
+}
+
+/// Published host ports of every service in a compose file, with the service.
+///
+/// Reads the short (`"8080:80"`, `"127.0.0.1:8080:80/tcp"`, `"9000-9001:9000-9001"`)
+/// and long (`published: 8080`) syntax line by line, without a YAML parser.
+/// A container port without a host port is published on a random port and
+/// is skipped.
+fn compose_ports(content: &str) -> Vec<(u16, String)> {
+    let mut ports = Vec::new();
+    let mut in_services = false;
+    let mut service_indent = None;
+    let mut service = String::new();
+    let mut ports_indent = None;
+
+    for line in content.lines() {
+        let text = match line.find(" #") {
+            Some(comment) => &line[..comment],
+            None => line,
+        };
+        let trimmed = text.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+
+        if let Some(list_indent) = ports_indent {
+            if indent > list_indent || (indent == list_indent && trimmed.starts_with('-')) {
+                let item = trimmed.trim_start_matches('-').trim();
+                let published = match item.split_once(':') {
+                    Some(("published", value)) => parse_port(value).map(|port| vec![port]),
+                    Some(("target" | "host_ip" | "protocol" | "mode" | "name", _)) => None,
+                    _ => Some(published_ports(item)),
+                };
+                for port in published.unwrap_or_default() {
+                    ports.push((port, service.clone()));
+                }
+                continue;
+            }
+            ports_indent = None;
+        }
+
+        if indent == 0 {
+            in_services = trimmed == "services:";
+            service_indent = None;
+            continue;
+        }
+        if !in_services {
+            continue;
+        }
+        if service_indent.is_none_or(|service_indent| indent <= service_indent) {
+            if let Some(name) = trimmed.strip_suffix(':') {
+                service = name.trim_matches(|c| c == '"' || c == '\'').to_string();
+                service_indent = Some(indent);
+            }
+            continue;
+        }
+        if trimmed == "ports:" {
+            ports_indent = Some(indent);
+        } else if let Some(list) = trimmed
+            .strip_prefix("ports:")
+            .map(str::trim)
+            .and_then(|list| list.strip_prefix('['))
+            .and_then(|list| list.strip_suffix(']'))
+        {
+            for item in list.split(',') {
+                for port in published_ports(item) {
+                    ports.push((port, service.clone()));
+                }
+            }
+        }
+    }
+    ports
+}
+
+/// Host ports of a short-syntax port mapping such as `127.0.0.1:8080:80/tcp`
+fn published_ports(mapping: &str) -> Vec<u16> {
+    let mapping = mapping.trim().trim_matches(|c| c == '"' || c == '\'');
+    let mapping = mapping.split('/').next().unwrap_or_default();
+    let Some((host, _container)) = mapping.rsplit_once(':') else {
+        return Vec::new();
+    };
+    // "127.0.0.1:8080" や "[::1]:8080" からポート部分を取り出す
+    let host_port = host.rsplit_once(':').map_or(host, |(_, port)| port);
+    match host_port.split_once('-') {
+        Some((start, end)) => match (parse_port(start), parse_port(end)) {
+            (Some(start), Some(end)) if start <= end => (start..=end).collect(),
+            _ => Vec::new(),
+        },
+        None => parse_port(host_port).into_iter().collect(),
+    }
+}
+
+/// `PORT=3000` (optionally exported or quoted) in a dotenv file
+fn env_ports(content: &str) -> Vec<u16> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "PORT").then(|| parse_port(value))?
+        })
+        .collect()
+}
+
+/// `server.port` of a Vite configuration.
+///
+/// The configuration is JavaScript, so this only finds a literal `port: 5173`
+/// directly inside a literal `server: { ... }`; a port computed at run time,
+/// or `preview.port`, is not found.
+fn vite_port(content: &str) -> Option<u16> {
+    key_values(content, "server").find_map(|rest| {
+        let block = rest.strip_prefix('{')?;
+        // server.hmr.port などの入れ子は除く
+        let mut depth = 0;
+        let mut top_level = String::new();
+        for c in block.chars() {
+            match c {
+                '{' | '[' => depth += 1,
+                '}' | ']' if depth == 0 => break,
+                '}' | ']' => depth -= 1,
+                _ if depth == 0 => top_level.push(c),
+                _ => {}
+            }
+        }
+        let port = key_values(&top_level, "port").find_map(|value| {
+            let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+            parse_port(&digits)
+        });
+        port
+    })
+}
+
+/// What follows each `key:` in `content`, after the colon and whitespace
+fn key_values<'a>(content: &'a str, key: &'a str) -> impl Iterator<Item = &'a str> {
+    content.match_indices(key).filter_map(move |(start, _)| {
+        let before = content[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+            return None;
+        }
+        let rest = content[start + key.len()..].trim_start();
+        Some(rest.strip_prefix(':')?.trim_start())
+    })
+}
+
+/// The port set by `port 3000`, `port ENV.fetch("PORT") { 3000 }` or
+/// `bind "tcp://0.0.0.0:3000"` in a Puma configuration
+fn puma_port(content: &str) -> Option<u16> {
+    content.lines().find_map(|line| {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("port ") {
+            rest.split(|c: char| !c.is_ascii_digit())
+                .find_map(parse_port)
+        } else if let Some(rest) = line.strip_prefix("bind ") {
+            let rest = rest.trim().trim_matches(|c| c == '"' || c == '\'');
+            parse_port(rest.rsplit_once(':')?.1)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_json_ports() {
+        let content = r#"{
+            "scripts": {
+                "dev": "vite --port 5173 --host",
+                "storybook": "storybook dev --port=6006",
+                "start": "PORT=4000 node server.js",
+                "test": "vitest"
+            }
+        }"#;
+        let mut ports: Vec<(u16, String)> = package_json_ports(content)
+            .into_iter()
+            .map(|(port, name, _)| (port, name))
+            .collect();
+        ports.sort();
+        assert_eq!(
+            ports,
+            [
+                (4000, "start".to_string()),
+                (5173, "dev".to_string()),
+                (6006, "storybook".to_string()),
+            ]
+        );
+        assert!(package_json_ports("not json").is_empty());
+    }
+
+    #[test]
+    fn test_compose_ports() {
+        let content = r#"
+version: "3.8"
+services:
+  web:
+    image: node:20
+    ports:
+      - "3000:3000"
+      - 127.0.0.1:9229:9229/tcp # debugger
+    environment:
+      - PORT=3000
+  db:
+    image: postgres
+    ports:
+      - target: 5432
+        published: 15432
+  cache:
+    image: redis
+    ports: ["6379"]
+  proxy:
+    ports: ["8080-8081:80-81"]
+volumes:
+  data:
+"#;
+        assert_eq!(
+            compose_ports(content),
+            [
+                (3000, "web".to_string()),
+                (9229, "web".to_string()),
+                (15432, "db".to_string()),
+                (8080, "proxy".to_string()),
+                (8081, "proxy".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_env_vite_and_puma_ports() {
+        assert_eq!(
+            env_ports("# app\nexport PORT=\"3001\"\nDB_PORT=5432\nPORT=\n"),
+            [3001]
+        );
+        assert_eq!(
+            vite_port("export default defineConfig({\n  server: {\n    port: 5174,\n  },\n})"),
+            Some(5174)
+        );
+        assert_eq!(
+            vite_port("export default defineConfig({ plugins: [] })"),
+            None
+        );
+        assert_eq!(
+            vite_port(
+                "export default {\n  preview: { port: 4173 },\n  server: { hmr: { port: 24678 }, proxy: { '/api': 'http://localhost:8080' }, port: 3001 },\n}"
+            ),
+            Some(3001)
+        );
+        assert_eq!(
+            vite_port("export default { preview: { port: 4173 } }"),
+            None
+        );
+        assert_eq!(
+            puma_port("threads 5, 5\nport ENV.fetch(\"PORT\") { 3002 }\n"),
+            Some(3002)
+        );
+        assert_eq!(puma_port("bind \"tcp://0.0.0.0:9292\"\n"), Some(9292));
+    }
+
+    #[test]
+    fn test_discover_project_ports() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"dev": "vite --port 5173"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("vite.config.ts"), "export default {}").unwrap();
+        fs::write(dir.path().join(".env"), "PORT=3000\n").unwrap();
+        fs::create_dir(dir.path().join("config")).unwrap();
+        fs::write(dir.path().join("config/puma.rb"), "port 3000\n").unwrap();
+
+        assert_eq!(
+            discover_project_ports(dir.path()),
+            [
+                ProjectPort {
+                    port: 3000,
+                    sources: vec![".env".to_string(), "config/puma.rb".to_string()],
+                },
+                ProjectPort {
+                    port: 5173,
+                    sources: vec!["package.json (scripts.dev)".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_framework_defaults_only_without_a_port() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"scripts": {"dev": "vite --port 4000"}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("vite.config.ts"), "export default {}").unwrap();
+        fs::write(dir.path().join(".env"), "PORT=3100\n").unwrap();
+        fs::create_dir(dir.path().join("config")).unwrap();
+        fs::write(dir.path().join("config/puma.rb"), "threads 5, 5\n").unwrap();
+
+        let ports: Vec<u16> = discover_project_ports(dir.path())
+            .iter()
+            .map(|p| p.port)
+            .collect();
+        assert_eq!(ports, [3100, 4000]);
+
+        fs::remove_file(dir.path().join("package.json")).unwrap();
+        fs::remove_file(dir.path().join(".env")).unwrap();
+        assert_eq!(
+            discover_project_ports(dir.path()),
+            [
+                ProjectPort {
+                    port: 3000,
+                    sources: vec!["config/puma.rb (default)".to_string()],
+                },
+                ProjectPort {
+                    port: 5173,
+                    sources: vec!["vite.config.ts (default)".to_string()],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use super::{ProcessInfo, Target};
+use crate::utils::glob_match;
+use crate::Result;
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// A port declared in the `[registry]` section of a configuration file,
+/// with the process expected to hold it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegistryEntry {
+    pub name: String,
+    pub port: u16,
+    /// Glob matched against the executable path, or against its file name
+    /// when the pattern has no `/`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    /// Glob matched against the working directory, which may also lie below
+    /// it; relative patterns are relative to the configuration file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+impl RegistryEntry {
+    /// Parse `name = 3000` or `name = { port = 3000, exe = "node", cwd = "web" }`
+    /// from the configuration file in `dir`; errors describe what is wrong
+    pub fn from_toml(
+        name: &str,
+        value: &toml::Value,
+        dir: &Path,
+    ) -> std::result::Result<Self, String> {
+        let invalid = |msg: String| format!("[registry] {name}: {msg}");
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Err(invalid(
+                "names must start with a letter so they cannot be mistaken for ports".to_string(),
+            ));
+        }
+        let port = |value: &toml::Value| {
+            value
+                .as_integer()
+                .and_then(|port| u16::try_from(port).ok())
+                .filter(|&port| port > 0)
+                .ok_or_else(|| invalid("port must be between 1 and 65535".to_string()))
+        };
+
+        let toml::Value::Table(table) = value else {
+            return Ok(Self {
+                name: name.to_string(),
+                port: port(value)?,
+                exe: None,
+                cwd: None,
+            });
+        };
+        let mut entry = Self {
+            name: name.to_string(),
+            port: port(
+                table
+                    .get("port")
+                    .ok_or_else(|| invalid("missing port".to_string()))?,
+            )?,
+            exe: None,
+            cwd: None,
+        };
+        for (key, value) in table {
+            let pattern = || {
+                value
+                    .as_str()
+                    .map(String::from)
+                    .ok_or_else(|| invalid(format!("{key} must be a string")))
+            };
+            match key.as_str() {
+                "port" => {}
+                "exe" => entry.exe = Some(pattern()?),
+                "cwd" => entry.cwd = Some(dir.join(pattern()?).to_string_lossy().into_owned()),
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown key '{key}' (expected port, exe or cwd)"
+                    )))
+                }
+            }
+        }
+        Ok(entry)
+    }
+
+    /// Whether `process` is the one this entry expects; any process is when
+    /// the entry names neither an executable nor a directory
+    pub fn is_owner(&self, process: &ProcessInfo) -> bool {
+        let exe = self.exe.as_ref().is_none_or(|pattern| {
+            if pattern.contains('/') {
+                glob_match(pattern, &process.executable_path)
+            } else {
+                let file_name = Path::new(&process.executable_path)
+                    .file_name()
+                    .map_or(process.name.clone(), |name| {
+                        name.to_string_lossy().into_owned()
+                    });
+                glob_match(pattern, &file_name) || glob_match(pattern, &process.name)
+            }
+        });
+        let cwd = self.cwd.as_ref().is_none_or(|pattern| {
+            glob_match(pattern, &process.working_directory)
+                || Path::new(&process.working_directory).starts_with(pattern)
+        });
+        exe && cwd
+    }
+}
+
+/// Who holds a registered port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistryStatus {
+    Free,
+    /// Every holder is the expected process
+    Owned,
+    /// At least one holder is not the expected process
+    Impostor,
+}
+
+impl RegistryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegistryStatus::Free => "free",
+            RegistryStatus::Owned => "owned",
+            RegistryStatus::Impostor => "impostor",
+        }
+    }
+}
+
+/// The ports a project declares in `[registry]`, so that they can be
+/// checked and addressed by name (`kilar kill frontend`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortRegistry {
+    entries: Vec<RegistryEntry>,
+}
+
+impl PortRegistry {
+    /// Registry of `entries`, ordered by port
+    pub fn new(mut entries: Vec<RegistryEntry>) -> Self {
+        entries.sort_by(|a, b| (a.port, &a.name).cmp(&(b.port, &b.name)));
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[RegistryEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegistryEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Parse a `check`/`kill` argument, which may also be a registry name
+    pub fn resolve<T>(&self, s: &str) -> Result<T>
+    where
+        T: FromStr<Err = crate::Error> + From<Target>,
+    {
+        match self.get(s) {
+            Some(entry) => Ok(T::from(Target::from(entry.port))),
+            None => s.parse(),
+        }
+    }
+
+    /// Names of the entries declaring the port of `process`
+    pub fn label(&self, process: &ProcessInfo) -> Option<String> {
+        if process.protocol == "unix" {
+            return None;
+        }
+        let names: Vec<&str> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.port == process.port)
+            .map(|entry| entry.name.as_str())
+            .collect();
+        (!names.is_empty()).then(|| names.join(","))
+    }
+
+    /// The status of `entry` given the processes listening on its port
+    pub fn status(entry: &RegistryEntry, holders: &[ProcessInfo]) -> RegistryStatus {
+        if holders.is_empty() {
+            RegistryStatus::Free
+        } else if holders.iter().all(|holder| entry.is_owner(holder)) {
+            RegistryStatus::Owned
+        } else {
+            RegistryStatus::Impostor
+        }
+    }
+}
+
+/// Label every listener whose port is declared in `registry`
+pub fn attach_registry(processes: &mut [ProcessInfo], registry: &PortRegistry) {
+    if registry.is_empty() {
+        return;
+    }
+    for process in processes.iter_mut() {
+        process.registry = registry.label(process);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(port: u16, exe: &str, cwd: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid: 4242,
+            name: Path::new(exe).file_name().unwrap().to_string_lossy().into(),
+            command: exe.to_string(),
+            executable_path: exe.to_string(),
+            working_directory: cwd.to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            address: "127.0.0.1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn entry(name: &str, toml: &str) -> std::result::Result<RegistryEntry, String> {
+        let value: toml::Table = format!("v = {toml}").parse().unwrap();
+        RegistryEntry::from_toml(name, &value["v"], Path::new("/work/shop"))
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let db = entry("db", "5432").unwrap();
+        assert_eq!((db.port, db.exe, db.cwd), (5432, None, None));
+
+        let web = entry("frontend", r#"{ port = 3000, exe = "node", cwd = "web" }"#).unwrap();
+        assert_eq!(web.port, 3000);
+        assert_eq!(web.exe.as_deref(), Some("node"));
+        assert_eq!(web.cwd.as_deref(), Some("/work/shop/web"));
+        let abs = entry("api", r#"{ port = 8080, cwd = "/srv/api*" }"#).unwrap();
+        assert_eq!(abs.cwd.as_deref(), Some("/srv/api*"));
+
+        assert!(entry("3000", "3000").is_err());
+        assert!(entry("db", "70000").is_err());
+        assert!(entry("db", r#"{ exe = "postgres" }"#).is_err());
+        assert!(entry("db", r#"{ port = 5432, user = "postgres" }"#).is_err());
+    }
+
+    #[test]
+    fn test_status() {
+        let web = entry("frontend", r#"{ port = 3000, exe = "node", cwd = "web" }"#).unwrap();
+        let owner = process(3000, "/usr/bin/node", "/work/shop/web/src");
+        let other_app = process(3000, "/usr/bin/node", "/work/blog");
+        let other_exe = process(3000, "/usr/bin/python3", "/work/shop/web");
+
+        assert_eq!(PortRegistry::status(&web, &[]), RegistryStatus::Free);
+        assert_eq!(
+            PortRegistry::status(&web, std::slice::from_ref(&owner)),
+            RegistryStatus::Owned
+        );
+        assert_eq!(
+            PortRegistry::status(&web, &[owner, other_app]),
+            RegistryStatus::Impostor
+        );
+        assert_eq!(
+            PortRegistry::status(&web, &[other_exe]),
+            RegistryStatus::Impostor
+        );
+
+        let db = entry("db", r#"{ port = 5432, exe = "/usr/lib/postgresql/*" }"#).unwrap();
+        assert!(db.is_owner(&process(5432, "/usr/lib/postgresql/16/bin/postgres", "/")));
+        assert!(!db.is_owner(&process(5432, "/usr/bin/socat", "/")));
+    }
+
+    #[test]
+    fn test_resolve_and_label() {
+        let registry = PortRegistry::new(vec![
+            entry("api", "8080").unwrap(),
+            entry("frontend", "3000").unwrap(),
+        ]);
+        assert_eq!(registry.entries()[0].name, "frontend");
+        assert_eq!(
+            registry.resolve::<Target>("frontend").unwrap(),
+            Target::from(3000)
+        );
+        assert_eq!(
+            registry.resolve::<Target>("3001").unwrap(),
+            Target::from(3001)
+        );
+        assert!(registry.resolve::<Target>("backend").is_err());
+
+        let mut processes = vec![
+            process(8080, "/usr/bin/java", "/"),
+            process(9000, "/usr/bin/php", "/"),
+        ];
+        attach_registry(&mut processes, &registry);
+        assert_eq!(processes[0].registry.as_deref(), Some("api"));
+        assert_eq!(processes[1].registry, None);
+    }
+}
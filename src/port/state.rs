@@ -0,0 +1,233 @@
+use crate::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// TCP connection state as defined in `include/net/tcp_states.h`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TcpState {
+    Established,
+    SynSent,
+    SynRecv,
+    #[serde(rename = "fin-wait-1")]
+    FinWait1,
+    #[serde(rename = "fin-wait-2")]
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    NewSynRecv,
+}
+
+impl TcpState {
+    /// Every state, in kernel order
+    pub const ALL: [TcpState; 12] = [
+        TcpState::Established,
+        TcpState::SynSent,
+        TcpState::SynRecv,
+        TcpState::FinWait1,
+        TcpState::FinWait2,
+        TcpState::TimeWait,
+        TcpState::Close,
+        TcpState::CloseWait,
+        TcpState::LastAck,
+        TcpState::Listen,
+        TcpState::Closing,
+        TcpState::NewSynRecv,
+    ];
+
+    /// Decode the kernel state number (`st` column of `/proc/net/tcp`)
+    pub fn from_kernel(code: u8) -> Option<Self> {
+        match code {
+            1..=12 => Some(Self::ALL[code as usize - 1]),
+            _ => None,
+        }
+    }
+
+    /// Kernel state number, e.g. 10 for `LISTEN`
+    pub fn kernel_code(&self) -> u8 {
+        *self as u8 + 1
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TcpState::Established => "established",
+            TcpState::SynSent => "syn-sent",
+            TcpState::SynRecv => "syn-recv",
+            TcpState::FinWait1 => "fin-wait-1",
+            TcpState::FinWait2 => "fin-wait-2",
+            TcpState::TimeWait => "time-wait",
+            TcpState::Close => "close",
+            TcpState::CloseWait => "close-wait",
+            TcpState::LastAck => "last-ack",
+            TcpState::Listen => "listen",
+            TcpState::Closing => "closing",
+            TcpState::NewSynRecv => "new-syn-recv",
+        }
+    }
+
+    /// Parse a state name as printed by `ss`, `lsof` or `netstat`
+    /// (`ESTAB`, `(CLOSE_WAIT)`, `FIN_WAIT2`, ...)
+    pub fn from_tool_name(name: &str) -> Option<Self> {
+        let name = name.trim_matches(|c| c == '(' || c == ')');
+        match name.to_uppercase().replace('_', "-").as_str() {
+            "ESTAB" => Some(TcpState::Established),
+            "SYN-RECEIVED" => Some(TcpState::SynRecv),
+            "UNCONN" => None,
+            other => other.parse().ok(),
+        }
+    }
+
+    /// Parse a comma-separated `--state` value; `all` selects every state
+    pub fn parse_list(value: &str) -> Result<Vec<TcpState>> {
+        let mut states = Vec::new();
+        for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if name.eq_ignore_ascii_case("all") {
+                return Ok(Self::ALL.to_vec());
+            }
+            let state = name.parse::<TcpState>()?;
+            if !states.contains(&state) {
+                states.push(state);
+            }
+        }
+
+        if states.is_empty() {
+            return Err(crate::Error::Other(
+                "No TCP state given. Use e.g. --state listen,established".to_string(),
+            ));
+        }
+
+        Ok(states)
+    }
+
+    /// Whether `states` selects nothing but listening sockets
+    pub fn is_listen_only(states: &[TcpState]) -> bool {
+        states.iter().all(|s| *s == TcpState::Listen)
+    }
+}
+
+impl fmt::Display for TcpState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TcpState {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized = s.trim().to_lowercase().replace(['_', '-'], "");
+        Self::ALL
+            .into_iter()
+            .find(|state| state.as_str().replace('-', "") == normalized)
+            .ok_or_else(|| {
+                crate::Error::Other(format!(
+                    "Invalid TCP state '{s}'. Must be one of: {}, or all",
+                    Self::ALL.map(|state| state.as_str()).join(", ")
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_kernel_codes() {
+        assert_eq!(TcpState::from_kernel(0x01), Some(TcpState::Established));
+        assert_eq!(TcpState::from_kernel(0x06), Some(TcpState::TimeWait));
+        assert_eq!(TcpState::from_kernel(0x08), Some(TcpState::CloseWait));
+        assert_eq!(TcpState::from_kernel(0x0A), Some(TcpState::Listen));
+        assert_eq!(TcpState::from_kernel(0x0C), Some(TcpState::NewSynRecv));
+        assert_eq!(TcpState::from_kernel(0), None);
+        assert_eq!(TcpState::from_kernel(13), None);
+
+        for state in TcpState::ALL {
+            assert_eq!(TcpState::from_kernel(state.kernel_code()), Some(state));
+        }
+    }
+
+    #[test]
+    fn test_parse_state_names() {
+        assert_eq!(
+            "established".parse::<TcpState>().unwrap(),
+            TcpState::Established
+        );
+        assert_eq!("TIME_WAIT".parse::<TcpState>().unwrap(), TcpState::TimeWait);
+        assert_eq!(
+            "close-wait".parse::<TcpState>().unwrap(),
+            TcpState::CloseWait
+        );
+        assert_eq!("finwait2".parse::<TcpState>().unwrap(), TcpState::FinWait2);
+
+        let result = "bogus".parse::<TcpState>();
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid TCP state"));
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            TcpState::parse_list("established,time-wait,close-wait").unwrap(),
+            vec![
+                TcpState::Established,
+                TcpState::TimeWait,
+                TcpState::CloseWait
+            ]
+        );
+        assert_eq!(
+            TcpState::parse_list("listen, listen").unwrap(),
+            vec![TcpState::Listen]
+        );
+        assert_eq!(TcpState::parse_list("all").unwrap().len(), 12);
+        assert!(TcpState::parse_list("").is_err());
+        assert!(TcpState::parse_list("listen,nope").is_err());
+    }
+
+    #[test]
+    fn test_from_tool_name() {
+        assert_eq!(
+            TcpState::from_tool_name("ESTAB"),
+            Some(TcpState::Established)
+        );
+        assert_eq!(
+            TcpState::from_tool_name("(CLOSE_WAIT)"),
+            Some(TcpState::CloseWait)
+        );
+        assert_eq!(
+            TcpState::from_tool_name("FIN_WAIT2"),
+            Some(TcpState::FinWait2)
+        );
+        assert_eq!(TcpState::from_tool_name("LISTEN"), Some(TcpState::Listen));
+        assert_eq!(TcpState::from_tool_name("UNCONN"), None);
+    }
+
+    #[test]
+    fn test_serialize_kebab_case() {
+        assert_eq!(
+            serde_json::to_string(&TcpState::CloseWait).unwrap(),
+            "\"close-wait\""
+        );
+        assert_eq!(
+            serde_json::to_string(&TcpState::FinWait2).unwrap(),
+            "\"fin-wait-2\""
+        );
+    }
+
+    #[test]
+    fn test_is_listen_only() {
+        assert!(TcpState::is_listen_only(&[TcpState::Listen]));
+        assert!(!TcpState::is_listen_only(&[
+            TcpState::Listen,
+            TcpState::Established
+        ]));
+    }
+}
@@ -0,0 +1,29 @@
+//! Helpers shared by the unit tests
+
+use super::{PortBackend, ProcessInfo};
+use crate::Result;
+use async_trait::async_trait;
+use std::net::{Ipv4Addr, TcpListener};
+
+/// A backend that reports no sockets
+pub(crate) struct EmptyBackend;
+
+#[async_trait]
+impl PortBackend for EmptyBackend {
+    fn name(&self) -> &'static str {
+        "EmptyBackend"
+    }
+
+    async fn list_processes(&self, _protocol: &str) -> Result<Vec<ProcessInfo>> {
+        Ok(Vec::new())
+    }
+}
+
+/// OSが割り当てた空きポート
+pub(crate) fn unused_port() -> u16 {
+    TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
@@ -1,6 +1,220 @@
-use crate::Result;
+use crate::{port::ProcessInfo, Result};
+use std::os::fd::OwnedFd;
+use std::time::{Duration, Instant};
 use tokio::process::Command as TokioCommand;
 
+pub mod protect;
+pub mod signal;
+pub mod systemd;
+pub mod tree;
+
+pub use protect::{ProtectField, ProtectPattern, Protection};
+pub use signal::{Escalation, EscalationStep, Signal};
+pub use systemd::{SystemctlStopper, SystemdUnit, UnitStopper};
+
+use signal::FINAL_GRACE;
+
+/// How often a signalled process is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A process to signal: its PID and, when it was recorded at scan time, its
+/// start time, so that a PID recycled since the scan is never signalled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessRef {
+    pub pid: u32,
+    pub start_time: Option<u64>,
+}
+
+impl From<u32> for ProcessRef {
+    fn from(pid: u32) -> Self {
+        Self {
+            pid,
+            start_time: None,
+        }
+    }
+}
+
+impl From<&ProcessInfo> for ProcessRef {
+    fn from(process: &ProcessInfo) -> Self {
+        Self {
+            pid: process.pid,
+            start_time: process.start_time,
+        }
+    }
+}
+
+impl From<&tree::ProcStat> for ProcessRef {
+    fn from(stat: &tree::ProcStat) -> Self {
+        Self {
+            pid: stat.pid,
+            start_time: stat.start_time,
+        }
+    }
+}
+
+/// An opened process that signals go to.
+///
+/// On Linux 5.3+ this holds a pidfd, which keeps referring to the same
+/// process even if its PID is reused. Elsewhere the start time is checked
+/// again before every signal.
+struct ProcessHandle {
+    pid: u32,
+    start_time: Option<u64>,
+    pidfd: Option<OwnedFd>,
+}
+
+impl ProcessHandle {
+    fn open(process: ProcessRef) -> Result<Self> {
+        let pid = raw_pid(process.pid)?;
+        let handle = Self {
+            pid: process.pid,
+            start_time: process.start_time,
+            pidfd: pidfd_open(pid).map_err(|e| signal_error(process.pid, e))?,
+        };
+        // pidfdを開いた後に確認するので、確認できたプロセスがそのままシグナルの宛先になる
+        handle.verify()?;
+        Ok(handle)
+    }
+
+    /// Fail if the PID now belongs to a process other than the one scanned
+    fn verify(&self) -> Result<()> {
+        match (self.start_time, tree::start_time(self.pid)) {
+            (Some(expected), Some(actual)) if expected != actual => {
+                Err(crate::Error::ProcessReplaced(self.pid))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn signal(&self, signal: Signal) -> Result<()> {
+        let result = match &self.pidfd {
+            Some(pidfd) => pidfd_send_signal(pidfd, signal),
+            None => {
+                self.verify()?;
+                kill(raw_pid(self.pid)?, signal)
+            }
+        };
+        result.map_err(|e| signal_error(self.pid, e))
+    }
+
+    fn is_alive(&self) -> bool {
+        if let Some(pidfd) = &self.pidfd {
+            return !pidfd_exited(pidfd);
+        }
+        let exists = match raw_pid(self.pid).map(|pid| kill(pid, Signal::NONE)) {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => e.raw_os_error() == Some(libc::EPERM),
+            Err(_) => false,
+        };
+        // 親が回収するまで残るゾンビや、PIDが再利用された場合は終了済みとみなす
+        exists && !is_zombie(self.pid) && self.verify().is_ok()
+    }
+}
+
+/// `pid` as a `pid_t`; 0 and values past `i32::MAX` would address a whole
+/// process group, so they are rejected
+fn raw_pid(pid: u32) -> Result<libc::pid_t> {
+    match libc::pid_t::try_from(pid) {
+        Ok(raw) if raw > 0 => Ok(raw),
+        _ => Err(crate::Error::ProcessNotFound(pid)),
+    }
+}
+
+/// Map a failed signal delivery to an error
+fn signal_error(pid: u32, error: std::io::Error) -> crate::Error {
+    match error.raw_os_error() {
+        Some(libc::ESRCH) => crate::Error::ProcessNotFound(pid),
+        Some(libc::EPERM) => crate::Error::PermissionDenied(
+            "プロセス終了の権限がありません。sudoで実行してください。".to_string(),
+        ),
+        _ => crate::Error::IoError(format!("Failed to signal process {pid}: {error}")),
+    }
+}
+
+fn kill(pid: libc::pid_t, signal: Signal) -> std::io::Result<()> {
+    if unsafe { libc::kill(pid, signal.number()) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+/// Open a pidfd for `pid`, or `None` where pidfds are unavailable (old
+/// kernels, seccomp filters, non-Linux systems)
+#[cfg(target_os = "linux")]
+fn pidfd_open(pid: libc::pid_t) -> std::io::Result<Option<OwnedFd>> {
+    use std::os::fd::FromRawFd;
+
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if fd >= 0 {
+        return Ok(Some(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) }));
+    }
+    let error = std::io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::ESRCH) => Err(error),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pidfd_open(_pid: libc::pid_t) -> std::io::Result<Option<OwnedFd>> {
+    Ok(None)
+}
+
+#[cfg(target_os = "linux")]
+fn pidfd_send_signal(pidfd: &OwnedFd, signal: Signal) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_pidfd_send_signal,
+            pidfd.as_raw_fd(),
+            signal.number(),
+            std::ptr::null::<libc::siginfo_t>(),
+            0,
+        )
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pidfd_send_signal(_pidfd: &OwnedFd, _signal: Signal) -> std::io::Result<()> {
+    Err(std::io::Error::from_raw_os_error(libc::ENOSYS))
+}
+
+/// Whether the process behind `pidfd` has exited; the fd becomes readable then
+fn pidfd_exited(pidfd: &OwnedFd) -> bool {
+    use std::os::fd::AsRawFd;
+
+    let mut poll_fd = libc::pollfd {
+        fd: pidfd.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ready = unsafe { libc::poll(&mut poll_fd, 1, 0) };
+    ready > 0
+}
+
+/// Whether `pid` has exited but not been reaped yet (Linux only)
+fn is_zombie(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()
+        .and_then(|stat| {
+            let (_, rest) = stat.rsplit_once(')')?;
+            rest.split_whitespace().next().map(|state| state == "Z")
+        })
+        .unwrap_or(false)
+}
+
+/// Whether `process` is still running and its PID has not been reused
+pub fn is_running(process: impl Into<ProcessRef>) -> bool {
+    ProcessHandle::open(process.into()).is_ok_and(|handle| handle.is_alive())
+}
+
 pub struct ProcessManager;
 
 impl ProcessManager {
@@ -8,70 +222,152 @@ impl ProcessManager {
         Self
     }
 
+    /// Terminate a process with the default escalation (SIGTERM, then SIGKILL)
     pub async fn kill_process(&self, pid: u32) -> Result<()> {
-        self.kill_process_unix(pid).await
+        self.kill_process_with(pid, &Escalation::default()).await
     }
 
-    async fn kill_process_unix(&self, pid: u32) -> Result<()> {
-        // まずSIGTERMで優雅な終了を試行
-        let output = TokioCommand::new("kill")
-            .arg("-TERM")
-            .arg(pid.to_string())
-            .output()
+    /// Terminate a process, walking `escalation` until it has exited.
+    ///
+    /// Fails with [`crate::Error::ProcessReplaced`] when the process has
+    /// exited since its start time was recorded and the PID was reused.
+    pub async fn kill_process_with(
+        &self,
+        process: impl Into<ProcessRef>,
+        escalation: &Escalation,
+    ) -> Result<()> {
+        let handle = ProcessHandle::open(process.into())?;
+        if !handle.is_alive() {
+            return Err(crate::Error::ProcessNotFound(handle.pid));
+        }
+        self.escalate(vec![handle], escalation).await
+    }
+
+    /// Terminate several processes in the given order (children first).
+    ///
+    /// Every process gets SIGTERM before any is escalated to SIGKILL, so a
+    /// parent cannot respawn a child that was killed ahead of it. Processes
+    /// that have already exited are ignored.
+    pub async fn kill_processes(&self, pids: &[u32]) -> Result<()> {
+        let processes: Vec<ProcessRef> = pids.iter().map(|pid| ProcessRef::from(*pid)).collect();
+        self.kill_processes_with(&processes, &Escalation::default())
             .await
-            .map_err(|e| crate::Error::CommandFailed(format!("kill command failed: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.contains("No such process") {
-                return Err(crate::Error::ProcessNotFound(pid));
-            } else if stderr.contains("Operation not permitted") {
-                return Err(crate::Error::PermissionDenied(
-                    "プロセス終了の権限がありません。sudoで実行してください。".to_string(),
-                ));
+    }
+
+    /// Like [`Self::kill_processes`], with a custom escalation ladder.
+    ///
+    /// Processes whose PID has been reused since it was recorded are skipped
+    /// like those that have exited.
+    pub async fn kill_processes_with(
+        &self,
+        processes: &[ProcessRef],
+        escalation: &Escalation,
+    ) -> Result<()> {
+        let mut handles = Vec::with_capacity(processes.len());
+        for process in processes {
+            match ProcessHandle::open(*process) {
+                Ok(handle) => handles.push(handle),
+                Err(crate::Error::ProcessNotFound(_) | crate::Error::ProcessReplaced(_)) => {}
+                Err(e) => return Err(e),
             }
-            return Err(crate::Error::CommandFailed(format!(
-                "Failed to kill process: {stderr}"
-            )));
         }
+        self.escalate(handles, escalation).await
+    }
+
+    /// [`Self::kill_process_with`] behind `protection`: a process it guards
+    /// fails with [`crate::Error::Protected`] and is not signalled.
+    ///
+    /// Commands kill through this (or [`Self::kill_all_guarded`]), so that
+    /// `--protect` and the built-in protections apply however the process
+    /// was picked.
+    pub async fn kill_guarded(
+        &self,
+        process: &ProcessInfo,
+        protection: &Protection,
+        escalation: &Escalation,
+    ) -> Result<()> {
+        protection.check(process)?;
+        self.kill_process_with(process, escalation).await
+    }
+
+    /// [`Self::kill_processes_with`] behind `protection`: when it guards any
+    /// of `processes`, none is signalled
+    pub async fn kill_all_guarded(
+        &self,
+        processes: &[ProcessInfo],
+        protection: &Protection,
+        escalation: &Escalation,
+    ) -> Result<()> {
+        for process in processes {
+            protection.check(process)?;
+        }
+        let processes: Vec<ProcessRef> = processes.iter().map(ProcessRef::from).collect();
+        self.kill_processes_with(&processes, escalation).await
+    }
 
-        // 少し待ってプロセスが終了したか確認
-        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-
-        // プロセスがまだ存在するかチェック
-        if self.process_exists(pid).await? {
-            // SIGKILLで強制終了
-            let output = TokioCommand::new("kill")
-                .arg("-KILL")
-                .arg(pid.to_string())
-                .output()
-                .await
-                .map_err(|e| crate::Error::CommandFailed(format!("kill -KILL failed: {e}")))?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(crate::Error::CommandFailed(format!(
-                    "Failed to force kill process: {stderr}"
-                )));
+    /// Walk `escalation`: each step signals every process still alive, then
+    /// polls until they have all exited or the step's wait runs out
+    async fn escalate(
+        &self,
+        mut remaining: Vec<ProcessHandle>,
+        escalation: &Escalation,
+    ) -> Result<()> {
+        for step in &escalation.steps {
+            if remaining.is_empty() {
+                return Ok(());
+            }
+            for handle in &remaining {
+                match handle.signal(step.signal) {
+                    Ok(())
+                    | Err(crate::Error::ProcessNotFound(_) | crate::Error::ProcessReplaced(_)) => {}
+                    Err(e) => return Err(e),
+                }
             }
+            remaining = Self::wait_for_exit(remaining, step.wait.unwrap_or(FINAL_GRACE)).await;
         }
 
-        Ok(())
+        if remaining.is_empty() {
+            return Ok(());
+        }
+        let pids: Vec<String> = remaining.iter().map(|h| h.pid.to_string()).collect();
+        Err(crate::Error::CommandFailed(format!(
+            "Process {} still running after {}",
+            pids.join(", "),
+            escalation
+        )))
+    }
+
+    /// Send `signal` to `pid`
+    pub fn send_signal(&self, process: impl Into<ProcessRef>, signal: Signal) -> Result<()> {
+        ProcessHandle::open(process.into())?.signal(signal)
+    }
+
+    /// Poll until every process has exited or `timeout` passes; returns the survivors
+    async fn wait_for_exit(
+        mut handles: Vec<ProcessHandle>,
+        timeout: Duration,
+    ) -> Vec<ProcessHandle> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            handles.retain(ProcessHandle::is_alive);
+            if handles.is_empty() || Instant::now() >= deadline {
+                return handles;
+            }
+            tokio::time::sleep(POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
     }
 
-    async fn process_exists(&self, pid: u32) -> Result<bool> {
+    /// Whether `pid` is running; exited but unreaped processes count as gone
+    pub async fn process_exists(&self, pid: u32) -> Result<bool> {
         self.process_exists_unix(pid).await
     }
 
     async fn process_exists_unix(&self, pid: u32) -> Result<bool> {
-        let output = TokioCommand::new("ps")
-            .arg("-p")
-            .arg(pid.to_string())
-            .output()
-            .await
-            .map_err(|e| crate::Error::CommandFailed(format!("ps command failed: {e}")))?;
-
-        Ok(output.status.success())
+        match ProcessHandle::open(pid.into()) {
+            Ok(handle) => Ok(handle.is_alive()),
+            Err(crate::Error::ProcessNotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
     }
 
     pub async fn get_process_info(&self, pid: u32) -> Result<(String, String)> {
@@ -345,6 +641,143 @@ mod tests {
         }
     }
 
+    /// 子プロセスを起動し、終了時に回収するスレッドを付ける
+    fn spawn_reaped(script: &str) -> u32 {
+        let mut child = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .spawn()
+            .expect("Failed to spawn sh");
+        let pid = child.id();
+        std::thread::spawn(move || child.wait());
+        pid
+    }
+
+    #[tokio::test]
+    async fn test_kill_process_with_polls_until_exit() {
+        let process_manager = ProcessManager::new();
+        let pid = spawn_reaped("exec sleep 30");
+        let escalation: Escalation = "INT:10s,KILL".parse().unwrap();
+
+        // 終了を待つのはタイムアウトまでではなく、終了を検知するまで
+        let started = Instant::now();
+        process_manager
+            .kill_process_with(pid, &escalation)
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!process_manager.process_exists(pid).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_kill_process_with_escalates() {
+        let process_manager = ProcessManager::new();
+        // SIGTERMを無視するプロセスはSIGKILLまでエスカレーションする
+        let pid = spawn_reaped("trap '' TERM; while :; do sleep 0.1; done");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let escalation: Escalation = "TERM:300ms,KILL".parse().unwrap();
+
+        process_manager
+            .kill_process_with(pid, &escalation)
+            .await
+            .unwrap();
+        assert!(!process_manager.process_exists(pid).await.unwrap());
+
+        // 最後のシグナルでも終了しない場合はエラー
+        let pid = spawn_reaped("trap '' TERM; while :; do sleep 0.1; done");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let escalation: Escalation = "TERM:100ms".parse().unwrap();
+        let result = process_manager.kill_process_with(pid, &escalation).await;
+        assert!(result.unwrap_err().to_string().contains("still running"));
+        process_manager.send_signal(pid, Signal::KILL).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_kill_refuses_reused_pid() {
+        let process_manager = ProcessManager::new();
+        let pid = spawn_reaped("exec sleep 30");
+        let start_time = tree::start_time(pid).expect("start time of a running process");
+
+        // スキャン時と開始時刻が異なる場合はPIDが再利用されたとみなす
+        let stale = ProcessRef {
+            pid,
+            start_time: Some(start_time + 1),
+        };
+        let result = process_manager
+            .kill_process_with(stale, &Escalation::default())
+            .await;
+        assert!(matches!(result, Err(crate::Error::ProcessReplaced(p)) if p == pid));
+        process_manager
+            .kill_processes_with(&[stale], &Escalation::default())
+            .await
+            .unwrap();
+        assert!(process_manager.process_exists(pid).await.unwrap());
+
+        let current = ProcessRef {
+            pid,
+            start_time: Some(start_time),
+        };
+        process_manager
+            .kill_process_with(current, &Escalation::default())
+            .await
+            .unwrap();
+        assert!(!process_manager.process_exists(pid).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_kill_guarded_refuses_protected() {
+        let process_manager = ProcessManager::new();
+        let pid = spawn_reaped("exec sleep 30");
+        let process = ProcessInfo::new(pid, "sleep", 8080, "tcp");
+        let protection = Protection {
+            patterns: vec!["port:8080".parse().unwrap()],
+            allow: false,
+        };
+
+        let result = process_manager
+            .kill_guarded(&process, &protection, &Escalation::default())
+            .await;
+        assert!(matches!(result, Err(crate::Error::Protected { pid: p, .. }) if p == pid));
+        let result = process_manager
+            .kill_all_guarded(
+                &[ProcessInfo::new(pid, "sleep", 0, ""), process.clone()],
+                &protection,
+                &Escalation::default(),
+            )
+            .await;
+        assert!(result.is_err());
+        assert!(process_manager.process_exists(pid).await.unwrap());
+
+        process_manager
+            .kill_guarded(&process, &Protection::default(), &Escalation::default())
+            .await
+            .unwrap();
+        assert!(!process_manager.process_exists(pid).await.unwrap());
+    }
+
+    #[test]
+    fn test_signal_error_mapping() {
+        let error = signal_error(42, std::io::Error::from_raw_os_error(libc::ESRCH));
+        assert!(matches!(error, crate::Error::ProcessNotFound(42)));
+        let error = signal_error(42, std::io::Error::from_raw_os_error(libc::EPERM));
+        assert!(matches!(error, crate::Error::PermissionDenied(_)));
+        let error = signal_error(42, std::io::Error::from_raw_os_error(libc::EINVAL));
+        assert!(matches!(error, crate::Error::IoError(_)));
+    }
+
+    #[test]
+    fn test_send_signal_rejects_group_pids() {
+        let process_manager = ProcessManager::new();
+        // 0やi32に収まらないPIDはプロセスグループ宛てになるため送信しない
+        for pid in [0, u32::MAX] {
+            assert!(matches!(
+                process_manager.send_signal(pid, Signal::TERM),
+                Err(crate::Error::ProcessNotFound(_))
+            ));
+        }
+    }
+
     #[test]
     fn test_process_manager_struct_properties() {
         // ProcessManager構造体のプロパティをテスト
@@ -0,0 +1,305 @@
+use super::tree;
+use crate::port::ProcessInfo;
+use crate::utils::glob_match;
+use crate::Result;
+use std::fmt;
+use std::str::FromStr;
+
+/// Process names that are never killed without `--allow-protected`
+const BUILTIN_NAMES: &[&str] = &["init", "sshd", "sshd-session", "systemd", "systemd-*"];
+
+/// What a protection pattern is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtectField {
+    /// The process name
+    Name,
+    /// The path of the executable
+    Exe,
+    /// The user name or numeric UID owning the process
+    User,
+    /// The port the process holds, a port glob or a `START-END` range
+    Port,
+}
+
+impl ProtectField {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ProtectField::Name => "name",
+            ProtectField::Exe => "exe",
+            ProtectField::User => "user",
+            ProtectField::Port => "port",
+        }
+    }
+}
+
+/// A user-supplied protection rule such as `name:postgres`, `exe:/usr/sbin/*`,
+/// `user:root` or `port:5432`. A pattern without a field matches the name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtectPattern {
+    pub field: ProtectField,
+    pub pattern: String,
+}
+
+impl ProtectPattern {
+    /// Whether `process` matches; `owner` looks up its user only when needed
+    fn matches(&self, process: &ProcessInfo, owner: impl FnOnce() -> Option<Owner>) -> bool {
+        match self.field {
+            ProtectField::Name => glob_match(&self.pattern, &process.name),
+            ProtectField::Exe => glob_match(&self.pattern, &process.executable_path),
+            ProtectField::User => owner().is_some_and(|owner| {
+                glob_match(&self.pattern, &owner.uid.to_string())
+                    || owner
+                        .name
+                        .is_some_and(|name| glob_match(&self.pattern, &name))
+            }),
+            ProtectField::Port => match self.pattern.split_once('-') {
+                Some((start, end)) => match (start.parse::<u16>(), end.parse::<u16>()) {
+                    (Ok(start), Ok(end)) => (start..=end).contains(&process.port),
+                    _ => false,
+                },
+                None => glob_match(&self.pattern, &process.port.to_string()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ProtectPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.field.as_str(), self.pattern)
+    }
+}
+
+impl FromStr for ProtectPattern {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (field, pattern) = match s.split_once(':') {
+            Some(("name", pattern)) => (ProtectField::Name, pattern),
+            Some(("exe", pattern)) => (ProtectField::Exe, pattern),
+            Some(("user", pattern)) => (ProtectField::User, pattern),
+            Some(("port", pattern)) => (ProtectField::Port, pattern),
+            Some((field, _)) if !field.contains('/') => {
+                return Err(crate::Error::ParseError(format!(
+                    "Unknown protect field '{field}' (expected name, exe, user or port)"
+                )))
+            }
+            _ => (ProtectField::Name, s),
+        };
+        if pattern.is_empty() {
+            return Err(crate::Error::ParseError(format!(
+                "Empty protect pattern '{s}'"
+            )));
+        }
+        Ok(Self {
+            field,
+            pattern: pattern.to_string(),
+        })
+    }
+}
+
+/// The user a process runs as
+#[derive(Clone)]
+struct Owner {
+    uid: u32,
+    name: Option<String>,
+}
+
+/// Look up who owns `pid` (Linux only)
+fn process_owner(pid: u32) -> Option<Owner> {
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = std::fs::metadata(format!("/proc/{pid}")).ok()?.uid();
+    let name = std::fs::read_to_string("/etc/passwd")
+        .ok()
+        .and_then(|passwd| user_name(&passwd, uid));
+    Some(Owner { uid, name })
+}
+
+/// The name of `uid` in the contents of `/etc/passwd`
+fn user_name(passwd: &str, uid: u32) -> Option<String> {
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        let name = fields.next()?;
+        let entry_uid = fields.nth(1)?.parse::<u32>().ok()?;
+        (entry_uid == uid).then(|| name.to_string())
+    })
+}
+
+/// Guards the processes that must not be killed by accident.
+///
+/// Besides the `patterns` given with `--protect`, init, sshd, systemd and its
+/// helpers, kernel threads, kilar itself, its parents and its session leader
+/// are always protected. `allow` (`--allow-protected`) lifts every protection.
+#[derive(Debug, Clone, Default)]
+pub struct Protection {
+    pub patterns: Vec<ProtectPattern>,
+    pub allow: bool,
+}
+
+impl Protection {
+    /// Fail with [`crate::Error::Protected`] when `process` must not be killed
+    pub fn check(&self, process: &ProcessInfo) -> Result<()> {
+        if self.allow {
+            return Ok(());
+        }
+        match self.reason(process) {
+            Some(reason) => Err(crate::Error::Protected {
+                pid: process.pid,
+                name: process.name.clone(),
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Why `process` is protected, or `None` when it may be killed
+    pub fn reason(&self, process: &ProcessInfo) -> Option<String> {
+        let pid = process.pid;
+        if pid == 1 {
+            return Some("it is init".to_string());
+        }
+        if let Some(pattern) = BUILTIN_NAMES
+            .iter()
+            .find(|pattern| glob_match(pattern, &process.name))
+        {
+            return Some(format!("it matches the built-in pattern {pattern}"));
+        }
+        if i64::from(pid) == i64::from(unsafe { libc::getsid(0) }) {
+            return Some("it leads the current session".to_string());
+        }
+        if tree::protected_pids().contains(&pid) {
+            return Some("it is kilar or one of its parent processes".to_string());
+        }
+        if tree::is_kernel_thread(pid) {
+            return Some("it is a kernel thread".to_string());
+        }
+
+        let mut owner = None;
+        self.patterns
+            .iter()
+            .find(|pattern| {
+                pattern.matches(process, || {
+                    owner.get_or_insert_with(|| process_owner(pid)).clone()
+                })
+            })
+            .map(|pattern| format!("it matches --protect {pattern}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, exe: &str, port: u16) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            command: exe.to_string(),
+            executable_path: exe.to_string(),
+            working_directory: "/".to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            address: "0.0.0.0".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_protect_pattern() {
+        let pattern: ProtectPattern = "exe:/usr/sbin/*".parse().unwrap();
+        assert_eq!(pattern.field, ProtectField::Exe);
+        assert_eq!(pattern.pattern, "/usr/sbin/*");
+        assert_eq!(pattern.to_string(), "exe:/usr/sbin/*");
+
+        let pattern: ProtectPattern = "postgres".parse().unwrap();
+        assert_eq!(pattern.field, ProtectField::Name);
+        assert_eq!(pattern.to_string(), "name:postgres");
+
+        assert!("pid:1".parse::<ProtectPattern>().is_err());
+        assert!("port:".parse::<ProtectPattern>().is_err());
+    }
+
+    #[test]
+    fn test_builtin_denylist() {
+        let protection = Protection::default();
+        assert!(protection
+            .reason(&process(1, "systemd", "/sbin/init", 111))
+            .is_some());
+        assert!(protection
+            .reason(&process(99990, "sshd", "/usr/sbin/sshd", 22))
+            .is_some());
+        assert!(protection
+            .reason(&process(99991, "systemd-resolved", "/lib/systemd/x", 53))
+            .is_some());
+        assert!(protection
+            .reason(&process(std::process::id(), "kilar", "/bin/kilar", 0))
+            .is_some());
+        assert!(protection
+            .reason(&process(99992, "node", "/usr/bin/node", 3000))
+            .is_none());
+    }
+
+    #[test]
+    fn test_user_patterns() {
+        let protection = Protection {
+            patterns: vec![
+                "exe:/opt/db/*".parse().unwrap(),
+                "port:5400-5499".parse().unwrap(),
+            ],
+            allow: false,
+        };
+        assert_eq!(
+            protection.reason(&process(99990, "pg", "/opt/db/bin/pg", 6000)),
+            Some("it matches --protect exe:/opt/db/*".to_string())
+        );
+        assert!(protection
+            .reason(&process(99990, "pg", "/usr/bin/pg", 5432))
+            .is_some());
+        assert!(protection
+            .reason(&process(99990, "pg", "/usr/bin/pg", 5500))
+            .is_none());
+    }
+
+    #[test]
+    fn test_user_pattern_matches_owner() {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        if let Ok(metadata) = std::fs::metadata(format!("/proc/{}", child.id())) {
+            let protection = Protection {
+                patterns: vec![format!("user:{}", metadata.uid()).parse().unwrap()],
+                allow: false,
+            };
+            let mut sleep = process(child.id(), "sleep", "/bin/sleep", 3000);
+            assert!(protection.reason(&sleep).is_some());
+            sleep.pid = 99990;
+            assert!(protection.reason(&sleep).is_none());
+        }
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_allow_overrides_protection() {
+        let sshd = process(99990, "sshd", "/usr/sbin/sshd", 22);
+        let err = Protection::default().check(&sshd).unwrap_err();
+        assert!(matches!(err, crate::Error::Protected { pid: 99990, .. }));
+
+        let protection = Protection {
+            allow: true,
+            ..Protection::default()
+        };
+        assert!(protection.check(&sshd).is_ok());
+    }
+
+    #[test]
+    fn test_user_name() {
+        let passwd =
+            "root:x:0:0:root:/root:/bin/bash\nwww-data:x:33:33::/var/www:/usr/sbin/nologin\n";
+        assert_eq!(user_name(passwd, 33), Some("www-data".to_string()));
+        assert_eq!(user_name(passwd, 1000), None);
+    }
+}
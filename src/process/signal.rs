@@ -0,0 +1,243 @@
+use crate::utils::{format_duration, parse_duration};
+use crate::Result;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Signals accepted by name, in the order `kill -l` lists them
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("WINCH", libc::SIGWINCH),
+];
+
+/// How long to wait for a process to exit after the last signal of a ladder
+pub const FINAL_GRACE: Duration = Duration::from_secs(1);
+
+/// Default wait between SIGTERM and SIGKILL (`--timeout`)
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A signal, parsed from a name (`TERM`, `SIGTERM`, `term`) or number (`15`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signal(i32);
+
+impl Signal {
+    /// The null signal, which only checks that the process exists
+    pub const NONE: Signal = Signal(0);
+    pub const TERM: Signal = Signal(libc::SIGTERM);
+    pub const KILL: Signal = Signal(libc::SIGKILL);
+
+    pub fn number(&self) -> i32 {
+        self.0
+    }
+
+    /// Short name without the `SIG` prefix, or the number for unnamed signals
+    pub fn name(&self) -> String {
+        SIGNALS
+            .iter()
+            .find(|(_, number)| *number == self.0)
+            .map_or_else(|| self.0.to_string(), |(name, _)| name.to_string())
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match SIGNALS.iter().find(|(_, number)| *number == self.0) {
+            Some((name, _)) => write!(f, "SIG{name}"),
+            None => write!(f, "signal {}", self.0),
+        }
+    }
+}
+
+impl FromStr for Signal {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            crate::Error::ParseError(format!(
+                "Unknown signal '{s}' (expected a name like TERM or INT, or a number)"
+            ))
+        };
+
+        if let Ok(number) = s.parse::<i32>() {
+            return if (1..=64).contains(&number) {
+                Ok(Signal(number))
+            } else {
+                Err(invalid())
+            };
+        }
+
+        let upper = s.to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        SIGNALS
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, number)| Signal(*number))
+            .ok_or_else(invalid)
+    }
+}
+
+/// One rung of an escalation ladder: send `signal`, then wait up to `wait`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscalationStep {
+    pub signal: Signal,
+    /// How long to poll for exit before the next step; the last step waits
+    /// [`FINAL_GRACE`] when unset
+    pub wait: Option<Duration>,
+}
+
+/// Signals sent in turn until the process exits, e.g. `INT:5s,TERM:10s,KILL`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escalation {
+    pub steps: Vec<EscalationStep>,
+}
+
+impl Escalation {
+    /// Send `signal`, wait up to `timeout`, then SIGKILL
+    pub fn with_timeout(signal: Signal, timeout: Duration) -> Self {
+        let mut steps = vec![EscalationStep {
+            signal,
+            wait: Some(timeout),
+        }];
+        if signal != Signal::KILL {
+            steps.push(EscalationStep {
+                signal: Signal::KILL,
+                wait: None,
+            });
+        }
+        Self { steps }
+    }
+
+    /// Build the ladder from `--signal`, `--timeout` and `--escalation`
+    pub fn from_options(
+        signal: Option<Signal>,
+        timeout: Option<Duration>,
+        escalation: Option<Escalation>,
+    ) -> Self {
+        escalation.unwrap_or_else(|| {
+            Self::with_timeout(
+                signal.unwrap_or(Signal::TERM),
+                timeout.unwrap_or(DEFAULT_TIMEOUT),
+            )
+        })
+    }
+}
+
+impl Default for Escalation {
+    /// SIGTERM, then SIGKILL after [`DEFAULT_TIMEOUT`]
+    fn default() -> Self {
+        Self::with_timeout(Signal::TERM, DEFAULT_TIMEOUT)
+    }
+}
+
+impl fmt::Display for Escalation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| match step.wait {
+                Some(wait) => format!("{}:{}", step.signal.name(), format_duration(wait)),
+                None => step.signal.name(),
+            })
+            .collect();
+        f.write_str(&steps.join(","))
+    }
+}
+
+impl FromStr for Escalation {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let steps = s
+            .split(',')
+            .map(|step| {
+                let (signal, wait) = match step.trim().split_once(':') {
+                    Some((signal, wait)) => (signal, Some(parse_duration(wait)?)),
+                    None => (step.trim(), None),
+                };
+                Ok(EscalationStep {
+                    signal: signal.parse()?,
+                    wait,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Only the last step may omit its wait
+        if steps[..steps.len() - 1]
+            .iter()
+            .any(|step| step.wait.is_none())
+        {
+            return Err(crate::Error::ParseError(format!(
+                "Invalid escalation '{s}': every step but the last needs a wait (e.g. INT:5s,TERM:10s,KILL)"
+            )));
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!("TERM".parse::<Signal>().unwrap(), Signal::TERM);
+        assert_eq!("SIGTERM".parse::<Signal>().unwrap(), Signal::TERM);
+        assert_eq!("int".parse::<Signal>().unwrap().number(), libc::SIGINT);
+        assert_eq!("9".parse::<Signal>().unwrap(), Signal::KILL);
+        assert_eq!("HUP".parse::<Signal>().unwrap().to_string(), "SIGHUP");
+
+        for invalid in ["", "TERMINATE", "0", "65", "-9"] {
+            assert!(
+                invalid.parse::<Signal>().is_err(),
+                "{invalid} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_escalation() {
+        let escalation = "INT:5s,TERM:10s,KILL".parse::<Escalation>().unwrap();
+        assert_eq!(escalation.steps.len(), 3);
+        assert_eq!(escalation.steps[0].signal.number(), libc::SIGINT);
+        assert_eq!(escalation.steps[0].wait, Some(Duration::from_secs(5)));
+        assert_eq!(escalation.steps[1].wait, Some(Duration::from_secs(10)));
+        assert_eq!(escalation.steps[2].signal, Signal::KILL);
+        assert_eq!(escalation.steps[2].wait, None);
+        assert_eq!(escalation.to_string(), "INT:5s,TERM:10s,KILL");
+
+        // 最後の段階以外は待ち時間が必須
+        assert!("INT,KILL".parse::<Escalation>().is_err());
+        assert!("INT:5x,KILL".parse::<Escalation>().is_err());
+        assert!("".parse::<Escalation>().is_err());
+    }
+
+    #[test]
+    fn test_escalation_from_options() {
+        assert_eq!(
+            Escalation::from_options(None, None, None).to_string(),
+            "TERM:5s,KILL"
+        );
+
+        let escalation = Escalation::from_options(
+            Some("INT".parse().unwrap()),
+            Some(Duration::from_secs(30)),
+            None,
+        );
+        assert_eq!(escalation.to_string(), "INT:30s,KILL");
+
+        // SIGKILLを指定した場合はそれ以上エスカレーションしない
+        let escalation = Escalation::from_options(Some(Signal::KILL), None, None);
+        assert_eq!(escalation.steps.len(), 1);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{port::ProcessInfo, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap};
+use std::fmt;
+use tokio::process::Command as TokioCommand;
+
+/// systemd unit that owns a listener
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemdUnit {
+    /// Unit name, e.g. `nginx.service` or `sshd.socket`
+    pub name: String,
+    /// Whether the unit belongs to a user manager (`systemctl --user`)
+    pub user: bool,
+    /// Service a socket unit activates, stopped together with the socket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activates: Option<String>,
+}
+
+impl SystemdUnit {
+    pub fn new(name: &str, user: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            user,
+            activates: None,
+        }
+    }
+
+    /// Whether this is a socket unit, i.e. the port is socket-activated
+    pub fn is_socket(&self) -> bool {
+        self.name.ends_with(".socket")
+    }
+}
+
+impl fmt::Display for SystemdUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if self.user {
+            f.write_str(" (user)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Stops systemd units in place of killing their processes.
+///
+/// Killing a service's main process only makes systemd restart it; stopping
+/// the unit frees the port for good.
+#[async_trait]
+pub trait UnitStopper: Send + Sync {
+    async fn stop(&self, unit: &SystemdUnit) -> Result<()>;
+}
+
+/// Stops units with `systemctl stop`
+pub struct SystemctlStopper;
+
+#[async_trait]
+impl UnitStopper for SystemctlStopper {
+    async fn stop(&self, unit: &SystemdUnit) -> Result<()> {
+        let mut command = TokioCommand::new("systemctl");
+        if unit.user {
+            command.arg("--user");
+        }
+        command.arg("stop").arg(&unit.name);
+        // The activated service may still hold the socket it inherited
+        if let Some(service) = &unit.activates {
+            command.arg(service);
+        }
+
+        let output = command
+            .output()
+            .await
+            .map_err(|e| crate::Error::CommandFailed(format!("systemctl command failed: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stderr.contains("Access denied") || stderr.contains("authentication required") {
+                return Err(crate::Error::PermissionDenied(format!(
+                    "Stopping {} requires root; run with sudo",
+                    unit.name
+                )));
+            }
+            return Err(crate::Error::CommandFailed(format!(
+                "Failed to stop {}: {}",
+                unit.name,
+                stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Attach the owning systemd unit to every process that runs as part of a
+/// service, and to sockets that systemd itself holds for socket activation.
+///
+/// Best effort: rows stay untouched when `/proc` or `systemctl` is unavailable.
+pub async fn attach_units(processes: &mut [ProcessInfo]) {
+    let mut cache: HashMap<u32, Option<SystemdUnit>> = HashMap::new();
+    let mut socket_units: HashMap<bool, Vec<(String, SystemdUnit)>> = HashMap::new();
+
+    for process in processes.iter_mut().filter(|p| p.pid != 0) {
+        if is_systemd_manager(process) {
+            let user = process.pid != 1;
+            let listing = match socket_units.entry(user) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(list_socket_units(user).await),
+            };
+            process.unit = listing
+                .iter()
+                .find(|(listen, _)| listen_matches(listen, process))
+                .map(|(_, unit)| unit.clone());
+        } else {
+            process.unit = cache
+                .entry(process.pid)
+                .or_insert_with(|| {
+                    std::fs::read_to_string(format!("/proc/{}/cgroup", process.pid))
+                        .ok()
+                        .and_then(|cgroup| parse_unit(&cgroup))
+                })
+                .clone();
+        }
+    }
+}
+
+/// Whether `process` is PID 1 or a user's `systemd --user` manager
+pub(crate) fn is_systemd_manager(process: &ProcessInfo) -> bool {
+    process.pid == 1 || process.name == "systemd"
+}
+
+/// Find the service a process belongs to in the contents of `/proc/<pid>/cgroup`.
+///
+/// Only `.service` units are reported: scopes (login sessions, containers)
+/// are not restarted by systemd, and `user@UID.service` is the user's whole
+/// service manager rather than the listener.
+pub(crate) fn parse_unit(content: &str) -> Option<SystemdUnit> {
+    for line in content.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // The unified hierarchy, or the named systemd hierarchy on cgroup v1
+        if !controllers.is_empty() && controllers != "name=systemd" {
+            continue;
+        }
+
+        let segments: Vec<&str> = path.split('/').collect();
+        let user = segments
+            .iter()
+            .any(|s| s.starts_with("user@") && s.ends_with(".service"));
+        if let Some(unit) = segments
+            .iter()
+            .rev()
+            .find(|s| s.ends_with(".service") && !s.starts_with("user@"))
+        {
+            return Some(SystemdUnit::new(unit, user));
+        }
+    }
+    None
+}
+
+/// Socket units and their listen addresses from `systemctl list-sockets`
+async fn list_socket_units(user: bool) -> Vec<(String, SystemdUnit)> {
+    let mut command = TokioCommand::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    command.args(["list-sockets", "--all", "--no-legend", "--full", "--plain"]);
+
+    match command.output().await {
+        Ok(output) if output.status.success() => {
+            parse_list_sockets(&String::from_utf8_lossy(&output.stdout), user)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Parse `systemctl list-sockets --no-legend` output (`LISTEN UNIT ACTIVATES`)
+pub(crate) fn parse_list_sockets(output: &str, user: bool) -> Vec<(String, SystemdUnit)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (listen, name) = (parts.first()?, parts.get(1)?);
+            if !name.ends_with(".socket") {
+                return None;
+            }
+            let mut unit = SystemdUnit::new(name, user);
+            unit.activates = parts
+                .get(2)
+                .filter(|s| s.ends_with(".service"))
+                .map(|s| s.to_string());
+            Some((listen.to_string(), unit))
+        })
+        .collect()
+}
+
+/// Whether a `list-sockets` listen address (`[::]:22`, `/run/app.sock`) is `process`'s socket
+pub(crate) fn listen_matches(listen: &str, process: &ProcessInfo) -> bool {
+    if process.protocol == "unix" {
+        return listen == process.address;
+    }
+    listen
+        .rsplit_once(':')
+        .map_or(listen, |(_, port)| port)
+        .parse::<u16>()
+        .is_ok_and(|port| port == process.port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, name: &str, port: u16) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            command: name.to_string(),
+            executable_path: format!("/usr/sbin/{name}"),
+            working_directory: "/".to_string(),
+            port,
+            protocol: "tcp".to_string(),
+            address: "*".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_unit_system_service() {
+        let unit = parse_unit("0::/system.slice/nginx.service\n").unwrap();
+        assert_eq!(unit.name, "nginx.service");
+        assert!(!unit.user);
+
+        // cgroup v1の名前付き階層
+        let content =
+            "12:pids:/system.slice/nginx.service\n1:name=systemd:/system.slice/nginx.service\n";
+        assert_eq!(parse_unit(content).unwrap().name, "nginx.service");
+    }
+
+    #[test]
+    fn test_parse_unit_user_service() {
+        let content =
+            "0::/user.slice/user-1000.slice/user@1000.service/app.slice/devserver.service\n";
+        let unit = parse_unit(content).unwrap();
+        assert_eq!(unit.name, "devserver.service");
+        assert!(unit.user);
+        assert_eq!(unit.to_string(), "devserver.service (user)");
+    }
+
+    #[test]
+    fn test_parse_unit_ignores_scopes() {
+        // ログインセッションやコンテナのscopeは停止対象にしない
+        assert_eq!(
+            parse_unit("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+        assert_eq!(
+            parse_unit("0::/user.slice/user-1000.slice/user@1000.service/init.scope\n"),
+            None
+        );
+        assert_eq!(parse_unit("0::/system.slice/docker-abc.scope\n"), None);
+        assert_eq!(parse_unit("0::/\n"), None);
+    }
+
+    #[test]
+    fn test_parse_list_sockets() {
+        let output = "\
+/run/dbus/system_bus_socket dbus.socket dbus.service
+[::]:22 sshd.socket sshd.service
+0.0.0.0:8080 myapp.socket
+";
+        let sockets = parse_list_sockets(output, false);
+        assert_eq!(sockets.len(), 3);
+        assert_eq!(sockets[1].0, "[::]:22");
+        assert_eq!(sockets[1].1.name, "sshd.socket");
+        assert_eq!(sockets[1].1.activates.as_deref(), Some("sshd.service"));
+        assert_eq!(sockets[2].1.activates, None);
+        assert!(sockets[2].1.is_socket());
+    }
+
+    #[test]
+    fn test_listen_matches() {
+        let init = process(1, "systemd", 22);
+        assert!(is_systemd_manager(&init));
+        assert!(listen_matches("[::]:22", &init));
+        assert!(listen_matches("0.0.0.0:22", &init));
+        assert!(!listen_matches("0.0.0.0:2222", &init));
+        assert!(!listen_matches("/run/app.sock", &init));
+
+        let mut unix = process(1, "systemd", 0);
+        unix.protocol = "unix".to_string();
+        unix.address = "/run/app.sock".to_string();
+        assert!(listen_matches("/run/app.sock", &unix));
+    }
+}
@@ -0,0 +1,348 @@
+use crate::port::ProcessInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Shells that are climbed through only when they run a command (`sh -c`)
+const SHELLS: &[&str] = &[
+    "sh", "bash", "zsh", "dash", "fish", "ksh", "tcsh", "csh", "ash",
+];
+
+/// Fields of `/proc/<pid>/stat` needed to walk the process tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcStat {
+    pub pid: u32,
+    pub name: String,
+    pub ppid: u32,
+    pub pgrp: u32,
+    pub session: u32,
+    /// Clock ticks after boot at which the process started
+    pub start_time: Option<u64>,
+}
+
+/// One ancestor of a listening process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ancestor {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Parse the contents of `/proc/<pid>/stat`.
+///
+/// The command name is wrapped in parentheses and may itself contain spaces
+/// and parentheses, so the remaining fields are split after the last `)`.
+pub(crate) fn parse_stat(content: &str) -> Option<ProcStat> {
+    let open = content.find('(')?;
+    let close = content.rfind(')')?;
+    let pid = content[..open].trim().parse().ok()?;
+    let name = content.get(open + 1..close)?.to_string();
+
+    // state ppid pgrp session ... starttime (field 22 of the whole line)
+    let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+    Some(ProcStat {
+        pid,
+        name,
+        ppid: fields.get(1)?.parse().ok()?,
+        pgrp: fields.get(2)?.parse().ok()?,
+        session: fields.get(3)?.parse().ok()?,
+        start_time: fields.get(19).and_then(|s| s.parse().ok()),
+    })
+}
+
+pub fn read_stat(pid: u32) -> Option<ProcStat> {
+    parse_stat(&std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)
+}
+
+/// When `pid` started, to tell it apart from a later process that reuses the PID
+pub fn start_time(pid: u32) -> Option<u64> {
+    read_stat(pid)?.start_time
+}
+
+/// `PF_KTHREAD` in the flags field of `/proc/<pid>/stat`
+const PF_KTHREAD: u64 = 0x0020_0000;
+
+/// Whether `pid` is a kernel thread (Linux only)
+pub fn is_kernel_thread(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()
+        .and_then(|stat| {
+            let (_, rest) = stat.rsplit_once(')')?;
+            rest.split_whitespace().nth(6)?.parse::<u64>().ok()
+        })
+        .is_some_and(|flags| flags & PF_KTHREAD != 0)
+}
+
+/// Record every process's start time so a later kill can verify its identity
+pub fn attach_start_times(processes: &mut [ProcessInfo]) {
+    for process in processes.iter_mut().filter(|p| p.pid != 0) {
+        process.start_time = start_time(process.pid);
+    }
+}
+
+/// Every process visible in `/proc`
+pub fn all_stats() -> Vec<ProcStat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(read_stat)
+        .collect()
+}
+
+/// Parents of `pid`, nearest first, up to but not including PID 1
+pub fn ancestors(pid: u32) -> Vec<ProcStat> {
+    let mut chain = Vec::new();
+    let mut current = read_stat(pid);
+
+    while let Some(stat) = current {
+        if stat.ppid <= 1 || chain.len() > 64 {
+            break;
+        }
+        current = read_stat(stat.ppid);
+        if let Some(parent) = &current {
+            chain.push(parent.clone());
+        }
+    }
+    chain
+}
+
+/// Attach the ancestor chain to every process (Linux only; a no-op elsewhere)
+pub fn attach_ancestors(processes: &mut [ProcessInfo]) {
+    let mut cache: HashMap<u32, Option<Vec<Ancestor>>> = HashMap::new();
+
+    for process in processes.iter_mut().filter(|p| p.pid != 0) {
+        process.ancestors = cache
+            .entry(process.pid)
+            .or_insert_with(|| {
+                read_stat(process.pid)?;
+                Some(
+                    ancestors(process.pid)
+                        .into_iter()
+                        .map(|stat| Ancestor {
+                            pid: stat.pid,
+                            name: stat.name,
+                        })
+                        .collect(),
+                )
+            })
+            .clone();
+    }
+}
+
+/// Whether `stat` is an interactive shell, as opposed to `sh -c "..."`
+fn is_interactive_shell(stat: &ProcStat) -> bool {
+    if !SHELLS.contains(&stat.name.trim_start_matches('-')) {
+        return false;
+    }
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", stat.pid)).unwrap_or_default();
+    !cmdline.split(|b| *b == 0).any(|arg| arg == b"-c")
+}
+
+/// The top-most ancestor of `pid` that belongs to the same job.
+///
+/// Climbs parents while they stay in the same session, skipping `sh -c`
+/// wrappers, and stops below an interactive shell, a service manager or
+/// PID 1. For `npm run dev` → `sh -c vite` → `node` this is `npm`.
+pub fn tree_root(pid: u32) -> u32 {
+    let Some(start) = read_stat(pid) else {
+        return pid;
+    };
+    find_tree_root(&start, &ancestors(pid), is_interactive_shell)
+}
+
+pub(crate) fn find_tree_root(
+    start: &ProcStat,
+    ancestors: &[ProcStat],
+    is_shell: impl Fn(&ProcStat) -> bool,
+) -> u32 {
+    let mut root = start.pid;
+    for parent in ancestors {
+        if parent.pid <= 1
+            || parent.session != start.session
+            || parent.name == "systemd"
+            || is_shell(parent)
+        {
+            break;
+        }
+        root = parent.pid;
+    }
+    root
+}
+
+/// `root` and all of its descendants, children before their parents
+pub fn descendants(root: u32, stats: &[ProcStat]) -> Vec<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for stat in stats {
+        children.entry(stat.ppid).or_default().push(stat.pid);
+    }
+
+    let mut ordered = Vec::new();
+    let mut stack = vec![(root, false)];
+    while let Some((pid, visited)) = stack.pop() {
+        if visited {
+            ordered.push(pid);
+            continue;
+        }
+        stack.push((pid, true));
+        for child in children.get(&pid).into_iter().flatten() {
+            stack.push((*child, false));
+        }
+    }
+    ordered
+}
+
+/// The processes in `members`, deepest in the tree first
+pub fn children_first(members: &[u32], stats: &[ProcStat]) -> Vec<u32> {
+    let parents: HashMap<u32, u32> = stats.iter().map(|s| (s.pid, s.ppid)).collect();
+    let depth = |mut pid: u32| {
+        let mut depth = 0;
+        while let Some(&parent) = parents.get(&pid) {
+            if parent == 0 || depth > 64 {
+                break;
+            }
+            pid = parent;
+            depth += 1;
+        }
+        depth
+    };
+
+    let mut ordered = members.to_vec();
+    ordered.sort_by_key(|pid| std::cmp::Reverse(depth(*pid)));
+    ordered
+}
+
+/// kilar itself and its ancestors, which a group or session kill must spare
+pub fn protected_pids() -> HashSet<u32> {
+    let pid = std::process::id();
+    let mut protected: HashSet<u32> = ancestors(pid).into_iter().map(|s| s.pid).collect();
+    protected.insert(pid);
+    protected.insert(1);
+    protected
+}
+
+/// Format a chain such as `npm (200) → sh (201) → node (202)`
+pub fn format_chain<'a>(chain: impl IntoIterator<Item = (&'a str, u32)>) -> String {
+    chain
+        .into_iter()
+        .map(|(name, pid)| format!("{name} ({pid})"))
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stat(pid: u32, name: &str, ppid: u32, session: u32) -> ProcStat {
+        ProcStat {
+            pid,
+            name: name.to_string(),
+            ppid,
+            pgrp: pid,
+            session,
+            start_time: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_stat() {
+        let stat = parse_stat(
+            "4242 (node) S 4200 4200 100 34816 4200 4194304 9017 0 12 0 85 31 0 0 20 0 11 0 1830417 1234",
+        )
+        .unwrap();
+        assert_eq!(stat.pid, 4242);
+        assert_eq!(stat.name, "node");
+        assert_eq!(stat.ppid, 4200);
+        assert_eq!(stat.pgrp, 4200);
+        assert_eq!(stat.session, 100);
+        assert_eq!(stat.start_time, Some(1830417));
+
+        // コマンド名に空白や括弧が含まれるケース
+        let stat = parse_stat("77 (tmux: server (1)) S 1 77 77 0 -1").unwrap();
+        assert_eq!(stat.name, "tmux: server (1)");
+        assert_eq!(stat.ppid, 1);
+        assert_eq!(stat.start_time, None);
+
+        assert_eq!(parse_stat("garbage"), None);
+    }
+
+    #[test]
+    fn test_find_tree_root_npm_chain() {
+        // bash → npm → sh -c → node → esbuild
+        let esbuild = stat(205, "esbuild", 203, 100);
+        let ancestors = [
+            stat(203, "node", 202, 100),
+            stat(202, "sh", 201, 100),
+            stat(201, "npm run dev", 100, 100),
+            stat(100, "bash", 99, 100),
+            stat(99, "sshd", 1, 99),
+        ];
+        let root = find_tree_root(&esbuild, &ancestors, |s| s.name == "bash");
+        assert_eq!(root, 201);
+    }
+
+    #[test]
+    fn test_find_tree_root_stops_at_session_and_init() {
+        // デーモン化したプロセスはそれ自身がルート
+        let daemon = stat(500, "redis-server", 1, 500);
+        assert_eq!(find_tree_root(&daemon, &[], |_| false), 500);
+
+        // nginxのワーカーはマスターまで遡る
+        let worker = stat(601, "nginx", 600, 600);
+        let ancestors = [stat(600, "nginx", 1, 600)];
+        assert_eq!(find_tree_root(&worker, &ancestors, |_| false), 600);
+
+        let child = stat(701, "node", 700, 700);
+        let ancestors = [stat(700, "tmux: server", 1, 650)];
+        assert_eq!(find_tree_root(&child, &ancestors, |_| false), 701);
+    }
+
+    #[test]
+    fn test_descendants_children_first() {
+        let stats = [
+            stat(201, "npm", 100, 100),
+            stat(202, "sh", 201, 100),
+            stat(203, "node", 202, 100),
+            stat(205, "esbuild", 203, 100),
+            stat(206, "watcher", 201, 100),
+            stat(300, "other", 100, 100),
+        ];
+        let order = descendants(201, &stats);
+
+        assert_eq!(order.len(), 5);
+        assert_eq!(*order.last().unwrap(), 201);
+        let position = |pid| order.iter().position(|p| *p == pid).unwrap();
+        assert!(position(205) < position(203));
+        assert!(position(203) < position(202));
+        assert!(!order.contains(&300));
+    }
+
+    #[test]
+    fn test_children_first() {
+        let stats = [
+            stat(201, "npm", 100, 100),
+            stat(202, "sh", 201, 100),
+            stat(203, "node", 202, 100),
+        ];
+        assert_eq!(
+            children_first(&[201, 203, 202], &stats),
+            vec![203, 202, 201]
+        );
+    }
+
+    #[test]
+    fn test_own_ancestors_are_protected() {
+        let protected = protected_pids();
+        assert!(protected.contains(&std::process::id()));
+        assert!(protected.contains(&1));
+    }
+
+    #[test]
+    fn test_format_chain() {
+        assert_eq!(
+            format_chain([("npm", 201), ("node", 203)]),
+            "npm (201) → node (203)"
+        );
+    }
+}
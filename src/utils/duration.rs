@@ -0,0 +1,74 @@
+use crate::Result;
+use std::time::Duration;
+
+/// Parse a human-friendly duration: `500ms`, `5s`, `2m`, `1h`, or bare seconds (`10`)
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let invalid = || {
+        crate::Error::ParseError(format!(
+            "Invalid duration '{value}' (expected e.g. 500ms, 5s, 2m or 1h)"
+        ))
+    };
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+/// Format a duration the way [`parse_duration`] reads it back
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if !millis.is_multiple_of(1000) {
+        format!("{millis}ms")
+    } else if !millis.is_multiple_of(60_000) || millis == 0 {
+        format!("{}s", millis / 1000)
+    } else {
+        format!("{}m", millis / 60_000)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("5s").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        for input in ["", "s", "5x", "-1s", "abc", "5 s"] {
+            let result = parse_duration(input);
+            assert!(result.is_err(), "{input} should be rejected");
+        }
+        assert!(parse_duration("5x")
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid duration"));
+    }
+
+    #[test]
+    fn test_format_duration_round_trip() {
+        for input in ["500ms", "5s", "2m", "0s"] {
+            let duration = parse_duration(input).unwrap();
+            assert_eq!(format_duration(duration), input);
+        }
+    }
+}
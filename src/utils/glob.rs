@@ -0,0 +1,53 @@
+/// Whether `text` matches the shell-style `pattern`, where `*` matches any
+/// run of characters (including `/`) and `?` matches one character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 直前の`*`の位置と、そこから試しているテキストの位置
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    p = star + 1;
+                    t = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("systemd-*", "systemd-resolved"));
+        assert!(glob_match("systemd-*", "systemd-"));
+        assert!(!glob_match("systemd-*", "systemd"));
+        assert!(glob_match("/usr/sbin/*", "/usr/sbin/nginx"));
+        assert!(glob_match(
+            "*postgres*",
+            "/usr/lib/postgresql/16/bin/postgres"
+        ));
+        assert!(glob_match("node?", "node1"));
+        assert!(!glob_match("node?", "node"));
+        assert!(glob_match("sshd", "sshd"));
+        assert!(!glob_match("sshd", "sshd-session"));
+        assert!(glob_match("*", ""));
+    }
+}
@@ -1,3 +1,7 @@
+pub mod duration;
+pub mod glob;
 pub mod validation;
 
+pub use duration::*;
+pub use glob::*;
 pub use validation::*;
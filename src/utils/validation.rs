@@ -18,6 +18,18 @@ pub fn validate_protocol(protocol: &str) -> Result<()> {
     }
 }
 
+/// Like [`validate_protocol`], but also accept `unix` for `list --protocol`
+pub fn validate_list_protocol(protocol: &str) -> Result<()> {
+    match protocol.to_lowercase().as_str() {
+        "unix" => Ok(()),
+        _ => validate_protocol(protocol).map_err(|_| {
+            crate::Error::InvalidPort(format!(
+                "Invalid protocol '{protocol}'. Must be tcp, udp, unix, or all"
+            ))
+        }),
+    }
+}
+
 pub fn validate_sort_option(sort: &str) -> Result<()> {
     match sort.to_lowercase().as_str() {
         "port" | "pid" | "name" => Ok(()),
@@ -50,12 +62,22 @@ mod tests {
         assert!(validate_protocol("UDP").is_ok());
         assert!(validate_protocol("all").is_ok());
         assert!(validate_protocol("ALL").is_ok());
+        assert!(validate_protocol("unix").is_err());
 
         let result = validate_protocol("http");
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid protocol"));
     }
 
+    #[test]
+    fn test_validate_list_protocol() {
+        assert!(validate_list_protocol("tcp").is_ok());
+        assert!(validate_list_protocol("all").is_ok());
+        assert!(validate_list_protocol("unix").is_ok());
+        assert!(validate_list_protocol("UNIX").is_ok());
+        assert!(validate_list_protocol("http").is_err());
+    }
+
     #[test]
     fn test_validate_sort_option() {
         assert!(validate_sort_option("port").is_ok());